@@ -0,0 +1,241 @@
+use alloc::{vec, vec::Vec};
+
+use super::{strides, unwrap_index, Maze, Progress, Walls};
+
+/// How a maze picks which walls to open. Every algorithm produces a perfect maze, with
+/// exactly one path between any two cells, but each leaves a different texture.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GenAlgorithm {
+    /// Randomized Kruskal's, with many short dead ends.
+    #[default]
+    Kruskal,
+    /// A randomized depth first search, with long winding corridors.
+    RecursiveBacktracker,
+    /// Loop-erased random walks, an unbiased sample of every possible maze.
+    Wilson,
+}
+
+impl<const DIMS: usize> Maze<DIMS> {
+    /// Generate a maze with a recursive backtracker, panicking if `try_new` would fail.
+    pub fn new_backtracker(lengths: &[u16; DIMS], rng: &mut impl rand::RngCore) -> Maze<DIMS> {
+        Self::generate_or_panic(lengths, GenAlgorithm::RecursiveBacktracker, rng)
+    }
+
+    /// Generate a maze with Wilson's algorithm, panicking if `try_new` would fail.
+    pub fn new_wilson(lengths: &[u16; DIMS], rng: &mut impl rand::RngCore) -> Maze<DIMS> {
+        Self::generate_or_panic(lengths, GenAlgorithm::Wilson, rng)
+    }
+
+    fn generate_or_panic(
+        lengths: &[u16; DIMS],
+        algorithm: GenAlgorithm,
+        rng: &mut impl rand::RngCore,
+    ) -> Maze<DIMS> {
+        let progress = Progress::default();
+        match Self::try_generate(lengths, algorithm, super::DEFAULT_MAX_CELLS, rng, &progress) {
+            Ok(maze) => maze,
+            Err(error) => panic!("could not generate maze: {}", error),
+        }
+    }
+}
+
+/// Flattened indices of the cells next to `index`, written into `out`.
+fn neighbor_indices<const DIMS: usize>(
+    lengths: &[u16; DIMS],
+    strides: &[usize; DIMS],
+    index: usize,
+    out: &mut Vec<usize>,
+) {
+    out.clear();
+    let cell = unwrap_index(lengths, index).unwrap();
+    for dim in 0..DIMS {
+        if cell[dim] > 0 {
+            out.push(index - strides[dim]);
+        }
+        if cell[dim] as usize + 1 < lengths[dim] as usize {
+            out.push(index + strides[dim]);
+        }
+    }
+}
+
+fn pick<T: Copy>(items: &[T], rng: &mut impl rand::RngCore) -> T {
+    items[rng.next_u32() as usize % items.len()]
+}
+
+/// Opens the wall between two neighboring cells, which is stored on the lower one.
+fn open<const DIMS: usize>(
+    walls: &mut Walls<DIMS>,
+    lengths: &[u16; DIMS],
+    strides: &[usize; DIMS],
+    a: usize,
+    b: usize,
+) {
+    let (low, high) = if a < b { (a, b) } else { (b, a) };
+    // An axis of length 1 shares its stride with the next one, but has no neighbors along it.
+    let dim = (0..DIMS)
+        .find(|dim| lengths[*dim] > 1 && strides[*dim] == high - low)
+        .expect("cells are neighbors");
+    walls.open(low, dim);
+}
+
+/// Randomized depth first search: walks to a random unvisited neighbor, backing up
+/// whenever it gets stuck, until every cell has been visited.
+pub(crate) fn backtracker<const DIMS: usize>(
+    lengths: &[u16; DIMS],
+    cell_count: usize,
+    rng: &mut impl rand::RngCore,
+    progress: &Progress,
+) -> Walls<DIMS> {
+    let strides = strides(lengths);
+    progress.start(cell_count);
+    let mut walls = Walls::closed(cell_count);
+    let mut visited = vec![false; cell_count];
+    let mut neighbors = Vec::with_capacity(DIMS * 2);
+    let mut unvisited = Vec::with_capacity(DIMS * 2);
+
+    visited[0] = true;
+    let mut steps = 1;
+    let mut stack = vec![0];
+    while let Some(&index) = stack.last() {
+        neighbor_indices(lengths, &strides, index, &mut neighbors);
+        unvisited.clear();
+        unvisited.extend(neighbors.iter().copied().filter(|n| !visited[*n]));
+        if unvisited.is_empty() {
+            stack.pop();
+            continue;
+        }
+        let next = pick(&unvisited, rng);
+        open(&mut walls, lengths, &strides, index, next);
+        visited[next] = true;
+        stack.push(next);
+        steps += 1;
+        progress.report(steps);
+    }
+    walls
+}
+
+/// Wilson's algorithm: from each cell outside the maze, random walks until it hits the maze,
+/// then carves the walk with its loops erased.
+pub(crate) fn wilson<const DIMS: usize>(
+    lengths: &[u16; DIMS],
+    cell_count: usize,
+    rng: &mut impl rand::RngCore,
+    progress: &Progress,
+) -> Walls<DIMS> {
+    let strides = strides(lengths);
+    progress.start(cell_count);
+    let mut walls = Walls::closed(cell_count);
+    let mut in_maze = vec![false; cell_count];
+    // The step last taken out of each cell on the current walk, which erases loops for free.
+    let mut next_step = vec![0; cell_count];
+    let mut neighbors = Vec::with_capacity(DIMS * 2);
+
+    in_maze[0] = true;
+    let mut steps = 1;
+    for start in 1..cell_count {
+        let mut index = start;
+        while !in_maze[index] {
+            neighbor_indices(lengths, &strides, index, &mut neighbors);
+            next_step[index] = pick(&neighbors, rng);
+            index = next_step[index];
+        }
+
+        let mut index = start;
+        while !in_maze[index] {
+            in_maze[index] = true;
+            open(&mut walls, lengths, &strides, index, next_step[index]);
+            index = next_step[index];
+            steps += 1;
+            progress.report(steps);
+        }
+    }
+    walls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hashbrown::HashSet;
+    use rand::prelude::*;
+
+    const ALGORITHMS: [GenAlgorithm; 3] = [
+        GenAlgorithm::Kruskal,
+        GenAlgorithm::RecursiveBacktracker,
+        GenAlgorithm::Wilson,
+    ];
+
+    fn generate<const DIMS: usize>(lengths: [u16; DIMS], algorithm: GenAlgorithm) -> Maze<DIMS> {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        Maze::try_generate(
+            &lengths,
+            algorithm,
+            usize::MAX,
+            &mut rng,
+            &Progress::default(),
+        )
+        .unwrap()
+    }
+
+    fn assert_perfect<const DIMS: usize>(maze: &Maze<DIMS>) {
+        let cell_count = maze
+            .lengths()
+            .iter()
+            .map(|l| *l as usize)
+            .product::<usize>();
+        assert_eq!(maze.open_wall_count(), cell_count - 1);
+        let origin = [0; DIMS];
+        for index in 0..cell_count {
+            let cell = unwrap_index(maze.lengths(), index).unwrap();
+            assert!(maze.distance(&origin, &cell).is_some(), "{:?}", cell);
+        }
+    }
+
+    #[test]
+    fn algorithms_generate_perfect_mazes() {
+        for algorithm in ALGORITHMS {
+            assert_perfect(&generate([5, 4], algorithm));
+            assert_perfect(&generate([4, 3, 2], algorithm));
+            assert_perfect(&generate([2, 3, 1, 2, 2, 3], algorithm));
+            assert_perfect(&generate([1, 1], algorithm));
+        }
+    }
+
+    fn walks<const DIMS: usize>(maze: &Maze<DIMS>) -> HashSet<([u16; DIMS], [u16; DIMS])> {
+        maze.iter_walks().collect()
+    }
+
+    #[test]
+    fn algorithms_are_deterministic() {
+        for algorithm in ALGORITHMS {
+            assert_eq!(
+                walks(&generate([6, 5, 4], algorithm)),
+                walks(&generate([6, 5, 4], algorithm))
+            );
+        }
+    }
+
+    #[test]
+    fn constructors_match_try_generate() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        assert_eq!(
+            walks(&Maze::new_backtracker(&[6, 5, 4], &mut rng)),
+            walks(&generate([6, 5, 4], GenAlgorithm::RecursiveBacktracker))
+        );
+        let mut rng = StdRng::seed_from_u64(684153987);
+        assert_eq!(
+            walks(&Maze::new_wilson(&[6, 5, 4], &mut rng)),
+            walks(&generate([6, 5, 4], GenAlgorithm::Wilson))
+        );
+    }
+
+    #[test]
+    fn algorithms_report_completion() {
+        for algorithm in ALGORITHMS {
+            let progress = Progress::default();
+            let mut rng = StdRng::seed_from_u64(684153987);
+            Maze::try_generate(&[20, 20, 20], algorithm, usize::MAX, &mut rng, &progress).unwrap();
+            assert_eq!(progress.fraction(), 1.0);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use alloc::string::String;
+use core::fmt;
+
+use super::Maze;
+
+/// Why a 2D slice of a maze could not be drawn.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SliceError {
+    /// Both displayed axes are the same dimension.
+    SameAxis(usize),
+    /// The maze has no dimension at this index.
+    AxisOutOfRange(usize),
+    /// The fixed coordinates are outside the maze.
+    OutOfBounds,
+}
+
+impl fmt::Display for SliceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SliceError::SameAxis(axis) => write!(f, "both axes are dimension {}", axis),
+            SliceError::AxisOutOfRange(axis) => write!(f, "there is no dimension {}", axis),
+            SliceError::OutOfBounds => write!(f, "the fixed coordinates are outside the maze"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SliceError {}
+
+impl<const DIMS: usize> Maze<DIMS> {
+    /// Draws the slice through `fixed` spanned by `axis_a` across and `axis_b` down, using
+    /// `+`, `-` and `|` for walls and gaps for open ones. The coordinates of `fixed` along
+    /// the two drawn axes are ignored, but must still be inside the maze.
+    pub fn render_slice_ascii(
+        &self,
+        axis_a: usize,
+        axis_b: usize,
+        fixed: &[u16; DIMS],
+    ) -> Result<String, SliceError> {
+        if let Some(axis) = [axis_a, axis_b].into_iter().find(|axis| *axis >= DIMS) {
+            return Err(SliceError::AxisOutOfRange(axis));
+        }
+        if axis_a == axis_b {
+            return Err(SliceError::SameAxis(axis_a));
+        }
+        if !self.in_bounds(fixed) {
+            return Err(SliceError::OutOfBounds);
+        }
+
+        let (width, height) = (self.lengths[axis_a], self.lengths[axis_b]);
+        let cell = |a: u16, b: u16| {
+            let mut cell = *fixed;
+            cell[axis_a] = a;
+            cell[axis_b] = b;
+            cell
+        };
+        // Walls on the far edges are always closed, as `can_move` is `None` past them.
+        let open = |a: u16, b: u16, axis: usize| self.can_move(&cell(a, b), axis) == Some(true);
+
+        let mut out = String::new();
+        let border = |out: &mut String| {
+            out.push('+');
+            for _ in 0..width {
+                out.push_str("-+");
+            }
+            out.push('\n');
+        };
+        border(&mut out);
+        for b in 0..height {
+            out.push('|');
+            for a in 0..width {
+                out.push(' ');
+                out.push(if open(a, b, axis_a) { ' ' } else { '|' });
+            }
+            out.push('\n');
+            if b + 1 == height {
+                break;
+            }
+            out.push('+');
+            for a in 0..width {
+                out.push(if open(a, b, axis_b) { ' ' } else { '-' });
+                out.push('+');
+            }
+            out.push('\n');
+        }
+        border(&mut out);
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn render_corridor() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::new(&[5, 1, 1], &mut rng);
+        assert_eq!(
+            maze.render_slice_ascii(0, 1, &[0, 0, 0]).unwrap(),
+            "+-+-+-+-+-+\n\
+             |         |\n\
+             +-+-+-+-+-+\n"
+        );
+        assert_eq!(
+            maze.render_slice_ascii(1, 0, &[0, 0, 0]).unwrap(),
+            "+-+\n| |\n+ +\n| |\n+ +\n| |\n+ +\n| |\n+ +\n| |\n+-+\n"
+        );
+    }
+
+    #[test]
+    fn render_square() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::new(&[4, 4], &mut rng);
+        assert_eq!(
+            maze.render_slice_ascii(0, 1, &[0, 0]).unwrap(),
+            "+-+-+-+-+\n\
+             | |     |\n\
+             + +-+ +-+\n\
+             |       |\n\
+             + +-+ +-+\n\
+             | | |   |\n\
+             + + +-+ +\n\
+             |   |   |\n\
+             +-+-+-+-+\n"
+        );
+    }
+
+    #[test]
+    fn render_rejects_bad_slices() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::new(&[4, 3, 2], &mut rng);
+        assert_eq!(
+            maze.render_slice_ascii(1, 1, &[0, 0, 0]),
+            Err(SliceError::SameAxis(1))
+        );
+        assert_eq!(
+            maze.render_slice_ascii(0, 3, &[0, 0, 0]),
+            Err(SliceError::AxisOutOfRange(3))
+        );
+        assert_eq!(
+            maze.render_slice_ascii(0, 1, &[0, 0, 2]),
+            Err(SliceError::OutOfBounds)
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use alloc::vec::Vec;
+
+use rand::seq::SliceRandom;
+
+use super::{unwrap_index, Maze};
+
+impl<const DIMS: usize> Maze<DIMS> {
+    /// Every cell with exactly one open wall, in flattened index order.
+    pub fn dead_ends(&self) -> Vec<[u16; DIMS]> {
+        let cell_count = self.lengths.iter().map(|l| *l as usize).product();
+        (0..cell_count)
+            .map(|index| unwrap_index(&self.lengths, index).unwrap())
+            .filter(|cell| self.neighbors(cell).count() == 1)
+            .collect()
+    }
+
+    /// Opens one more wall out of a random `fraction` of the dead ends, clamped to 0 to 1,
+    /// turning them into loops. Walls toward another dead end are preferred, as that removes
+    /// both at once. A dead end with no closed wall left, like the end of an axis with a
+    /// length of 1, stays as it is.
+    pub fn braid(&mut self, fraction: f32, rng: &mut impl rand::RngCore) {
+        let mut dead_ends = self.dead_ends();
+        dead_ends.shuffle(rng);
+        // Rounded by hand, as `f32::round` needs std and the count is never negative.
+        let count = (dead_ends.len() as f32 * fraction.clamp(0.0, 1.0) + 0.5) as usize;
+
+        for cell in dead_ends.into_iter().take(count) {
+            // Opening a wall out of an earlier dead end may have already joined this one.
+            if self.neighbors(&cell).count() != 1 {
+                continue;
+            }
+            let closed: Vec<_> = self
+                .adjacent(&cell)
+                .filter(|neighbor| self.check_pair(&cell, neighbor) == Some(false))
+                .collect();
+            let toward_dead_ends: Vec<_> = closed
+                .iter()
+                .copied()
+                .filter(|neighbor| self.neighbors(neighbor).count() == 1)
+                .collect();
+            let candidates = if toward_dead_ends.is_empty() {
+                closed
+            } else {
+                toward_dead_ends
+            };
+            let target = match candidates.choose(rng) {
+                Some(target) => *target,
+                None => continue,
+            };
+            let dim = (0..DIMS).find(|dim| cell[*dim] != target[*dim]).unwrap();
+            self.open_wall(&cell.min(target), dim);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hashbrown::HashSet;
+    use rand::prelude::*;
+
+    fn walks<const DIMS: usize>(maze: &Maze<DIMS>) -> HashSet<([u16; DIMS], [u16; DIMS])> {
+        maze.iter_walks().collect()
+    }
+
+    #[test]
+    fn dead_ends_of_a_corridor() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::new(&[1, 4], &mut rng);
+        assert_eq!(maze.dead_ends(), [[0, 0], [0, 3]]);
+        assert!(Maze::new(&[1, 1], &mut rng).dead_ends().is_empty());
+    }
+
+    #[test]
+    fn braid_one_removes_every_dead_end() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let mut maze = Maze::new(&[6, 5, 4], &mut rng);
+        assert!(!maze.dead_ends().is_empty());
+        maze.braid(1.0, &mut rng);
+        assert!(maze.dead_ends().is_empty());
+        for (a, b) in maze.iter_walks() {
+            assert!(maze.neighbors(&a).any(|n| n == b));
+        }
+
+        // The ends of a corridor have nothing else to open.
+        let mut corridor = Maze::new(&[1, 4], &mut rng);
+        corridor.braid(1.0, &mut rng);
+        assert_eq!(corridor.dead_ends(), [[0, 0], [0, 3]]);
+    }
+
+    #[test]
+    fn braid_zero_changes_nothing() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let mut maze = Maze::new(&[6, 5, 4], &mut rng);
+        let before = walks(&maze);
+        maze.braid(0.0, &mut rng);
+        assert_eq!(walks(&maze), before);
+    }
+
+    #[test]
+    fn braid_is_deterministic() {
+        let braided = |fraction| {
+            let mut rng = StdRng::seed_from_u64(684153987);
+            let mut maze = Maze::new(&[6, 5, 4], &mut rng);
+            maze.braid(fraction, &mut rng);
+            maze
+        };
+        assert_eq!(walks(&braided(0.5)), walks(&braided(0.5)));
+
+        let before = braided(0.0);
+        let half = braided(0.5);
+        assert!(half.dead_ends().len() < before.dead_ends().len());
+        assert!(!half.dead_ends().is_empty());
+    }
+}
@@ -0,0 +1,43 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// How often, as a power of two, the generation loops publish their step count.
+const REPORT_EVERY: usize = 1 << 12;
+
+/// Coarse progress of a running maze generation, readable from other threads.
+///
+/// Generation takes one step per edge as the cells are initialized, then one per edge as
+/// they are processed, so the fraction moves steadily through both phases.
+#[derive(Debug, Default)]
+pub struct Progress {
+    done: AtomicUsize,
+    total: AtomicUsize,
+}
+
+impl Progress {
+    /// Fraction of the generation done, from 0 before it starts to 1 once it finishes.
+    pub fn fraction(&self) -> f32 {
+        let total = self.total.load(Ordering::Relaxed);
+        if total == 0 {
+            return 0.0;
+        }
+        let done = self.done.load(Ordering::Relaxed);
+        (done as f32 / total as f32).min(1.0)
+    }
+
+    pub(crate) fn start(&self, total: usize) {
+        self.done.store(0, Ordering::Relaxed);
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    /// Publishes the step count, throttled to every `REPORT_EVERY` steps.
+    pub(crate) fn report(&self, done: usize) {
+        if done & (REPORT_EVERY - 1) == 0 {
+            self.done.store(done, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn finish(&self) {
+        self.done
+            .store(self.total.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,126 @@
+//! Serializes a maze as its side lengths plus the sorted list of open walls, since serde can't
+//! derive for arrays of a const-generic length.
+
+use alloc::{format, vec::Vec};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{checked_cell_count, Maze, DEFAULT_MAX_CELLS};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Maze")]
+struct MazeRepr {
+    lengths: Vec<u16>,
+    walks: Vec<(Vec<u16>, Vec<u16>)>,
+}
+
+impl<const DIMS: usize> Serialize for Maze<DIMS> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut walks: Vec<_> = self.iter_walks().collect();
+        walks.sort_unstable();
+        MazeRepr {
+            lengths: self.lengths.to_vec(),
+            walks: walks
+                .into_iter()
+                .map(|(a, b)| (a.to_vec(), b.to_vec()))
+                .collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, const DIMS: usize> Deserialize<'de> for Maze<DIMS> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = MazeRepr::deserialize(deserializer)?;
+        let to_cell = |v: Vec<u16>| -> Result<[u16; DIMS], D::Error> {
+            let len = v.len();
+            v.try_into().map_err(|_| {
+                D::Error::custom(format!("expected {} coordinates, found {}", DIMS, len))
+            })
+        };
+
+        let lengths = to_cell(repr.lengths)?;
+        // Bounded like generation, so a hostile file can't ask for an arbitrarily large maze.
+        let cell_count =
+            checked_cell_count(&lengths, DEFAULT_MAX_CELLS).map_err(D::Error::custom)?;
+        let mut maze = Maze::closed(lengths, cell_count);
+        for (a, b) in repr.walks {
+            let (a, b) = (to_cell(a)?, to_cell(b)?);
+            let dim = (0..DIMS).find(|dim| {
+                let mut next = a;
+                next[*dim] = next[*dim].wrapping_add(1);
+                next == b
+            });
+            if !dim.is_some_and(|dim| maze.open_wall(&a, dim)) {
+                return Err(D::Error::custom(format!(
+                    "walk {:?} -> {:?} does not join neighboring cells in the maze",
+                    a, b
+                )));
+            }
+        }
+        Ok(maze)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn round_trip_preserves_walks() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::new(&[4, 3, 5], &mut rng);
+
+        let text = ron::to_string(&maze).unwrap();
+        let loaded: Maze<3> = ron::from_str(&text).unwrap();
+
+        assert_eq!(loaded.lengths, maze.lengths);
+        assert_eq!(loaded.walls, maze.walls);
+        assert_eq!(ron::to_string(&loaded).unwrap(), text);
+    }
+
+    #[test]
+    fn round_trip_axes_longer_than_a_byte() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::new(&[300, 2], &mut rng);
+
+        let loaded: Maze<2> = ron::from_str(&ron::to_string(&maze).unwrap()).unwrap();
+
+        assert_eq!(loaded.lengths(), &[300, 2]);
+        assert_eq!(loaded.fingerprint(), maze.fingerprint());
+        assert_eq!(
+            loaded.distance(&[0, 0], &[299, 1]),
+            maze.distance(&[0, 0], &[299, 1])
+        );
+    }
+
+    #[test]
+    fn rejects_wrong_dimensions() {
+        let text = "(lengths: [2, 2], walks: [])";
+        assert!(ron::from_str::<Maze<3>>(text).is_err());
+        assert!(ron::from_str::<Maze<2>>(text).is_ok());
+    }
+
+    #[test]
+    fn rejects_lengths_too_large_to_hold() {
+        assert!(ron::from_str::<Maze<2>>("(lengths: [0, 2], walks: [])").is_err());
+        assert!(
+            ron::from_str::<Maze<6>>("(lengths: [255, 255, 255, 255, 255, 255], walks: [])")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_walks() {
+        for walks in [
+            "[([0, 0], [1, 1])]",
+            "[([1, 0], [0, 0])]",
+            "[([1, 0], [2, 0])]",
+            "[([0, 0], [0, 0])]",
+        ] {
+            let text = format!("(lengths: [2, 2], walks: {})", walks);
+            assert!(ron::from_str::<Maze<2>>(&text).is_err(), "{}", walks);
+        }
+        assert!(ron::from_str::<Maze<2>>("(lengths: [2, 2], walks: [([0, 1], [1, 1])])").is_ok());
+    }
+}
@@ -0,0 +1,83 @@
+use alloc::{vec, vec::Vec};
+
+/// Open walls as one bit per dimension for every cell, indexed by flattened cell index. Bit
+/// `d` of a cell is set when the wall toward the next cell along dimension `d` is open, so
+/// each wall is stored once, on its lower cell. Up to 8 dimensions take a byte per cell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Walls<const DIMS: usize> {
+    bits: Vec<u8>,
+}
+
+impl<const DIMS: usize> Walls<DIMS> {
+    const CELL_BYTES: usize = DIMS.div_ceil(8);
+
+    /// Every wall between `cell_count` cells closed.
+    pub(crate) fn closed(cell_count: usize) -> Self {
+        Self {
+            bits: vec![0; cell_count * Self::CELL_BYTES],
+        }
+    }
+
+    #[inline]
+    fn locate(index: usize, dimension: usize) -> (usize, u8) {
+        (
+            index * Self::CELL_BYTES + dimension / 8,
+            1 << (dimension % 8),
+        )
+    }
+
+    /// Whether the wall from the cell at `index` toward the next one along `dimension` is open.
+    #[inline]
+    pub(crate) fn is_open(&self, index: usize, dimension: usize) -> bool {
+        let (byte, mask) = Self::locate(index, dimension);
+        self.bits[byte] & mask != 0
+    }
+
+    pub(crate) fn open(&mut self, index: usize, dimension: usize) {
+        let (byte, mask) = Self::locate(index, dimension);
+        self.bits[byte] |= mask;
+    }
+
+    pub(crate) fn count(&self) -> usize {
+        self.bits
+            .iter()
+            .map(|byte| byte.count_ones() as usize)
+            .sum()
+    }
+
+    /// Every open wall as the index of its lower cell and the dimension it crosses, in index
+    /// order and then dimension order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.bits.len() / Self::CELL_BYTES.max(1)).flat_map(move |index| {
+            (0..DIMS)
+                .filter(move |dim| self.is_open(index, *dim))
+                .map(move |dim| (index, dim))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walls_open_one_bit_each() {
+        let mut walls = Walls::<3>::closed(4);
+        assert_eq!(walls.count(), 0);
+        walls.open(1, 2);
+        walls.open(3, 0);
+        walls.open(3, 0);
+        assert!(walls.is_open(1, 2));
+        assert!(!walls.is_open(1, 1));
+        assert!(!walls.is_open(2, 2));
+        assert_eq!(walls.count(), 2);
+        assert_eq!(walls.iter().collect::<Vec<_>>(), [(1, 2), (3, 0)]);
+
+        // Past 8 dimensions each cell spills into a second byte.
+        let mut wide = Walls::<9>::closed(2);
+        wide.open(0, 8);
+        wide.open(1, 7);
+        assert!(wide.is_open(0, 8) && !wide.is_open(1, 8));
+        assert_eq!(wide.iter().collect::<Vec<_>>(), [(0, 8), (1, 7)]);
+    }
+}
@@ -0,0 +1,79 @@
+//! The system clipboard, reached through each platform's command line tools, as Bevy has no
+//! clipboard of its own.
+
+use std::{
+    io::{self, Read, Write},
+    process::{Command, Stdio},
+};
+
+#[cfg(target_os = "windows")]
+const COPY: &[&[&str]] = &[&["clip"]];
+#[cfg(target_os = "windows")]
+const PASTE: &[&[&str]] = &[&["powershell", "-NoProfile", "-Command", "Get-Clipboard"]];
+
+#[cfg(target_os = "macos")]
+const COPY: &[&[&str]] = &[&["pbcopy"]];
+#[cfg(target_os = "macos")]
+const PASTE: &[&[&str]] = &[&["pbpaste"]];
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const COPY: &[&[&str]] = &[
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+];
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const PASTE: &[&[&str]] = &[
+    &["wl-paste", "--no-newline"],
+    &["xclip", "-selection", "clipboard", "-o"],
+    &["xsel", "--clipboard", "--output"],
+];
+
+/// Puts `text` on the clipboard with the first tool that's installed.
+pub fn copy(text: &str) -> io::Result<()> {
+    first_available(COPY, |command| {
+        let mut child = command.stdin(Stdio::piped()).spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        check(child.wait()?.success())
+    })
+}
+
+/// Reads text off the clipboard with the first tool that's installed.
+pub fn paste() -> io::Result<String> {
+    first_available(PASTE, |command| {
+        let mut child = command.stdout(Stdio::piped()).spawn()?;
+        let mut text = String::new();
+        if let Some(mut stdout) = child.stdout.take() {
+            stdout.read_to_string(&mut text)?;
+        }
+        check(child.wait()?.success())?;
+        Ok(text)
+    })
+}
+
+/// Runs `run` with each command in turn until one succeeds, returning the last failure.
+fn first_available<T>(
+    commands: &[&[&str]],
+    mut run: impl FnMut(&mut Command) -> io::Result<T>,
+) -> io::Result<T> {
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, "no clipboard tool found");
+    for args in commands {
+        let mut command = Command::new(args[0]);
+        command.args(&args[1..]).stderr(Stdio::null());
+        match run(&mut command) {
+            Ok(value) => return Ok(value),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+fn check(success: bool) -> io::Result<()> {
+    if success {
+        Ok(())
+    } else {
+        Err(io::Error::other("clipboard tool failed"))
+    }
+}
@@ -0,0 +1,286 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+/// Distance under which a slide snaps to its target.
+const SNAP_EPSILON: f32 = 1e-3;
+/// The smallest scale a resize draws, since a zero scale has no normals to light.
+const MIN_SCALE: f32 = 0.01;
+
+/// How the maze slides under the player when the displayed position changes.
+pub struct MoveAnimation {
+    pub movement: Duration,
+    /// How long to slide after the displayed axes change, or `None` to snap, since the
+    /// player can land anywhere in the new slice.
+    pub axis_change: Option<Duration>,
+    /// How long walls take to grow into or shrink out of the slice when the displayed axes
+    /// change, or zero for them to pop in and out.
+    pub wall_transition: Duration,
+    pub easing: fn(f32) -> f32,
+}
+
+impl Default for MoveAnimation {
+    fn default() -> Self {
+        Self {
+            movement: Duration::from_millis(120),
+            axis_change: None,
+            wall_transition: Duration::from_millis(200),
+            easing: smoothstep,
+        }
+    }
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// An in-progress move of an entity's translation toward `to`.
+#[derive(Component, Clone, Debug)]
+pub struct Slide {
+    from: Vec3,
+    to: Vec3,
+    duration: f32,
+    elapsed: f32,
+    easing: fn(f32) -> f32,
+}
+
+impl Slide {
+    pub fn new(from: Vec3, to: Vec3, duration: Duration, easing: fn(f32) -> f32) -> Self {
+        Self {
+            from,
+            to,
+            duration: duration.as_secs_f32(),
+            elapsed: 0.0,
+            easing,
+        }
+    }
+
+    /// Linear progress through the slide, from 0 to 1.
+    pub fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            1.0
+        } else {
+            (self.elapsed / self.duration).clamp(0.0, 1.0)
+        }
+    }
+
+    pub fn translation(&self) -> Vec3 {
+        self.from.lerp(self.to, (self.easing)(self.progress()))
+    }
+
+    pub fn finished(&self) -> bool {
+        self.progress() >= 1.0 || self.translation().distance(self.to) < SNAP_EPSILON
+    }
+
+    /// Heads for a new target from wherever the slide currently is, so it doesn't jump back.
+    pub fn retarget(&mut self, to: Vec3, duration: Duration) {
+        *self = Self::new(self.translation(), to, duration, self.easing);
+    }
+
+    pub fn advance(&mut self, delta: Duration) {
+        self.elapsed += delta.as_secs_f32();
+    }
+}
+
+pub fn animate_slides(
+    mut c: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Transform, &mut Slide)>,
+) {
+    for (entity, mut transform, mut slide) in query.iter_mut() {
+        slide.advance(time.delta());
+        if slide.finished() {
+            transform.translation = slide.to;
+            c.entity(entity).remove::<Slide>();
+        } else {
+            transform.translation = slide.translation();
+        }
+    }
+}
+
+/// An in-progress change of an entity's scale, for walls growing into the slice or shrinking
+/// out of it.
+#[derive(Component, Clone, Debug)]
+pub struct Resize {
+    from: f32,
+    to: f32,
+    duration: f32,
+    elapsed: f32,
+    easing: fn(f32) -> f32,
+}
+
+impl Resize {
+    pub fn new(from: f32, to: f32, duration: Duration, easing: fn(f32) -> f32) -> Self {
+        Self {
+            from,
+            to,
+            duration: duration.as_secs_f32(),
+            elapsed: 0.0,
+            easing,
+        }
+    }
+
+    /// Linear progress through the resize, from 0 to 1.
+    pub fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            1.0
+        } else {
+            (self.elapsed / self.duration).clamp(0.0, 1.0)
+        }
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.from + (self.to - self.from) * (self.easing)(self.progress())
+    }
+
+    pub fn finished(&self) -> bool {
+        self.progress() >= 1.0
+    }
+
+    pub fn advance(&mut self, delta: Duration) {
+        self.elapsed += delta.as_secs_f32();
+    }
+
+    /// Shrinks from `scale` to nothing at the pace of a full `duration` from full size, so
+    /// a wall that had only half grown in is gone in half the time.
+    pub fn shrink(scale: f32, duration: Duration, easing: fn(f32) -> f32) -> Self {
+        Self::new(scale, 0.0, duration.mul_f32(scale.clamp(0.0, 1.0)), easing)
+    }
+
+    /// Scales `transform` to where the resize has got to.
+    pub fn apply(&self, transform: &mut Transform) {
+        transform.scale = Vec3::splat(self.scale().max(MIN_SCALE));
+    }
+}
+
+pub fn animate_resizes(
+    mut c: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Transform, &mut Resize)>,
+) {
+    for (entity, mut transform, mut resize) in query.iter_mut() {
+        resize.advance(time.delta());
+        resize.apply(&mut transform);
+        if resize.finished() {
+            c.entity(entity).remove::<Resize>();
+        }
+    }
+}
+
+/// An entity left in the level only until `timer` finishes, so it can animate out first.
+#[derive(Component, Clone, Debug)]
+pub struct Despawning {
+    timer: Timer,
+}
+
+impl Despawning {
+    pub fn after(duration: Duration) -> Self {
+        Self {
+            timer: Timer::new(duration, false),
+        }
+    }
+
+    /// Counts down by `delta`, returning whether the entity is due to go.
+    pub fn tick(&mut self, delta: Duration) -> bool {
+        self.timer.tick(delta).finished()
+    }
+}
+
+pub fn finish_despawns(
+    mut c: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Despawning)>,
+) {
+    for (entity, mut despawning) in query.iter_mut() {
+        if despawning.tick(time.delta()) {
+            c.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn linear(t: f32) -> f32 {
+        t
+    }
+
+    #[test]
+    fn slide_progress_is_clamped() {
+        let mut slide = Slide::new(Vec3::ZERO, Vec3::X, Duration::from_millis(100), linear);
+        assert_eq!(slide.progress(), 0.0);
+        slide.advance(Duration::from_millis(50));
+        assert!((slide.progress() - 0.5).abs() < 1e-6);
+        assert!((slide.translation() - Vec3::X * 0.5).length() < 1e-6);
+        slide.advance(Duration::from_millis(500));
+        assert_eq!(slide.progress(), 1.0);
+        assert_eq!(slide.translation(), Vec3::X);
+        assert!(slide.finished());
+    }
+
+    #[test]
+    fn zero_duration_snaps() {
+        let slide = Slide::new(Vec3::ZERO, Vec3::Z, Duration::ZERO, smoothstep);
+        assert!(slide.finished());
+        assert_eq!(slide.translation(), Vec3::Z);
+    }
+
+    #[test]
+    fn retarget_continues_from_current() {
+        let mut slide = Slide::new(Vec3::ZERO, Vec3::X, Duration::from_millis(100), smoothstep);
+        slide.advance(Duration::from_millis(50));
+        let current = slide.translation();
+
+        slide.retarget(Vec3::Z, Duration::from_millis(100));
+        assert_eq!(slide.translation(), current);
+        assert_eq!(slide.progress(), 0.0);
+        slide.advance(Duration::from_millis(100));
+        assert_eq!(slide.translation(), Vec3::Z);
+    }
+
+    #[test]
+    fn resizes_ease_between_scales() {
+        let mut resize = Resize::new(0.0, 1.0, Duration::from_millis(200), smoothstep);
+        assert_eq!(resize.scale(), 0.0);
+        resize.advance(Duration::from_millis(50));
+        // Smoothstep starts slowly, so a quarter of the way in is under a quarter grown.
+        assert!(resize.scale() > 0.0 && resize.scale() < 0.25);
+        resize.advance(Duration::from_millis(50));
+        assert!((resize.scale() - 0.5).abs() < 1e-6);
+        assert!(!resize.finished());
+        resize.advance(Duration::from_millis(500));
+        assert!(resize.finished());
+        assert_eq!(resize.scale(), 1.0);
+
+        let mut transform = Transform::default();
+        Resize::new(0.0, 1.0, Duration::from_millis(200), smoothstep).apply(&mut transform);
+        assert_eq!(transform.scale, Vec3::splat(MIN_SCALE));
+        assert!(Resize::new(1.0, 0.0, Duration::ZERO, smoothstep).finished());
+    }
+
+    #[test]
+    fn shrinking_starts_from_the_current_scale() {
+        let full = Duration::from_millis(200);
+        let mut growing = Resize::new(0.0, 1.0, full, linear);
+        growing.advance(Duration::from_millis(100));
+
+        // Leaving the slice halfway through growing in takes half as long to shrink back out.
+        let mut shrinking = Resize::shrink(growing.scale(), full, linear);
+        assert!((shrinking.scale() - 0.5).abs() < 1e-6);
+        shrinking.advance(Duration::from_millis(50));
+        assert!((shrinking.scale() - 0.25).abs() < 1e-6);
+        shrinking.advance(Duration::from_millis(50));
+        assert!(shrinking.finished());
+        assert_eq!(shrinking.scale(), 0.0);
+        assert_eq!(Resize::shrink(1.0, full, linear).duration, 0.2);
+    }
+
+    #[test]
+    fn despawning_waits_out_its_timer() {
+        let mut despawning = Despawning::after(Duration::from_millis(200));
+        assert!(!despawning.tick(Duration::from_millis(150)));
+        assert!(despawning.tick(Duration::from_millis(50)));
+        assert!(Despawning::after(Duration::ZERO).tick(Duration::ZERO));
+    }
+}
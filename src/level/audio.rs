@@ -0,0 +1,231 @@
+use std::{collections::HashSet, time::Duration};
+
+use bevy::{asset::LoadState, ecs::system::SystemParam, prelude::*};
+use rand::prelude::*;
+use serde::Deserialize;
+
+use super::{
+    goal::MazeCompleted,
+    maze_level::{AxisChanged, ChangeCause, MoveBlocked, PositionChanged},
+};
+
+/// Shortest gap between two footsteps, so held keys don't stack overlapping sounds.
+const FOOTSTEP_GAP: Duration = Duration::from_millis(90);
+/// How far each footstep's pitch may stray from the recording, so repeats sound less robotic.
+const FOOTSTEP_PITCH_JITTER: f32 = 0.06;
+
+/// The kinds of sound a level plays.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SoundCategory {
+    Footstep,
+    Bump,
+    Rotation,
+    Completion,
+}
+
+impl SoundCategory {
+    const ALL: [SoundCategory; 4] = [
+        SoundCategory::Footstep,
+        SoundCategory::Bump,
+        SoundCategory::Rotation,
+        SoundCategory::Completion,
+    ];
+
+    fn path(&self) -> &'static str {
+        match self {
+            SoundCategory::Footstep => "sounds/footstep.ogg",
+            SoundCategory::Bump => "sounds/bump.ogg",
+            SoundCategory::Rotation => "sounds/rotate.ogg",
+            SoundCategory::Completion => "sounds/complete.ogg",
+        }
+    }
+}
+
+/// How loud each kind of sound plays, from 0 to 1, read from the `audio` section of
+/// `assets/keybindings.ron`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AudioSettings {
+    pub master: f32,
+    pub footsteps: f32,
+    pub bumps: f32,
+    pub rotations: f32,
+    pub completion: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            master: 1.0,
+            footsteps: 0.5,
+            bumps: 0.8,
+            rotations: 0.8,
+            completion: 1.0,
+        }
+    }
+}
+
+impl AudioSettings {
+    pub fn volume(&self, category: SoundCategory) -> f32 {
+        let volume = match category {
+            SoundCategory::Footstep => self.footsteps,
+            SoundCategory::Bump => self.bumps,
+            SoundCategory::Rotation => self.rotations,
+            SoundCategory::Completion => self.completion,
+        };
+        (self.master * volume).clamp(0.0, 1.0)
+    }
+}
+
+/// Lets a footstep through only if the last one was at least `FOOTSTEP_GAP` ago.
+#[derive(Default)]
+struct FootstepThrottle {
+    last: Option<Duration>,
+}
+
+impl FootstepThrottle {
+    fn ready(&mut self, now: Duration) -> bool {
+        if self
+            .last
+            .is_some_and(|last| now.saturating_sub(last) < FOOTSTEP_GAP)
+        {
+            return false;
+        }
+        self.last = Some(now);
+        true
+    }
+}
+
+/// The level's sounds, loaded at startup.
+pub struct LevelSounds {
+    sounds: Vec<(SoundCategory, Handle<AudioSource>)>,
+    /// Sounds that failed to load and have already been reported.
+    missing: HashSet<SoundCategory>,
+    footsteps: FootstepThrottle,
+}
+
+pub fn load_level_sounds(mut c: Commands, assets: Res<AssetServer>) {
+    c.insert_resource(LevelSounds {
+        sounds: SoundCategory::ALL
+            .iter()
+            .map(|category| (*category, assets.load(category.path())))
+            .collect(),
+        missing: HashSet::new(),
+        footsteps: FootstepThrottle::default(),
+    });
+}
+
+impl LevelSounds {
+    /// The sound for `category` if it has loaded, reporting it the first time it fails to.
+    fn get(
+        &mut self,
+        assets: &AssetServer,
+        category: SoundCategory,
+    ) -> Option<Handle<AudioSource>> {
+        let handle = self
+            .sounds
+            .iter()
+            .find(|(c, _)| *c == category)
+            .map(|(_, handle)| handle.clone())?;
+        match assets.get_load_state(&handle) {
+            LoadState::Loaded => Some(handle),
+            LoadState::Failed => {
+                if self.missing.insert(category) {
+                    warn!("Could not load {}, playing without it", category.path());
+                }
+                None
+            }
+            // Queueing a sound that never loads would keep it queued forever.
+            _ => None,
+        }
+    }
+}
+
+/// The level events that make a sound.
+#[derive(SystemParam)]
+pub struct SoundEvents<'w, 's> {
+    position: EventReader<'w, 's, PositionChanged>,
+    blocked: EventReader<'w, 's, MoveBlocked>,
+    axis: EventReader<'w, 's, AxisChanged>,
+    completed: EventReader<'w, 's, MazeCompleted>,
+}
+
+impl<'w, 's> SoundEvents<'w, 's> {
+    /// The sounds this frame's events call for, at most one of each.
+    fn categories(&mut self) -> Vec<SoundCategory> {
+        let player_caused = |cause: ChangeCause| cause != ChangeCause::Loaded;
+        let mut categories = Vec::new();
+        if self.position.iter().any(|e| e.cause == ChangeCause::Moved) {
+            categories.push(SoundCategory::Footstep);
+        }
+        if self.blocked.iter().count() > 0 {
+            categories.push(SoundCategory::Bump);
+        }
+        if self.axis.iter().any(|e| player_caused(e.cause)) {
+            categories.push(SoundCategory::Rotation);
+        }
+        if self.completed.iter().count() > 0 {
+            categories.push(SoundCategory::Completion);
+        }
+        categories
+    }
+}
+
+pub fn play_level_sounds(
+    // Only present with the audio plugin, so missing in headless runs.
+    audio: Option<Res<Audio>>,
+    assets: Res<AssetServer>,
+    settings: Res<AudioSettings>,
+    time: Res<Time>,
+    mut sounds: ResMut<LevelSounds>,
+    mut events: SoundEvents,
+) {
+    for category in events.categories() {
+        let mut playback = PlaybackSettings::ONCE.with_volume(settings.volume(category));
+        if category == SoundCategory::Footstep {
+            if !sounds.footsteps.ready(time.time_since_startup()) {
+                continue;
+            }
+            let jitter = thread_rng().gen_range(-FOOTSTEP_PITCH_JITTER..=FOOTSTEP_PITCH_JITTER);
+            playback = playback.with_speed(1.0 + jitter);
+        }
+        if playback.volume <= 0.0 {
+            continue;
+        }
+        if let (Some(audio), Some(sound)) = (&audio, sounds.get(&assets, category)) {
+            audio.play_with_settings(sound, playback);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volume_scales_by_master() {
+        let settings = AudioSettings {
+            master: 0.5,
+            ..Default::default()
+        };
+        assert_eq!(settings.volume(SoundCategory::Completion), 0.5);
+        assert_eq!(settings.volume(SoundCategory::Footstep), 0.25);
+        let loud = AudioSettings {
+            master: 4.0,
+            ..Default::default()
+        };
+        assert_eq!(loud.volume(SoundCategory::Bump), 1.0);
+    }
+
+    #[test]
+    fn footsteps_are_throttled() {
+        let mut throttle = FootstepThrottle::default();
+        let ms = Duration::from_millis;
+        assert!(throttle.ready(ms(1000)));
+        assert!(!throttle.ready(ms(1040)));
+        assert!(throttle.ready(ms(1100)));
+        // A dropped footstep doesn't push the next one back.
+        assert!(!throttle.ready(ms(1150)));
+        assert!(throttle.ready(ms(1190)));
+    }
+}
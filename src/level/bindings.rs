@@ -0,0 +1,553 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+    time::Duration,
+};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    audio::AudioSettings,
+    palette::{AxisPalette, PaletteKind},
+    repeat::KeyRepeat,
+    time_trial::TimeTrialSettings,
+};
+
+/// Overrides for the default input settings, under `assets/`.
+const INPUT_CONFIG_FILE: &str = "keybindings.ron";
+
+/// Something the player can do in a level, bound to keys by `KeyBindings`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    /// Step forward along the first displayed axis.
+    MoveNorth,
+    MoveSouth,
+    /// Step forward along the second displayed axis.
+    MoveEast,
+    MoveWest,
+    /// Cycle the dimension shown on the first displayed axis.
+    RotatePrimaryBack,
+    RotatePrimaryForward,
+    /// Cycle the dimension shown on the second displayed axis.
+    RotateSecondaryBack,
+    RotateSecondaryForward,
+    /// Cycle the hidden dimension whose layers the volume view draws.
+    RotateDepthBack,
+    RotateDepthForward,
+    Hint,
+    Undo,
+    ToggleTrail,
+    /// Switches between the normal lighting and seeing only the cells around the player.
+    ToggleDarkMode,
+    /// Shows the layers on either side of the slice along the depth dimension, ghosted.
+    ToggleVolumeView,
+    /// Starts the current maze over from the beginning.
+    Restart,
+    /// Swaps the current maze for a new one of the same size.
+    Regenerate,
+    /// Pauses the maze, resumes it from the pause menu, and cancels a load.
+    Pause,
+    /// Leaves the completion screen for the menu.
+    Confirm,
+    /// Steps through free orbiting, following the player, and framing the whole slice.
+    CycleCameraMode,
+    /// Switches the camera between orbiting the maze and the player.
+    ToggleCameraFocus,
+    /// Points the camera back at the player from the starting angle.
+    RecenterCamera,
+    /// Logs the displayed slice, for inspecting a maze that looks wrong.
+    DebugSlice,
+    /// Switches to the next theme in `assets/themes`.
+    NextTheme,
+    /// Switches between the standard and colorblind axis colors.
+    NextPalette,
+    /// Lets the maze solve itself, for watching or testing, until any other input.
+    StartDemo,
+}
+
+impl Action {
+    pub const ALL: [Action; 26] = [
+        Action::MoveNorth,
+        Action::MoveSouth,
+        Action::MoveEast,
+        Action::MoveWest,
+        Action::RotatePrimaryBack,
+        Action::RotatePrimaryForward,
+        Action::RotateSecondaryBack,
+        Action::RotateSecondaryForward,
+        Action::RotateDepthBack,
+        Action::RotateDepthForward,
+        Action::Hint,
+        Action::Undo,
+        Action::ToggleTrail,
+        Action::ToggleDarkMode,
+        Action::ToggleVolumeView,
+        Action::Restart,
+        Action::Regenerate,
+        Action::Pause,
+        Action::Confirm,
+        Action::CycleCameraMode,
+        Action::ToggleCameraFocus,
+        Action::RecenterCamera,
+        Action::DebugSlice,
+        Action::NextTheme,
+        Action::NextPalette,
+        Action::StartDemo,
+    ];
+
+    fn default_keys(&self) -> Vec<KeyCode> {
+        match self {
+            Action::MoveNorth => vec![KeyCode::W, KeyCode::Up],
+            Action::MoveSouth => vec![KeyCode::S, KeyCode::Down],
+            Action::MoveEast => vec![KeyCode::D, KeyCode::Right],
+            Action::MoveWest => vec![KeyCode::A, KeyCode::Left],
+            Action::RotatePrimaryBack => vec![KeyCode::Q],
+            Action::RotatePrimaryForward => vec![KeyCode::E],
+            Action::RotateSecondaryBack => vec![KeyCode::Z],
+            Action::RotateSecondaryForward => vec![KeyCode::X],
+            Action::RotateDepthBack => vec![KeyCode::F],
+            Action::RotateDepthForward => vec![KeyCode::G],
+            Action::Hint => vec![KeyCode::H],
+            Action::Undo => vec![KeyCode::U],
+            Action::ToggleTrail => vec![KeyCode::T],
+            Action::ToggleDarkMode => vec![KeyCode::L],
+            Action::ToggleVolumeView => vec![KeyCode::V],
+            Action::Restart => vec![KeyCode::R],
+            Action::Regenerate => vec![],
+            Action::Pause => vec![KeyCode::Escape],
+            Action::Confirm => vec![KeyCode::Return],
+            Action::CycleCameraMode => vec![KeyCode::M],
+            Action::ToggleCameraFocus => vec![KeyCode::B],
+            Action::RecenterCamera => vec![KeyCode::C],
+            Action::DebugSlice => vec![KeyCode::F3],
+            Action::NextTheme => vec![KeyCode::F4],
+            Action::NextPalette => vec![KeyCode::F5],
+            Action::StartDemo => vec![KeyCode::F6],
+        }
+    }
+
+    fn default_chords(&self) -> Vec<KeyChord> {
+        match self {
+            Action::Regenerate => {
+                vec![(KeyCode::LShift, KeyCode::R), (KeyCode::RShift, KeyCode::R)]
+            }
+            _ => vec![],
+        }
+    }
+}
+
+/// A key pressed while a modifier is held, like `(LShift, R)`.
+pub type KeyChord = (KeyCode, KeyCode);
+
+/// The keys bound to each action. Input systems read it every frame, so changing it takes
+/// effect immediately.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyBindings {
+    bindings: HashMap<Action, Vec<KeyCode>>,
+    /// Chords bound to each action, which take over their key from any plain binding while
+    /// the modifier is held.
+    chords: HashMap<Action, Vec<KeyChord>>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL
+                .iter()
+                .map(|action| (*action, action.default_keys()))
+                .collect(),
+            chords: Action::ALL
+                .iter()
+                .map(|action| (*action, action.default_chords()))
+                .filter(|(_, chords)| !chords.is_empty())
+                .collect(),
+        }
+    }
+}
+
+/// The layout of `assets/keybindings.ron`, where anything left out keeps its default, like
+/// `(bindings: { Undo: [Back, U] }, chords: { Regenerate: [(LControl, N)] }, palette: Colorblind)`.
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct InputConfig {
+    /// Actions to rebind, each to every key it should respond to.
+    bindings: HashMap<Action, Vec<KeyCode>>,
+    /// Actions to rebind to modified keys, replacing any chords they had.
+    chords: HashMap<Action, Vec<KeyChord>>,
+    repeat_delay_ms: u64,
+    repeat_interval_ms: u64,
+    audio: AudioSettings,
+    palette: PaletteKind,
+    time_trial: TimeTrialSettings,
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        let repeat = KeyRepeat::default();
+        Self {
+            bindings: HashMap::new(),
+            chords: HashMap::new(),
+            repeat_delay_ms: repeat.delay.as_millis() as u64,
+            repeat_interval_ms: repeat.interval.as_millis() as u64,
+            audio: AudioSettings::default(),
+            palette: PaletteKind::default(),
+            time_trial: TimeTrialSettings::default(),
+        }
+    }
+}
+
+/// Everything read from `assets/keybindings.ron`.
+#[derive(Debug, Default, PartialEq)]
+struct Settings {
+    bindings: KeyBindings,
+    repeat: KeyRepeat,
+    audio: AudioSettings,
+    palette: AxisPalette,
+    time_trial: TimeTrialSettings,
+}
+
+/// The default settings, with whatever `text` lists overridden.
+fn parse_input_config(text: &str) -> Result<Settings, ron::Error> {
+    let config: InputConfig = ron::from_str(text)?;
+    let mut bindings = KeyBindings::default();
+    for (action, keys) in config.bindings {
+        bindings.bind(action, keys);
+    }
+    for (action, chords) in config.chords {
+        bindings.bind_chords(action, chords);
+    }
+    let repeat = KeyRepeat::new(
+        Duration::from_millis(config.repeat_delay_ms),
+        Duration::from_millis(config.repeat_interval_ms),
+    );
+    Ok(Settings {
+        bindings,
+        repeat,
+        audio: config.audio,
+        palette: AxisPalette {
+            kind: config.palette,
+        },
+        time_trial: config.time_trial,
+    })
+}
+
+impl KeyBindings {
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.bindings
+            .get(&action)
+            .map_or(&[], |keys| keys.as_slice())
+    }
+
+    pub fn chords(&self, action: Action) -> &[KeyChord] {
+        self.chords
+            .get(&action)
+            .map_or(&[], |chords| chords.as_slice())
+    }
+
+    /// Replaces the keys bound to `action`.
+    pub fn bind(&mut self, action: Action, keys: Vec<KeyCode>) {
+        self.bindings.insert(action, keys);
+    }
+
+    /// Replaces the chords bound to `action`.
+    pub fn bind_chords(&mut self, action: Action, chords: Vec<KeyChord>) {
+        self.chords.insert(action, chords);
+    }
+
+    /// Keys bound to more than one action, with the actions they trigger.
+    pub fn conflicts(&self) -> Vec<(KeyCode, Vec<Action>)> {
+        let mut by_key: Vec<(KeyCode, Vec<Action>)> = Vec::new();
+        for action in Action::ALL {
+            for key in self.keys(action) {
+                match by_key.iter_mut().find(|(bound, _)| bound == key) {
+                    Some((_, actions)) => actions.push(action),
+                    None => by_key.push((*key, vec![action])),
+                }
+            }
+        }
+        by_key.retain(|(_, actions)| actions.len() > 1);
+        by_key
+    }
+
+    /// Whether `key` is part of a chord whose modifier is held, and so not pressed alone.
+    fn in_held_chord(&self, keys: &Input<KeyCode>, key: KeyCode) -> bool {
+        self.chords
+            .values()
+            .flatten()
+            .any(|(modifier, chord_key)| *chord_key == key && keys.pressed(*modifier))
+    }
+
+    fn chord_just_pressed(keys: &Input<KeyCode>, (modifier, key): KeyChord) -> bool {
+        keys.pressed(modifier) && keys.just_pressed(key)
+    }
+
+    fn just_pressed(&self, keys: &Input<KeyCode>, action: Action) -> bool {
+        self.keys(action)
+            .iter()
+            .any(|key| keys.just_pressed(*key) && !self.in_held_chord(keys, *key))
+            || self
+                .chords(action)
+                .iter()
+                .any(|chord| Self::chord_just_pressed(keys, *chord))
+    }
+
+    fn pressed(&self, keys: &Input<KeyCode>, action: Action) -> bool {
+        self.keys(action)
+            .iter()
+            .any(|key| keys.pressed(*key) && !self.in_held_chord(keys, *key))
+    }
+}
+
+/// Actions triggered this frame by something other than the keyboard, like a gamepad or a
+/// mouse click, read alongside the keyboard by `ActionInput`.
+#[derive(Default)]
+pub struct TriggeredActions {
+    pressed: HashSet<Action>,
+}
+
+impl TriggeredActions {
+    pub fn trigger(&mut self, action: Action) {
+        self.pressed.insert(action);
+    }
+
+    fn just_pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pressed.is_empty()
+    }
+
+    fn clear_just_pressed(&mut self, action: Action) -> bool {
+        self.pressed.remove(&action)
+    }
+}
+
+/// Forgets last frame's triggered actions, before any device adds this frame's.
+pub fn clear_triggered_actions(mut actions: ResMut<TriggeredActions>) {
+    actions.pressed.clear();
+}
+
+/// Reads actions from the keyboard through the current `KeyBindings`, and from other devices.
+#[derive(SystemParam)]
+pub struct ActionInput<'w, 's> {
+    keys: Res<'w, Input<KeyCode>>,
+    bindings: Res<'w, KeyBindings>,
+    triggered: Res<'w, TriggeredActions>,
+    #[system_param(ignore)]
+    _marker: std::marker::PhantomData<&'s ()>,
+}
+
+impl<'w, 's> ActionInput<'w, 's> {
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.bindings.just_pressed(&self.keys, action) || self.triggered.just_pressed(action)
+    }
+}
+
+/// Like `ActionInput`, but able to consume a press so later systems don't see it too.
+#[derive(SystemParam)]
+pub struct ActionInputMut<'w, 's> {
+    keys: ResMut<'w, Input<KeyCode>>,
+    bindings: Res<'w, KeyBindings>,
+    triggered: ResMut<'w, TriggeredActions>,
+    #[system_param(ignore)]
+    _marker: std::marker::PhantomData<&'s ()>,
+}
+
+impl<'w, 's> ActionInputMut<'w, 's> {
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.bindings.just_pressed(&self.keys, action) || self.triggered.just_pressed(action)
+    }
+
+    /// Whether a key bound to `action` is held down. Other devices repeat on their own, if
+    /// at all, so this only reads the keyboard.
+    pub fn pressed(&self, action: Action) -> bool {
+        self.bindings.pressed(&self.keys, action)
+    }
+
+    /// Whether any key or device triggered `action`, clearing all of them.
+    pub fn clear_just_pressed(&mut self, action: Action) -> bool {
+        if !self.just_pressed(action) {
+            return false;
+        }
+        self.triggered.clear_just_pressed(action);
+        for key in self.bindings.keys(action) {
+            self.keys.clear_just_pressed(*key);
+        }
+        for (_, key) in self.bindings.chords(action) {
+            self.keys.clear_just_pressed(*key);
+        }
+        true
+    }
+
+    /// Whether any key was pressed or any device triggered an action, bound or not.
+    pub fn any_just_pressed(&self) -> bool {
+        self.keys.get_just_pressed().next().is_some() || !self.triggered.is_empty()
+    }
+
+    /// Clears every press this frame, so no later system acts on it.
+    pub fn clear_all(&mut self) {
+        self.keys.clear();
+        self.triggered.pressed.clear();
+    }
+}
+
+/// Loads the key bindings, repeat timing, audio volumes, axis palette and time trial budget
+/// from `assets/keybindings.ron` when it exists, falling back to the defaults if it can't be
+/// parsed.
+pub fn load_input_config(mut c: Commands) {
+    let path = Path::new("assets").join(INPUT_CONFIG_FILE);
+    let settings = match fs::read_to_string(&path) {
+        Ok(text) => parse_input_config(&text).unwrap_or_else(|e| {
+            warn!("Ignoring {}: {}", path.display(), e);
+            Default::default()
+        }),
+        Err(_) => Default::default(),
+    };
+    c.insert_resource(settings.bindings);
+    c.insert_resource(settings.repeat);
+    c.insert_resource(settings.audio);
+    c.insert_resource(settings.palette);
+    c.insert_resource(settings.time_trial);
+}
+
+pub fn warn_on_conflicts(bindings: Res<KeyBindings>) {
+    if !bindings.is_changed() {
+        return;
+    }
+    for (key, actions) in bindings.conflicts() {
+        warn!("{:?} is bound to more than one action: {:?}", key, actions);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_bind_every_action_without_conflicts() {
+        let bindings = KeyBindings::default();
+        for action in Action::ALL {
+            assert!(
+                !bindings.keys(action).is_empty() || !bindings.chords(action).is_empty(),
+                "{:?}",
+                action
+            );
+        }
+        assert!(bindings.conflicts().is_empty());
+    }
+
+    #[test]
+    fn overrides_replace_only_the_listed_actions() {
+        let Settings {
+            bindings,
+            repeat,
+            audio,
+            palette,
+            time_trial,
+        } = parse_input_config("(bindings: { Undo: [Back, U], Hint: [W] })").unwrap();
+        assert_eq!(bindings.keys(Action::Undo), [KeyCode::Back, KeyCode::U]);
+        assert_eq!(
+            bindings.keys(Action::MoveSouth),
+            [KeyCode::S, KeyCode::Down]
+        );
+        assert_eq!(
+            bindings.conflicts(),
+            [(KeyCode::W, vec![Action::MoveNorth, Action::Hint])]
+        );
+
+        assert_eq!(repeat, KeyRepeat::default());
+        assert_eq!(audio, AudioSettings::default());
+        assert_eq!(palette, AxisPalette::default());
+        assert_eq!(time_trial, TimeTrialSettings::default());
+
+        assert!(parse_input_config("(bindings: { Undo: U })").is_err());
+        assert!(parse_input_config("(bindings: { Jump: [Space] })").is_err());
+        assert!(parse_input_config("(repeat_delay: 5)").is_err());
+    }
+
+    #[test]
+    fn config_tunes_key_repeat() {
+        let Settings {
+            bindings, repeat, ..
+        } = parse_input_config("(repeat_delay_ms: 400, repeat_interval_ms: 60)").unwrap();
+        assert_eq!(bindings, KeyBindings::default());
+        assert_eq!(
+            repeat,
+            KeyRepeat::new(Duration::from_millis(400), Duration::from_millis(60))
+        );
+        assert!(parse_input_config("()").is_ok());
+    }
+
+    #[test]
+    fn config_sets_audio_volumes() {
+        let audio = parse_input_config("(audio: (master: 0.5, bumps: 0.0))")
+            .unwrap()
+            .audio;
+        assert_eq!(
+            audio,
+            AudioSettings {
+                master: 0.5,
+                bumps: 0.0,
+                ..Default::default()
+            }
+        );
+        assert!(parse_input_config("(audio: (music: 1.0))").is_err());
+    }
+
+    #[test]
+    fn config_selects_the_palette() {
+        let settings = parse_input_config("(palette: Colorblind)").unwrap();
+        assert_eq!(settings.palette.kind, PaletteKind::Colorblind);
+        assert!(parse_input_config("(palette: Sepia)").is_err());
+    }
+
+    #[test]
+    fn config_sets_the_time_trial_budget() {
+        let time_trial = parse_input_config("(time_trial: (seconds_per_step: 3.5))")
+            .unwrap()
+            .time_trial;
+        assert_eq!(time_trial.seconds_per_step, 3.5);
+    }
+
+    #[test]
+    fn chords_take_over_their_key() {
+        let bindings = KeyBindings::default();
+        let mut keys = Input::<KeyCode>::default();
+        keys.press(KeyCode::R);
+        assert!(bindings.just_pressed(&keys, Action::Restart));
+        assert!(!bindings.just_pressed(&keys, Action::Regenerate));
+
+        let mut keys = Input::<KeyCode>::default();
+        keys.press(KeyCode::RShift);
+        keys.press(KeyCode::R);
+        assert!(!bindings.just_pressed(&keys, Action::Restart));
+        assert!(bindings.just_pressed(&keys, Action::Regenerate));
+
+        let settings = parse_input_config("(chords: { Regenerate: [(LControl, N)] })").unwrap();
+        assert_eq!(
+            settings.bindings.chords(Action::Regenerate),
+            [(KeyCode::LControl, KeyCode::N)]
+        );
+    }
+
+    #[test]
+    fn clearing_an_action_clears_every_bound_key() {
+        let mut world = World::new();
+        let mut keys = Input::<KeyCode>::default();
+        keys.press(KeyCode::W);
+        keys.press(KeyCode::Up);
+        world.insert_resource(keys);
+        world.insert_resource(KeyBindings::default());
+        world.init_resource::<TriggeredActions>();
+
+        let mut state: bevy::ecs::system::SystemState<ActionInputMut> =
+            bevy::ecs::system::SystemState::new(&mut world);
+        let mut input = state.get_mut(&mut world);
+        assert!(input.clear_just_pressed(Action::MoveNorth));
+        assert!(!input.just_pressed(Action::MoveNorth));
+        assert!(!input.clear_just_pressed(Action::MoveNorth));
+    }
+}
@@ -0,0 +1,193 @@
+use bevy::prelude::*;
+
+use super::{
+    loader::{MazeAssets, Player},
+    maze_level::{MazeLevel, MoveBlocked, SliceWall},
+    maze_renderer::{MazeRenderer, MazeWall, CELL_SIZE},
+};
+
+/// How long the player takes to nudge toward a blocking wall and back, in seconds.
+const BUMP_DURATION: f32 = 0.16;
+/// How far the player nudges toward a blocking wall, in world units.
+const BUMP_DISTANCE: f32 = 0.15 * CELL_SIZE;
+/// How long a blocking wall glows after a bump, in seconds.
+const FLASH_DURATION: f32 = 0.25;
+
+/// An in-progress nudge of the player toward the wall it walked into.
+#[derive(Component, Clone, Debug)]
+pub struct Bump {
+    /// Where the player rests, kept across restarts so bumps never compound.
+    rest: Vec3,
+    direction: Vec3,
+    elapsed: f32,
+}
+
+impl Bump {
+    fn new(rest: Vec3, direction: Vec3) -> Self {
+        Self {
+            rest,
+            direction,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Starts over toward `direction`, from the same resting place.
+    fn restart(&mut self, direction: Vec3) {
+        *self = Self::new(self.rest, direction);
+    }
+
+    fn advance(&mut self, delta: f32) {
+        self.elapsed += delta;
+    }
+
+    fn finished(&self) -> bool {
+        self.elapsed >= BUMP_DURATION
+    }
+
+    /// Out to the wall over the first half, and back over the second.
+    fn translation(&self) -> Vec3 {
+        let t = (self.elapsed / BUMP_DURATION).clamp(0.0, 1.0);
+        let reach = 1.0 - (2.0 * t - 1.0).abs();
+        self.rest + self.direction * BUMP_DISTANCE * reach * reach * (3.0 - 2.0 * reach)
+    }
+}
+
+/// Walls under a maze root that are glowing from a bump, with their remaining glow time.
+#[derive(Component, Default)]
+pub struct WallFlashes {
+    flashes: Vec<(MazeWall, f32)>,
+}
+
+impl WallFlashes {
+    fn flash(&mut self, wall: MazeWall) {
+        self.flashes.retain(|(flashing, _)| *flashing != wall);
+        self.flashes.push((wall, FLASH_DURATION));
+    }
+}
+
+/// The world direction of a blocked move, which lies along one of the displayed axes.
+fn bump_direction(axis: [u8; 2], blocked: &MoveBlocked) -> Option<Vec3> {
+    let direction = if blocked.dimension == axis[0] as usize {
+        Vec3::X
+    } else if blocked.dimension == axis[1] as usize {
+        Vec3::Z
+    } else {
+        return None;
+    };
+    Some(if blocked.positive {
+        direction
+    } else {
+        -direction
+    })
+}
+
+pub fn start_bumps(
+    mut c: Commands,
+    level: Res<MazeLevel>,
+    mut blocked: EventReader<MoveBlocked>,
+    mut players: Query<(Entity, &Transform, Option<&mut Bump>), With<Player>>,
+    mut roots: Query<&mut WallFlashes, With<MazeRenderer>>,
+) {
+    let axis = level.axis();
+    for blocked in blocked.iter() {
+        let direction = match bump_direction(axis, blocked) {
+            Some(direction) => direction,
+            None => continue,
+        };
+        for (player, transform, bump) in players.iter_mut() {
+            match bump {
+                Some(mut bump) => bump.restart(direction),
+                None => {
+                    c.entity(player)
+                        .insert(Bump::new(transform.translation, direction));
+                }
+            }
+        }
+        if let Some(wall) = SliceWall::blocking(axis, blocked).map(MazeWall) {
+            for mut flashes in roots.iter_mut() {
+                flashes.flash(wall);
+            }
+        }
+    }
+}
+
+pub fn animate_bumps(
+    mut c: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Transform, &mut Bump)>,
+) {
+    for (entity, mut transform, mut bump) in query.iter_mut() {
+        bump.advance(time.delta_seconds());
+        transform.translation = bump.translation();
+        if bump.finished() {
+            transform.translation = bump.rest;
+            c.entity(entity).remove::<Bump>();
+        }
+    }
+}
+
+/// Swaps glowing walls to the bump material, and back to their usual one once the glow ends.
+/// Walls respawned by a slice change since the bump simply stop glowing.
+pub fn animate_wall_flashes(
+    time: Res<Time>,
+    assets: Res<MazeAssets>,
+    mut roots: Query<(&MazeRenderer, &mut WallFlashes)>,
+    mut materials: Query<&mut Handle<StandardMaterial>, With<MazeWall>>,
+) {
+    for (renderer, mut flashes) in roots.iter_mut() {
+        for (wall, remaining) in flashes.flashes.iter_mut() {
+            *remaining -= time.delta_seconds();
+            if let Some((entity, drawn)) = renderer.wall(wall) {
+                if let Ok(mut material) = materials.get_mut(entity) {
+                    *material = if *remaining > 0.0 {
+                        assets.bump_material()
+                    } else {
+                        drawn
+                    };
+                }
+            }
+        }
+        flashes.flashes.retain(|(_, remaining)| *remaining > 0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bump_returns_to_rest() {
+        let rest = Vec3::new(1.0, 0.0, 2.0);
+        let mut bump = Bump::new(rest, Vec3::X);
+        bump.advance(BUMP_DURATION / 2.0);
+        assert!((bump.translation() - (rest + Vec3::X * BUMP_DISTANCE)).length() < 1e-5);
+        bump.advance(BUMP_DURATION);
+        assert!(bump.finished());
+        assert_eq!(bump.translation(), rest);
+    }
+
+    #[test]
+    fn restarting_a_bump_does_not_drift() {
+        let mut bump = Bump::new(Vec3::ZERO, Vec3::Z);
+        for _ in 0..10 {
+            bump.advance(BUMP_DURATION / 3.0);
+            bump.restart(-Vec3::X);
+        }
+        assert_eq!(bump.translation(), Vec3::ZERO);
+        bump.advance(BUMP_DURATION / 2.0);
+        assert!((bump.translation() + Vec3::X * BUMP_DISTANCE).length() < 1e-5);
+    }
+
+    #[test]
+    fn bumps_head_along_the_displayed_axis() {
+        let blocked = |dimension, positive| MoveBlocked {
+            position: [1, 1],
+            dimension,
+            positive,
+            out_of_bounds: false,
+        };
+        assert_eq!(bump_direction([2, 0], &blocked(2, true)), Some(Vec3::X));
+        assert_eq!(bump_direction([2, 0], &blocked(0, false)), Some(-Vec3::Z));
+        assert_eq!(bump_direction([2, 0], &blocked(1, true)), None);
+    }
+}
@@ -0,0 +1,414 @@
+use std::{f32::consts::FRAC_PI_2, time::Duration};
+
+use bevy::{
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
+    prelude::*,
+};
+
+use super::{
+    bindings::{Action, ActionInput},
+    loader::Player,
+    maze_level::MazeLevel,
+    maze_renderer::{MazeRenderer, CELL_SIZE},
+};
+
+const MIN_DISTANCE: f32 = 4.0;
+const MAX_DISTANCE: f32 = 60.0;
+/// Keeps the camera off the floor and from flipping over the top.
+const MIN_PITCH: f32 = 0.1;
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.05;
+/// Radians turned per pixel of mouse movement while dragging.
+const ORBIT_SPEED: f32 = 0.005;
+/// Fraction of the distance zoomed per line scrolled.
+const ZOOM_SPEED: f32 = 0.1;
+/// Pixels scrolled by a touchpad that count as one line of a mouse wheel.
+const PIXELS_PER_LINE: f32 = 16.0;
+
+/// How the camera chooses what to look at.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CameraMode {
+    /// Orbited and zoomed by hand, around the focus of its `OrbitCamera`.
+    #[default]
+    Free,
+    /// Tracks the player from `CameraSettings::follow_offset`.
+    FollowPlayer,
+    /// Frames the whole displayed slice.
+    Overview,
+}
+
+/// How the camera follows its target in every mode.
+pub struct CameraSettings {
+    /// Where the camera sits relative to the player while following it.
+    pub follow_offset: Vec3,
+    /// Where the camera looks relative to the player while following it.
+    pub follow_look_at: Vec3,
+    /// Roughly how long the camera takes to catch up with a target that jumps.
+    pub smoothing: Duration,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            follow_offset: Vec3::new(-8.0, 10.0, -6.0),
+            follow_look_at: Vec3::ZERO,
+            smoothing: Duration::from_millis(150),
+        }
+    }
+}
+
+/// A critically damped spring, which chases a moving target as fast as it can without
+/// overshooting, so quick successive moves blend together instead of jittering.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Spring {
+    value: Vec3,
+    velocity: Vec3,
+}
+
+impl Spring {
+    fn new(value: Vec3) -> Self {
+        Self {
+            value,
+            velocity: Vec3::ZERO,
+        }
+    }
+
+    /// Advances `delta` seconds toward `target`, taking about `smoothing` seconds to settle.
+    fn step(&mut self, target: Vec3, smoothing: f32, delta: f32) -> Vec3 {
+        if smoothing <= 0.0 {
+            *self = Self::new(target);
+            return target;
+        }
+        let omega = 2.0 / smoothing;
+        let x = omega * delta;
+        // A close approximation of e^-x that stays stable for long frames.
+        let decay = 1.0 / (1.0 + x + 0.48 * x * x + 0.235 * x * x * x);
+        let change = self.value - target;
+        let temp = (self.velocity + omega * change) * delta;
+        self.velocity = (self.velocity - omega * temp) * decay;
+        self.value = target + (change + temp) * decay;
+        self.value
+    }
+}
+
+/// Where to put the camera, relative to the center of a slice `limit` cells across, so a
+/// camera with a vertical field of view of `fov` sees all of it.
+fn overview_offset(limit: [u16; 2], fov: f32) -> Vec3 {
+    let [length_x, length_y] = limit;
+    let radius = Vec2::new(length_x as f32, length_y as f32).length() / 2.0 * CELL_SIZE;
+    let direction = OrbitCamera::default().direction();
+    // A little extra room so the outer walls don't touch the edge of the screen.
+    direction * (radius / (fov / 2.0).sin()).max(MIN_DISTANCE) * 1.1
+}
+
+/// What the camera orbits around.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraFocus {
+    /// The middle of the displayed slice.
+    Maze,
+    Player,
+}
+
+/// Orbits the camera around the displayed slice: right drag turns it, scrolling zooms.
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct OrbitCamera {
+    pub focus: CameraFocus,
+    /// Angle around the vertical axis, from +z toward +x.
+    yaw: f32,
+    /// Angle above the floor.
+    pitch: f32,
+    distance: f32,
+    /// Whether a right drag started over the maze, rather than the UI, and is still held.
+    dragging: bool,
+    /// Where the camera is and where it looks, easing toward the current mode's framing.
+    /// Empty until the first frame, which snaps.
+    smoothed: Option<(Spring, Spring)>,
+}
+
+impl Default for OrbitCamera {
+    /// Looks down at the maze from above its far corner.
+    fn default() -> Self {
+        Self {
+            focus: CameraFocus::Maze,
+            yaw: (-0.8f32).atan2(-0.6),
+            pitch: (1.0 / 2.0f32.sqrt()).asin(),
+            distance: 200.0f32.sqrt(),
+            dragging: false,
+            smoothed: None,
+        }
+    }
+}
+
+impl OrbitCamera {
+    fn orbit(&mut self, delta: Vec2) {
+        self.yaw -= delta.x * ORBIT_SPEED;
+        self.pitch = (self.pitch + delta.y * ORBIT_SPEED).clamp(MIN_PITCH, MAX_PITCH);
+    }
+
+    fn zoom(&mut self, lines: f32) {
+        self.distance =
+            (self.distance * (1.0 - lines * ZOOM_SPEED)).clamp(MIN_DISTANCE, MAX_DISTANCE);
+    }
+
+    /// Points back at the player from the default angle, easing over from the current view.
+    fn recenter(&mut self) {
+        *self = Self {
+            focus: CameraFocus::Player,
+            smoothed: self.smoothed,
+            ..Default::default()
+        };
+    }
+
+    /// The unit vector from the focus toward the camera.
+    fn direction(&self) -> Vec3 {
+        Vec3::new(
+            self.pitch.cos() * self.yaw.sin(),
+            self.pitch.sin(),
+            self.pitch.cos() * self.yaw.cos(),
+        )
+    }
+
+    /// Where the camera sits to orbit `target`.
+    fn eye(&self, target: Vec3) -> Vec3 {
+        target + self.direction() * self.distance
+    }
+
+    /// Eases the camera toward sitting at `eye` and looking at `look_at`.
+    fn ease(&mut self, eye: Vec3, look_at: Vec3, smoothing: f32, delta: f32) -> Transform {
+        let (eye, look_at) = match &mut self.smoothed {
+            Some((eye_spring, look_spring)) => (
+                eye_spring.step(eye, smoothing, delta),
+                look_spring.step(look_at, smoothing, delta),
+            ),
+            None => {
+                self.smoothed = Some((Spring::new(eye), Spring::new(look_at)));
+                (eye, look_at)
+            }
+        };
+        Transform::from_translation(eye).looking_at(look_at, Vec3::Y)
+    }
+}
+
+/// Whether `cursor` is over any of the UI nodes, given as their center and size, which are
+/// both measured from the bottom left of the window like the cursor.
+fn over_ui(cursor: Vec2, nodes: impl IntoIterator<Item = (Vec2, Vec2)>) -> bool {
+    nodes.into_iter().any(|(center, size)| {
+        let offset = (cursor - center).abs();
+        offset.x <= size.x / 2.0 && offset.y <= size.y / 2.0
+    })
+}
+
+pub fn camera_input(
+    buttons: Res<Input<MouseButton>>,
+    input: ActionInput,
+    windows: Option<Res<Windows>>,
+    mut motion: EventReader<MouseMotion>,
+    mut wheel: EventReader<MouseWheel>,
+    nodes: Query<(&Node, &GlobalTransform, Option<&UiColor>, Option<&Text>)>,
+    mut cameras: Query<&mut OrbitCamera>,
+) {
+    // Transparent nodes only lay out their children, so only drawn ones block the maze.
+    let cursor = windows
+        .as_ref()
+        .and_then(|windows| windows.get_primary())
+        .and_then(|window| window.cursor_position());
+    let blocked = cursor.is_some_and(|cursor| {
+        over_ui(
+            cursor,
+            nodes
+                .iter()
+                .filter(|(_, _, color, text)| {
+                    text.is_some() || color.is_some_and(|color| color.0.a() > 0.0)
+                })
+                .map(|(node, transform, _, _)| (transform.translation.truncate(), node.size)),
+        )
+    });
+    let delta: Vec2 = motion.iter().map(|motion| &motion.delta).sum();
+    let lines: f32 = wheel
+        .iter()
+        .map(|wheel| match wheel.unit {
+            MouseScrollUnit::Line => wheel.y,
+            MouseScrollUnit::Pixel => wheel.y / PIXELS_PER_LINE,
+        })
+        .sum();
+
+    for mut camera in cameras.iter_mut() {
+        if buttons.just_pressed(MouseButton::Right) {
+            camera.dragging = !blocked;
+        }
+        if !buttons.pressed(MouseButton::Right) {
+            camera.dragging = false;
+        }
+        if camera.dragging && delta != Vec2::ZERO {
+            camera.orbit(delta);
+        }
+        if lines != 0.0 && !blocked {
+            camera.zoom(lines);
+        }
+        if input.just_pressed(Action::ToggleCameraFocus) {
+            camera.focus = match camera.focus {
+                CameraFocus::Maze => CameraFocus::Player,
+                CameraFocus::Player => CameraFocus::Maze,
+            };
+        }
+        if input.just_pressed(Action::RecenterCamera) {
+            camera.recenter();
+        }
+    }
+}
+
+pub fn cycle_camera_mode(input: ActionInput, mut mode: ResMut<CameraMode>) {
+    if input.just_pressed(Action::CycleCameraMode) {
+        *mode = match *mode {
+            CameraMode::Free => CameraMode::FollowPlayer,
+            CameraMode::FollowPlayer => CameraMode::Overview,
+            CameraMode::Overview => CameraMode::Free,
+        };
+        info!("Camera mode: {:?}", *mode);
+    }
+}
+
+/// Eases each orbit camera toward the framing of the current mode. The maze slides under the
+/// player, so this follows the maze root rather than a fixed point, and the slice's size so
+/// new axes stay framed.
+pub fn frame_camera(
+    level: Res<MazeLevel>,
+    mode: Res<CameraMode>,
+    settings: Res<CameraSettings>,
+    time: Res<Time>,
+    roots: Query<&Transform, (With<MazeRenderer>, Without<OrbitCamera>)>,
+    players: Query<&Transform, (With<Player>, Without<OrbitCamera>)>,
+    mut cameras: Query<(&mut OrbitCamera, &PerspectiveProjection, &mut Transform)>,
+) {
+    let [length_x, length_y] = level.pos_limit();
+    let slice_center =
+        Vec3::new(length_x as f32 - 1.0, 0.0, length_y as f32 - 1.0) / 2.0 * CELL_SIZE;
+    let slice_center = roots
+        .iter()
+        .next()
+        .map(|root| root.translation + slice_center);
+    let player = players.iter().next().map(|player| player.translation);
+
+    for (mut camera, projection, mut transform) in cameras.iter_mut() {
+        let framing = match *mode {
+            CameraMode::Free => match camera.focus {
+                CameraFocus::Maze => slice_center,
+                CameraFocus::Player => player,
+            }
+            .map(|target| (camera.eye(target), target)),
+            CameraMode::FollowPlayer => player.map(|player| {
+                (
+                    player + settings.follow_offset,
+                    player + settings.follow_look_at,
+                )
+            }),
+            CameraMode::Overview => slice_center.map(|center| {
+                (
+                    center + overview_offset(level.pos_limit(), projection.fov),
+                    center,
+                )
+            }),
+        };
+        if let Some((eye, look_at)) = framing {
+            *transform = camera.ease(
+                eye,
+                look_at,
+                settings.smoothing.as_secs_f32(),
+                time.delta_seconds(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_matches_the_starting_view() {
+        let mut camera = OrbitCamera::default();
+        let target = Vec3::new(2.0, 0.0, 2.0);
+        let transform = camera.ease(camera.eye(target), target, 1.0, 0.0);
+        let expected =
+            Transform::from_xyz(-6.0, 10.0, -4.0).looking_at(Vec3::new(2.0, 0.0, 2.0), Vec3::Y);
+        assert!((transform.translation - expected.translation).length() < 1e-4);
+        assert!(transform.rotation.abs_diff_eq(expected.rotation, 1e-4));
+    }
+
+    #[test]
+    fn orbit_and_zoom_stay_in_range() {
+        let mut camera = OrbitCamera::default();
+        camera.orbit(Vec2::new(0.0, 10_000.0));
+        assert_eq!(camera.pitch, MAX_PITCH);
+        camera.orbit(Vec2::new(100.0, -10_000.0));
+        assert_eq!(camera.pitch, MIN_PITCH);
+        assert_ne!(camera.yaw, OrbitCamera::default().yaw);
+
+        camera.zoom(100.0);
+        assert_eq!(camera.distance, MIN_DISTANCE);
+        camera.zoom(-1000.0);
+        assert_eq!(camera.distance, MAX_DISTANCE);
+
+        camera.recenter();
+        assert_eq!(camera.focus, CameraFocus::Player);
+        assert_eq!(camera.distance, OrbitCamera::default().distance);
+    }
+
+    #[test]
+    fn spring_settles_without_overshooting() {
+        let target = Vec3::new(10.0, 0.0, 0.0);
+        let mut spring = Spring::new(Vec3::ZERO);
+        let mut last = 0.0;
+        for _ in 0..60 {
+            let x = spring.step(target, 0.15, 1.0 / 60.0).x;
+            assert!(x >= last && x <= target.x, "{} after {}", x, last);
+            last = x;
+        }
+        assert!((target.x - last) < 1e-2, "{}", last);
+
+        // A long frame doesn't blow up either.
+        let mut spring = Spring::new(Vec3::ZERO);
+        let x = spring.step(target, 0.15, 2.0).x;
+        assert!(x > 9.0 && x <= target.x);
+
+        let mut spring = Spring::new(Vec3::ZERO);
+        assert_eq!(spring.step(target, 0.0, 0.01), target);
+    }
+
+    #[test]
+    fn successive_moves_blend_smoothly() {
+        // Five moves one frame apart, in the same direction.
+        let mut spring = Spring::new(Vec3::ZERO);
+        let mut last = 0.0;
+        for frame in 0..60 {
+            let target = Vec3::new((frame.min(4) + 1) as f32, 0.0, 0.0);
+            let x = spring.step(target, 0.15, 1.0 / 60.0).x;
+            assert!(x >= last, "moved backward on frame {}", frame);
+            last = x;
+        }
+        assert!((last - 5.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn overview_backs_off_for_larger_slices() {
+        let fov = PerspectiveProjection::default().fov;
+        let small = overview_offset([3, 3], fov);
+        let large = overview_offset([30, 20], fov);
+        assert!(large.length() > small.length());
+        assert!(small.length() >= MIN_DISTANCE);
+        // The slice's half diagonal fits inside the view's half angle.
+        let half_diagonal = Vec2::new(30.0, 20.0).length() / 2.0 * CELL_SIZE;
+        assert!((half_diagonal / large.length()).asin() < fov / 2.0);
+        assert!(large
+            .normalize()
+            .abs_diff_eq(OrbitCamera::default().direction(), 1e-5));
+    }
+
+    #[test]
+    fn cursor_over_ui_is_detected() {
+        let nodes = [(Vec2::new(100.0, 50.0), Vec2::new(200.0, 100.0))];
+        assert!(over_ui(Vec2::new(10.0, 10.0), nodes));
+        assert!(over_ui(Vec2::new(200.0, 100.0), nodes));
+        assert!(!over_ui(Vec2::new(201.0, 50.0), nodes));
+        assert!(!over_ui(Vec2::new(10.0, 10.0), []));
+    }
+}
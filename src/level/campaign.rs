@@ -0,0 +1,336 @@
+use std::{fs, path::Path, time::Duration};
+
+use bevy::prelude::*;
+use maze::GenAlgorithm;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    demo::DemoMode,
+    error::LevelError,
+    loader::{DimensionLength, LastLoad, LoadLevel, RngSource},
+    save::{write_ron, SaveLocation},
+    stats::{BestStats, LevelStats},
+};
+
+const CAMPAIGN_FILE: &str = "campaign.ron";
+const PROGRESS_FILE: &str = "campaign_progress.ron";
+/// The campaign built into the game, so it's there however and wherever the game is run.
+const BUILT_IN_CAMPAIGN: &str = include_str!("../../assets/campaign.ron");
+
+/// One level of the campaign, as written in `assets/campaign.ron`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CampaignLevel {
+    pub lengths: Vec<u16>,
+    /// The seed to generate from, or `None` for a different maze every time.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub algorithm: GenAlgorithm,
+    #[serde(default)]
+    pub braid: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CampaignFile {
+    levels: Vec<CampaignLevel>,
+}
+
+pub fn parse_campaign(text: &str) -> Result<Vec<CampaignLevel>, LevelError> {
+    let file: CampaignFile = ron::from_str(text)?;
+    if file.levels.is_empty() {
+        return Err(LevelError::EmptyCampaign);
+    }
+    for (index, level) in file.levels.iter().enumerate() {
+        let invalid = |reason| Err(LevelError::InvalidCampaignLevel { index, reason });
+        if DimensionLength::from_lengths(&level.lengths).is_none() {
+            return invalid("needs 2 to 6 lengths");
+        }
+        if level.lengths.contains(&0) {
+            return invalid("has an axis without a length");
+        }
+        if !(0.0..=1.0).contains(&level.braid) {
+            return invalid("has a braid factor outside 0 to 1");
+        }
+    }
+    Ok(file.levels)
+}
+
+/// How far through the campaign the player is, persisted between launches.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CampaignProgress {
+    /// The level to play next, which is past the last level once the campaign is finished.
+    pub current: usize,
+    pub best: Vec<Option<BestStats>>,
+    /// Totals over every level completed since the campaign was last started from the top.
+    pub total_steps: u32,
+    pub total_elapsed: Duration,
+}
+
+/// The campaign's levels and the player's progress through them. Without levels only free
+/// play is offered.
+#[derive(Default)]
+pub struct Campaign {
+    levels: Vec<CampaignLevel>,
+    pub progress: CampaignProgress,
+}
+
+impl Campaign {
+    /// A campaign of `levels`, picking up `progress` saved against what may have been a
+    /// different campaign file.
+    pub fn new(levels: Vec<CampaignLevel>, mut progress: CampaignProgress) -> Self {
+        progress.best.resize(levels.len(), None);
+        progress.current = progress.current.min(levels.len());
+        Self { levels, progress }
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.progress.current >= self.levels.len()
+    }
+
+    /// The request for the campaign level at `index`, if there is one.
+    pub fn load_level(&self, index: usize) -> Option<LoadLevel> {
+        let level = self.levels.get(index)?;
+        Some(LoadLevel {
+            rng_source: match level.seed {
+                Some(seed) => RngSource::Seeded(seed),
+                None => RngSource::Entropy,
+            },
+            dimensions: DimensionLength::from_lengths(&level.lengths)?,
+            algorithm: level.algorithm,
+            braid: level.braid,
+            maze_file: None,
+            campaign_level: Some(index),
+            difficulty: None,
+            time_trial: false,
+        })
+    }
+
+    /// The level to play next, starting the run over once the campaign is finished.
+    pub fn next_level(&mut self) -> Option<LoadLevel> {
+        if self.is_finished() {
+            self.progress.current = 0;
+        }
+        if self.progress.current == 0 {
+            self.progress.total_steps = 0;
+            self.progress.total_elapsed = Duration::ZERO;
+        }
+        self.load_level(self.progress.current)
+    }
+
+    /// Counts a completed level toward the run, keeping the best steps and time seen on it
+    /// and moving on past it.
+    pub fn record(&mut self, index: usize, stats: &LevelStats) {
+        let best = match self.progress.best.get_mut(index) {
+            Some(best) => best,
+            None => return,
+        };
+        *best = Some(BestStats::improve(*best, stats));
+        self.progress.total_steps += stats.steps;
+        self.progress.total_elapsed += stats.elapsed;
+        self.progress.current = self.progress.current.max(index + 1);
+    }
+}
+
+/// Sent by the main menu to play the campaign's next level.
+pub struct StartCampaign;
+
+fn read_progress(path: &Path) -> CampaignProgress {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(_) => return Default::default(),
+    };
+    ron::from_str(&text).unwrap_or_else(|e| {
+        warn!("Ignoring campaign progress at {}: {}", path.display(), e);
+        Default::default()
+    })
+}
+
+fn write_progress(location: &SaveLocation, progress: &CampaignProgress) {
+    if let Some(path) = location.sibling(PROGRESS_FILE) {
+        if let Err(e) = write_ron(&path, progress) {
+            error!("Could not save campaign progress: {}", e);
+        }
+    }
+}
+
+fn built_in_levels() -> Vec<CampaignLevel> {
+    parse_campaign(BUILT_IN_CAMPAIGN).expect("the built-in campaign is valid")
+}
+
+/// The levels of the campaign file at `path`, which overrides the built-in campaign when it
+/// exists and can be parsed.
+fn read_levels(path: &Path) -> Vec<CampaignLevel> {
+    match fs::read_to_string(path) {
+        Ok(text) => parse_campaign(&text).unwrap_or_else(|e| {
+            error!(
+                "Ignoring {}, playing the built-in campaign: {}",
+                path.display(),
+                e
+            );
+            built_in_levels()
+        }),
+        Err(_) => built_in_levels(),
+    }
+}
+
+pub fn load_campaign(mut c: Commands, location: Res<SaveLocation>) {
+    let levels = read_levels(&Path::new("assets").join(CAMPAIGN_FILE));
+    let progress = location
+        .sibling(PROGRESS_FILE)
+        .map(|path| read_progress(&path))
+        .unwrap_or_default();
+    c.insert_resource(Campaign::new(levels, progress));
+}
+
+pub fn start_campaign(
+    mut campaign: ResMut<Campaign>,
+    mut requests: EventReader<StartCampaign>,
+    mut load_level: EventWriter<LoadLevel>,
+) {
+    if requests.iter().count() == 0 {
+        return;
+    }
+    if let Some(level) = campaign.next_level() {
+        load_level.send(level);
+    }
+}
+
+/// Records a completed campaign level before the completion screen is drawn from it.
+pub fn record_campaign_level(
+    mut campaign: ResMut<Campaign>,
+    last_load: Option<Res<LastLoad>>,
+    stats: Res<LevelStats>,
+    location: Res<SaveLocation>,
+    demo: Res<DemoMode>,
+) {
+    let index = match last_load.and_then(|last_load| last_load.0.campaign_level) {
+        Some(index) if !demo.is_running() => index,
+        _ => return,
+    };
+    campaign.record(index, &stats);
+    write_progress(&location, &campaign.progress);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn campaign() -> Campaign {
+        Campaign::new(built_in_levels(), default())
+    }
+
+    fn stats(steps: u32, seconds: u64) -> LevelStats {
+        LevelStats {
+            steps,
+            elapsed: Duration::from_secs(seconds),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn shipped_campaign_parses() {
+        let campaign = campaign();
+        assert!(campaign.len() > 1);
+        for index in 0..campaign.len() {
+            let level = campaign.load_level(index).unwrap();
+            assert_eq!(level.campaign_level, Some(index));
+        }
+        assert!(campaign.load_level(campaign.len()).is_none());
+    }
+
+    #[test]
+    fn a_campaign_file_overrides_the_built_in_one() {
+        let dir =
+            std::env::temp_dir().join(format!("nothing-moves-campaign-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(CAMPAIGN_FILE);
+        assert_eq!(read_levels(&path), built_in_levels());
+
+        fs::write(&path, "(levels: [(lengths: [3, 3], seed: Some(5))])").unwrap();
+        assert_eq!(read_levels(&path).len(), 1);
+
+        // A broken override still leaves the campaign to play.
+        fs::write(&path, "(levels: [").unwrap();
+        assert_eq!(read_levels(&path), built_in_levels());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn malformed_campaigns_are_rejected() {
+        assert!(matches!(
+            parse_campaign("(levels: [(lengths: [4, 4]"),
+            Err(LevelError::Parse(_))
+        ));
+        assert!(matches!(
+            parse_campaign("(levels: [])"),
+            Err(LevelError::EmptyCampaign)
+        ));
+        assert!(matches!(
+            parse_campaign("(levels: [(lengths: [4, 4]), (lengths: [4])])"),
+            Err(LevelError::InvalidCampaignLevel { index: 1, .. })
+        ));
+        assert!(matches!(
+            parse_campaign("(levels: [(lengths: [4, 0, 2])])"),
+            Err(LevelError::InvalidCampaignLevel { index: 0, .. })
+        ));
+        assert!(matches!(
+            parse_campaign("(levels: [(lengths: [4, 4], braid: 2.0)])"),
+            Err(LevelError::InvalidCampaignLevel { index: 0, .. })
+        ));
+        let levels =
+            parse_campaign("(levels: [(lengths: [3, 3], seed: Some(5), algorithm: Wilson)])")
+                .unwrap();
+        assert_eq!(levels[0].algorithm, GenAlgorithm::Wilson);
+    }
+
+    #[test]
+    fn completing_levels_keeps_the_best_and_moves_on() {
+        let mut campaign = campaign();
+        assert_eq!(campaign.next_level().unwrap().campaign_level, Some(0));
+        campaign.record(0, &stats(20, 30));
+        campaign.record(0, &stats(25, 10));
+        assert_eq!(
+            campaign.progress.best[0],
+            Some(BestStats {
+                steps: 20,
+                elapsed: Duration::from_secs(10)
+            })
+        );
+        assert_eq!(campaign.progress.current, 1);
+        assert_eq!(campaign.progress.total_steps, 45);
+        assert_eq!(campaign.next_level().unwrap().campaign_level, Some(1));
+
+        for index in 1..campaign.len() {
+            campaign.record(index, &stats(1, 1));
+        }
+        assert!(campaign.is_finished());
+        // Starting again after the last level is a fresh run, but the best stats stay.
+        assert_eq!(campaign.next_level().unwrap().campaign_level, Some(0));
+        assert_eq!(campaign.progress.total_steps, 0);
+        assert!(campaign.progress.best[0].is_some());
+    }
+
+    #[test]
+    fn progress_from_another_campaign_is_fitted() {
+        let progress = CampaignProgress {
+            current: 40,
+            best: vec![None; 50],
+            ..Default::default()
+        };
+        let levels = parse_campaign("(levels: [(lengths: [4, 4]), (lengths: [5, 5])])").unwrap();
+        let campaign = Campaign::new(levels, progress);
+        assert_eq!(campaign.progress.best.len(), 2);
+        assert!(campaign.is_finished());
+    }
+}
@@ -0,0 +1,328 @@
+use std::fmt;
+
+use maze::GenAlgorithm;
+
+use super::loader::{DimensionLength, LoadLevel, RngSource};
+
+/// Layout of the bytes behind a code. Bump it whenever the layout changes, or the maze a seed
+/// generates does, so builds on either side refuse the other's codes instead of loading the
+/// wrong maze. Version 2 shuffles Kruskal's walls rather than popping them off a heap,
+/// version 3 gives each length two bytes, and version 4 braids with `rand`'s shuffle.
+const VERSION: u8 = 4;
+/// Crockford's base32, which leaves out letters easily mistaken for digits.
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+/// Set in the flags byte when a seed follows, rather than drawing one from entropy.
+const SEEDED: u8 = 1;
+
+/// A short string that reproduces a generated level, for sharing mazes between players.
+///
+/// Codes carry the lengths, seed, algorithm and braid factor, but not a maze file, so a
+/// level loaded from disk has no code.
+pub struct LevelCode;
+
+impl LevelCode {
+    pub fn encode(level: &LoadLevel) -> String {
+        let lengths = level.dimensions.lengths();
+        let mut bytes = vec![VERSION, algorithm_byte(level.algorithm)];
+        match level.rng_source {
+            RngSource::Seeded(seed) => {
+                bytes.push(SEEDED);
+                bytes.extend_from_slice(&seed.to_le_bytes());
+            }
+            RngSource::Entropy => bytes.push(0),
+        }
+        bytes.extend_from_slice(&level.braid.to_bits().to_le_bytes());
+        bytes.push(lengths.len() as u8);
+        for length in lengths {
+            bytes.extend_from_slice(&length.to_le_bytes());
+        }
+        bytes.push(checksum(&bytes));
+        to_base32(&bytes)
+    }
+
+    /// Reads a code back into the request it was made from. Case, dashes and whitespace are
+    /// ignored, so a code survives being retyped.
+    pub fn decode(code: &str) -> Result<LoadLevel, LevelCodeError> {
+        let bytes = from_base32(code)?;
+        let (&sum, bytes) = bytes.split_last().ok_or(LevelCodeError::Truncated)?;
+        // Checked before the checksum, as a future layout may checksum differently.
+        match bytes.first() {
+            Some(&version) if version > VERSION => {
+                return Err(LevelCodeError::UnsupportedVersion(version))
+            }
+            Some(&version) if version < VERSION => {
+                return Err(LevelCodeError::OutdatedVersion(version))
+            }
+            Some(_) => {}
+            None => return Err(LevelCodeError::Truncated),
+        }
+        if checksum(bytes) != sum {
+            return Err(LevelCodeError::Checksum);
+        }
+
+        let mut reader = Reader(&bytes[1..]);
+        let algorithm = match reader.take(1)?[0] {
+            0 => GenAlgorithm::Kruskal,
+            1 => GenAlgorithm::RecursiveBacktracker,
+            2 => GenAlgorithm::Wilson,
+            other => return Err(LevelCodeError::UnknownAlgorithm(other)),
+        };
+        let rng_source = match reader.take(1)?[0] {
+            SEEDED => RngSource::Seeded(u64::from_le_bytes(reader.array()?)),
+            _ => RngSource::Entropy,
+        };
+        let braid = f32::from_bits(u32::from_le_bytes(reader.array()?));
+        if !(0.0..=1.0).contains(&braid) {
+            return Err(LevelCodeError::InvalidBraid);
+        }
+        let count = reader.take(1)?[0] as usize;
+        let lengths = (0..count)
+            .map(|_| Ok(u16::from_le_bytes(reader.array()?)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let dimensions =
+            DimensionLength::from_lengths(&lengths).ok_or(LevelCodeError::DimensionCount(count))?;
+        if !reader.0.is_empty() {
+            return Err(LevelCodeError::TrailingBytes);
+        }
+        Ok(LoadLevel {
+            rng_source,
+            dimensions,
+            algorithm,
+            braid,
+            maze_file: None,
+            campaign_level: None,
+            difficulty: None,
+            time_trial: false,
+        })
+    }
+}
+
+/// Why a typed code could not be read.
+#[derive(Debug, PartialEq, Eq)]
+pub enum LevelCodeError {
+    InvalidCharacter(char),
+    /// The code ends before the level it describes does.
+    Truncated,
+    TrailingBytes,
+    /// The code was mistyped somewhere.
+    Checksum,
+    /// The code comes from a newer build with a layout this one doesn't understand.
+    UnsupportedVersion(u8),
+    /// The code comes from an older build, which generated a different maze from its seed.
+    OutdatedVersion(u8),
+    UnknownAlgorithm(u8),
+    InvalidBraid,
+    DimensionCount(usize),
+}
+
+impl fmt::Display for LevelCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelCodeError::InvalidCharacter(c) => write!(f, "'{}' is not part of a code", c),
+            LevelCodeError::Truncated => write!(f, "the code is too short"),
+            LevelCodeError::TrailingBytes => write!(f, "the code is too long"),
+            LevelCodeError::Checksum => write!(f, "the code has a typo"),
+            LevelCodeError::UnsupportedVersion(v) => {
+                write!(f, "the code is from a newer version ({})", v)
+            }
+            LevelCodeError::OutdatedVersion(v) => {
+                write!(f, "the code is from an older version ({})", v)
+            }
+            LevelCodeError::UnknownAlgorithm(a) => write!(f, "unknown algorithm {}", a),
+            LevelCodeError::InvalidBraid => write!(f, "the braid factor is out of range"),
+            LevelCodeError::DimensionCount(count) => {
+                write!(f, "a code can't describe {} dimensions", count)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LevelCodeError {}
+
+fn algorithm_byte(algorithm: GenAlgorithm) -> u8 {
+    match algorithm {
+        GenAlgorithm::Kruskal => 0,
+        GenAlgorithm::RecursiveBacktracker => 1,
+        GenAlgorithm::Wilson => 2,
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes
+        .iter()
+        .fold(0u8, |sum, byte| sum.rotate_left(3) ^ byte)
+}
+
+/// Reads fields off the front of a decoded code.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], LevelCodeError> {
+        if self.0.len() < count {
+            return Err(LevelCodeError::Truncated);
+        }
+        let (taken, rest) = self.0.split_at(count);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], LevelCodeError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+}
+
+fn to_base32(bytes: &[u8]) -> String {
+    let mut code = String::with_capacity((bytes.len() * 8).div_ceil(5));
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    for byte in bytes {
+        buffer = (buffer << 8) | *byte as u16;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            code.push(ALPHABET[(buffer >> bits) as usize & 31] as char);
+        }
+    }
+    if bits > 0 {
+        code.push(ALPHABET[(buffer << (5 - bits)) as usize & 31] as char);
+    }
+    code
+}
+
+fn from_base32(code: &str) -> Result<Vec<u8>, LevelCodeError> {
+    let mut bytes = Vec::with_capacity(code.len() * 5 / 8);
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    for c in code.chars() {
+        if c == '-' || c.is_whitespace() {
+            continue;
+        }
+        let value = match c.to_ascii_uppercase() {
+            'O' => 0,
+            'I' | 'L' => 1,
+            upper => ALPHABET
+                .iter()
+                .position(|a| *a as char == upper)
+                .ok_or(LevelCodeError::InvalidCharacter(c))?,
+        };
+        buffer = (buffer << 5) | value as u16;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(dimensions: DimensionLength) -> LoadLevel {
+        LoadLevel {
+            rng_source: RngSource::Seeded(684153987),
+            dimensions,
+            algorithm: GenAlgorithm::Wilson,
+            braid: 0.35,
+            maze_file: None,
+            campaign_level: None,
+            difficulty: None,
+            time_trial: false,
+        }
+    }
+
+    fn assert_round_trips(level: &LoadLevel) {
+        let decoded = LevelCode::decode(&LevelCode::encode(level)).unwrap();
+        assert_eq!(decoded.dimensions.lengths(), level.dimensions.lengths());
+        assert_eq!(decoded.algorithm, level.algorithm);
+        assert_eq!(decoded.braid, level.braid);
+        match (decoded.rng_source, &level.rng_source) {
+            (RngSource::Seeded(a), RngSource::Seeded(b)) => assert_eq!(a, *b),
+            (RngSource::Entropy, RngSource::Entropy) => {}
+            (a, b) => panic!("{:?} decoded as {:?}", b, a),
+        }
+    }
+
+    #[test]
+    fn codes_round_trip_every_dimension_count() {
+        for dimensions in [
+            DimensionLength::Two([4, 15]),
+            DimensionLength::Two([300, 2]),
+            DimensionLength::Three([4, 5, 2]),
+            DimensionLength::Four([1, 2, 3, 4]),
+            DimensionLength::Five([255, 1, 9, 2, 3]),
+            DimensionLength::Six([2; 6]),
+        ] {
+            assert_round_trips(&level(dimensions));
+        }
+        assert_round_trips(&LoadLevel {
+            rng_source: RngSource::Seeded(u64::MAX),
+            algorithm: GenAlgorithm::RecursiveBacktracker,
+            braid: 1.0,
+            ..level(DimensionLength::Two([2, 2]))
+        });
+        assert_round_trips(&LoadLevel {
+            rng_source: RngSource::Entropy,
+            algorithm: GenAlgorithm::Kruskal,
+            ..level(DimensionLength::Three([3, 3, 3]))
+        });
+    }
+
+    #[test]
+    fn codes_survive_being_retyped() {
+        let code = LevelCode::encode(&level(DimensionLength::Three([4, 5, 2])));
+        let retyped = code
+            .to_lowercase()
+            .replace('0', "o")
+            .replace('1', "l")
+            .chars()
+            .collect::<Vec<_>>()
+            .chunks(4)
+            .map(|chunk| chunk.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("-");
+        assert_round_trips(&LevelCode::decode(&retyped).unwrap());
+    }
+
+    #[test]
+    fn damaged_codes_are_rejected() {
+        let code = LevelCode::encode(&level(DimensionLength::Three([4, 5, 2])));
+        assert_eq!(
+            LevelCode::decode(&code[..code.len() - 3]).err(),
+            Some(LevelCodeError::Checksum)
+        );
+        assert_eq!(LevelCode::decode("").err(), Some(LevelCodeError::Truncated));
+        assert_eq!(
+            LevelCode::decode("AB!C").err(),
+            Some(LevelCodeError::InvalidCharacter('!'))
+        );
+
+        let mut typo = code.into_bytes();
+        typo[6] = if typo[6] == b'2' { b'3' } else { b'2' };
+        assert_eq!(
+            LevelCode::decode(std::str::from_utf8(&typo).unwrap()).err(),
+            Some(LevelCodeError::Checksum)
+        );
+    }
+
+    #[test]
+    fn codes_from_newer_versions_are_rejected() {
+        let mut bytes = vec![VERSION + 1, 0, 0, 1, 2, 3];
+        bytes.push(checksum(&bytes));
+        assert_eq!(
+            LevelCode::decode(&to_base32(&bytes)).err(),
+            Some(LevelCodeError::UnsupportedVersion(VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn codes_from_older_versions_are_rejected() {
+        let mut bytes = vec![VERSION - 1, 0, 0, 1, 2, 3];
+        bytes.push(checksum(&bytes));
+        assert_eq!(
+            LevelCode::decode(&to_base32(&bytes)).err(),
+            Some(LevelCodeError::OutdatedVersion(VERSION - 1))
+        );
+    }
+}
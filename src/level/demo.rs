@@ -0,0 +1,258 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::{
+    bindings::{Action, ActionInputMut, TriggeredActions},
+    input::{AXIS_SHIFTS, MOVES},
+    loader::{DimensionLength, LastLoad, LoadLevel},
+    maze_level::{Axis, Direction, MazeLevel, MazeView},
+};
+use crate::{
+    toast::{ShowToast, ToastKind},
+    AppState,
+};
+
+/// How long the main menu sits untouched before the demo starts playing behind it.
+const MENU_IDLE: Duration = Duration::from_secs(30);
+/// How long the demo waits between its moves, slow enough to follow along.
+const STEP_INTERVAL: Duration = Duration::from_millis(300);
+
+/// One input the demo gives on its way to the goal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DemoStep {
+    /// Step along a displayed axis.
+    Move(Axis, Direction),
+    /// Cycle the dimension shown on an axis, toward the hidden one the path continues along.
+    Rotate(Axis, Direction),
+}
+
+impl DemoStep {
+    /// The action the player would press for this step.
+    pub fn action(&self) -> Action {
+        let (table, axis, dir) = match *self {
+            DemoStep::Move(axis, dir) => (&MOVES, axis, dir),
+            DemoStep::Rotate(axis, dir) => (&AXIS_SHIFTS, axis, dir),
+        };
+        table
+            .iter()
+            .find(|(_, a, d)| *a == axis && *d == dir)
+            .map(|(action, _, _)| *action)
+            .expect("every axis and direction has an action")
+    }
+}
+
+/// The next input along the shortest path from the player to the goal, or `None` once at the
+/// goal or if it can't be reached. It is worked out afresh from the level every time, so a
+/// path never goes stale.
+pub fn next_step(level: &dyn MazeView) -> Option<DemoStep> {
+    let (dimension, dir) = level.step_toward_goal()?;
+    let axis = level.axis();
+    if axis[0] == dimension {
+        return Some(DemoStep::Move(Axis::X, dir));
+    }
+    if axis[1] == dimension {
+        return Some(DemoStep::Move(Axis::Y, dir));
+    }
+    let dir = rotation_toward(axis, level.dims_limit().len(), dimension);
+    Some(DemoStep::Rotate(Axis::Y, dir))
+}
+
+/// Which way to cycle the second axis to bring the hidden `target` into view. The second axis
+/// cycles through every dimension but the first's, so it reaches any hidden one, and this
+/// turns whichever way gets there in fewer rotations.
+fn rotation_toward(axis: [u8; 2], dimensions: usize, target: u8) -> Direction {
+    let linear = |dim: u8| if dim > axis[0] { dim - 1 } else { dim };
+    let count = dimensions as u8 - 1;
+    let forward = (linear(target) + count - linear(axis[1])) % count;
+    if forward <= count - forward {
+        Direction::Positive
+    } else {
+        Direction::Negative
+    }
+}
+
+/// Whether the demo is playing, and how long the menu has been left alone.
+pub struct DemoMode {
+    running: Option<DemoRun>,
+    idle: Duration,
+}
+
+struct DemoRun {
+    /// Started by the idle menu rather than the debug key, so stopping goes back to it.
+    from_menu: bool,
+    next_step: Timer,
+}
+
+impl Default for DemoMode {
+    fn default() -> Self {
+        Self {
+            running: None,
+            idle: Duration::ZERO,
+        }
+    }
+}
+
+impl DemoMode {
+    /// Whether the demo is playing, in which case the level being played isn't the player's.
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    fn start(&mut self, from_menu: bool) {
+        self.running = Some(DemoRun {
+            from_menu,
+            next_step: Timer::new(STEP_INTERVAL, true),
+        });
+    }
+}
+
+/// The maze the idle menu plays: small enough to solve in a minute, with a hidden dimension
+/// so it shows rotating too.
+fn menu_demo_level() -> LoadLevel {
+    LoadLevel {
+        dimensions: DimensionLength::Three([5, 5, 3]),
+        ..Default::default()
+    }
+}
+
+/// Starts the demo on its debug key, or once the menu has been idle long enough, and stops it
+/// on any real input. Runs after every device has triggered its actions and before the demo
+/// triggers its own, so it only sees the player's.
+pub fn demo_input(
+    mut demo: ResMut<DemoMode>,
+    mut app_state: ResMut<State<AppState>>,
+    mut input: ActionInputMut,
+    buttons: Res<Input<MouseButton>>,
+    time: Res<Time>,
+    mut load_level: EventWriter<LoadLevel>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    let pressed = input.any_just_pressed() || buttons.get_just_pressed().next().is_some();
+    if let Some(run) = &demo.running {
+        if !pressed {
+            return;
+        }
+        let from_menu = run.from_menu;
+        demo.running = None;
+        demo.idle = Duration::ZERO;
+        // The menu's maze was never the player's, so the press only brings the menu back.
+        if from_menu {
+            input.clear_all();
+            if *app_state.current() != AppState::MainMenu {
+                if let Err(e) = app_state.replace(AppState::MainMenu) {
+                    error!("Could not leave the demo: {}", e);
+                }
+            }
+        }
+        return;
+    }
+
+    match app_state.current() {
+        AppState::InMaze if input.clear_just_pressed(Action::StartDemo) => {
+            demo.start(false);
+            toasts.send(ShowToast::new(
+                ToastKind::Info,
+                "Demo playing, press any key to take over",
+            ));
+        }
+        AppState::MainMenu if !pressed => {
+            demo.idle += time.delta();
+            if demo.idle >= MENU_IDLE {
+                demo.idle = Duration::ZERO;
+                demo.start(true);
+                load_level.send(menu_demo_level());
+            }
+        }
+        _ => demo.idle = Duration::ZERO,
+    }
+}
+
+/// Gives the demo's next input every step interval, and moves on to a new maze once one is
+/// solved, runs out of time, or turns out to have no way to the goal.
+pub fn play_demo(
+    mut demo: ResMut<DemoMode>,
+    app_state: Res<State<AppState>>,
+    mut actions: ResMut<TriggeredActions>,
+    level: Option<Res<MazeLevel>>,
+    last_load: Option<Res<LastLoad>>,
+    time: Res<Time>,
+    mut load_level: EventWriter<LoadLevel>,
+) {
+    let run = match demo.running.as_mut() {
+        Some(run) => run,
+        None => return,
+    };
+    if !run.next_step.tick(time.delta()).just_finished() {
+        return;
+    }
+    let regenerate = || match (&last_load, &level) {
+        (Some(last_load), Some(level)) => last_load.regenerate(level),
+        _ => menu_demo_level(),
+    };
+    match app_state.current() {
+        AppState::InMaze => match level.as_deref().and_then(|level| next_step(&**level)) {
+            Some(step) => actions.trigger(step.action()),
+            None => load_level.send(regenerate()),
+        },
+        AppState::Completed | AppState::Failed => load_level.send(regenerate()),
+        // A load that failed lands back on the menu, where there is nothing left to play.
+        AppState::MainMenu => demo.running = None,
+        AppState::Loading | AppState::Paused => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::input::{resolve_move, MoveOutcome};
+    use rand::prelude::*;
+
+    #[test]
+    fn steps_follow_the_shortest_path_to_the_goal() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let mut level = MazeLevel::from_maze(maze::Maze::new(&[4, 4, 3, 3], &mut rng));
+        let mut moves = 0;
+        for _ in 0..1000 {
+            match next_step(&*level) {
+                Some(DemoStep::Move(axis, dir)) => {
+                    assert!(matches!(
+                        resolve_move(&mut *level, axis, dir),
+                        MoveOutcome::Moved(_)
+                    ));
+                    moves += 1;
+                }
+                Some(DemoStep::Rotate(axis, dir)) => level.shift_axis(axis, dir),
+                None => break,
+            }
+        }
+        assert!(level.at_goal());
+        assert_eq!(moves, level.optimal_moves());
+    }
+
+    #[test]
+    fn rotations_take_the_shorter_way_round() {
+        // With dimension 0 on the first axis, the second cycles through 1, 2, 3 and 4.
+        assert_eq!(rotation_toward([0, 1], 5, 2), Direction::Positive);
+        assert_eq!(rotation_toward([0, 1], 5, 4), Direction::Negative);
+        assert_eq!(rotation_toward([2, 4], 5, 1), Direction::Positive);
+        assert_eq!(rotation_toward([2, 4], 5, 3), Direction::Negative);
+        assert_eq!(rotation_toward([1, 0], 3, 2), Direction::Positive);
+    }
+
+    #[test]
+    fn steps_press_the_players_actions() {
+        assert_eq!(
+            DemoStep::Move(Axis::X, Direction::Positive).action(),
+            Action::MoveNorth
+        );
+        assert_eq!(
+            DemoStep::Move(Axis::Y, Direction::Negative).action(),
+            Action::MoveWest
+        );
+        assert_eq!(
+            DemoStep::Rotate(Axis::Y, Direction::Positive).action(),
+            Action::RotateSecondaryForward
+        );
+    }
+}
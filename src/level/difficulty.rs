@@ -0,0 +1,291 @@
+use std::{collections::BTreeMap, fmt, fs, str::FromStr};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    demo::DemoMode,
+    loader::{DimensionLength, LastLoad, LoadLevel, RngSource},
+    maze_renderer::FogOfWar,
+    save::{write_ron, SaveLocation},
+    stats::{BestStats, LevelStats},
+};
+
+const BESTS_FILE: &str = "bests.ron";
+
+/// How hard a maze is, from a preset shape up to explicit lengths.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Nightmare,
+    Custom(DimensionLength),
+}
+
+impl Difficulty {
+    pub const PRESETS: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Medium,
+        Difficulty::Hard,
+        Difficulty::Nightmare,
+    ];
+
+    pub fn to_dimensions(&self) -> DimensionLength {
+        match self {
+            Difficulty::Easy => DimensionLength::Two([8, 8]),
+            Difficulty::Medium => DimensionLength::Three([6, 6, 4]),
+            Difficulty::Hard => DimensionLength::Four([5, 5, 4, 3]),
+            Difficulty::Nightmare => DimensionLength::Five([4, 4, 3, 3, 3]),
+            Difficulty::Custom(dimensions) => dimensions.clone(),
+        }
+    }
+
+    /// Easier presets open loops, so a wrong turn isn't always a dead end.
+    pub fn braid(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.3,
+            Difficulty::Medium => 0.1,
+            Difficulty::Hard | Difficulty::Nightmare | Difficulty::Custom(_) => 0.0,
+        }
+    }
+
+    pub fn fog(&self) -> FogOfWar {
+        match self {
+            Difficulty::Easy => FogOfWar::Off,
+            Difficulty::Medium | Difficulty::Hard | Difficulty::Custom(_) => FogOfWar::Dim,
+            Difficulty::Nightmare => FogOfWar::Hide,
+        }
+    }
+
+    /// The preset's name, which best times are kept under, or `None` for custom lengths.
+    pub fn preset_name(&self) -> Option<&'static str> {
+        match self {
+            Difficulty::Easy => Some("easy"),
+            Difficulty::Medium => Some("medium"),
+            Difficulty::Hard => Some("hard"),
+            Difficulty::Nightmare => Some("nightmare"),
+            Difficulty::Custom(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.preset_name() {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "custom"),
+        }
+    }
+}
+
+/// A difficulty that isn't a preset name or lengths like `6x6x4`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnknownDifficulty(pub String);
+
+impl fmt::Display for UnknownDifficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown difficulty '{}', expected easy, medium, hard, nightmare or lengths like 6x6x4",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for UnknownDifficulty {}
+
+impl FromStr for Difficulty {
+    type Err = UnknownDifficulty;
+
+    /// Parses a preset name, or custom lengths separated by `x`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unknown = || UnknownDifficulty(s.to_string());
+        if let Some(preset) = Difficulty::PRESETS
+            .into_iter()
+            .find(|preset| preset.preset_name() == Some(&s.to_ascii_lowercase()))
+        {
+            return Ok(preset);
+        }
+        let lengths = s
+            .split('x')
+            .map(|length| match length.trim().parse::<u16>() {
+                Ok(length) if length > 0 => Ok(length),
+                _ => Err(unknown()),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        DimensionLength::from_lengths(&lengths)
+            .map(Difficulty::Custom)
+            .ok_or_else(unknown)
+    }
+}
+
+impl LoadLevel {
+    /// A new maze at `difficulty`, with the preset's shape and braid factor.
+    pub fn from_difficulty(difficulty: Difficulty, rng_source: RngSource) -> Self {
+        LoadLevel {
+            rng_source,
+            dimensions: difficulty.to_dimensions(),
+            braid: difficulty.braid(),
+            difficulty: Some(difficulty),
+            ..Default::default()
+        }
+    }
+}
+
+/// The best run on each preset, persisted between launches.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DifficultyBests(pub BTreeMap<String, BestStats>);
+
+impl DifficultyBests {
+    pub fn get(&self, difficulty: &Difficulty) -> Option<BestStats> {
+        self.0.get(difficulty.preset_name()?).copied()
+    }
+
+    /// Records a completed run, returning whether it was on a preset.
+    pub fn record(&mut self, difficulty: &Difficulty, stats: &LevelStats) -> bool {
+        let name = match difficulty.preset_name() {
+            Some(name) => name,
+            None => return false,
+        };
+        let best = BestStats::improve(self.get(difficulty), stats);
+        self.0.insert(name.to_string(), best);
+        true
+    }
+}
+
+pub fn load_difficulty_bests(mut c: Commands, location: Res<SaveLocation>) {
+    let bests = location
+        .sibling(BESTS_FILE)
+        .and_then(|path| {
+            let text = fs::read_to_string(&path).ok()?;
+            ron::from_str(&text)
+                .map_err(|e| warn!("Ignoring best times at {}: {}", path.display(), e))
+                .ok()
+        })
+        .unwrap_or_default();
+    c.insert_resource::<DifficultyBests>(bests);
+}
+
+/// Switches to the fog of war the level's difficulty calls for, or the usual fog for a level
+/// without one, so a preset's fog doesn't carry over to the next level.
+pub fn apply_difficulty_fog(last_load: Option<Res<LastLoad>>, mut fog: ResMut<FogOfWar>) {
+    *fog = last_load
+        .and_then(|last_load| last_load.0.difficulty.as_ref().map(Difficulty::fog))
+        .unwrap_or_default();
+}
+
+/// Records a completed preset before the completion screen is drawn from it.
+pub fn record_difficulty_best(
+    mut bests: ResMut<DifficultyBests>,
+    last_load: Option<Res<LastLoad>>,
+    stats: Res<LevelStats>,
+    location: Res<SaveLocation>,
+    demo: Res<DemoMode>,
+) {
+    let difficulty = match last_load.and_then(|last_load| last_load.0.difficulty.clone()) {
+        Some(difficulty) if !demo.is_running() => difficulty,
+        _ => return,
+    };
+    if !bests.record(&difficulty, &stats) {
+        return;
+    }
+    if let Some(path) = location.sibling(BESTS_FILE) {
+        if let Err(e) = write_ron(&path, &*bests) {
+            error!("Could not save best times: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn presets_map_to_their_shapes() {
+        let lengths = |difficulty: Difficulty| difficulty.to_dimensions().lengths().to_vec();
+        assert_eq!(lengths(Difficulty::Easy), [8, 8]);
+        assert_eq!(lengths(Difficulty::Medium), [6, 6, 4]);
+        assert_eq!(lengths(Difficulty::Hard), [5, 5, 4, 3]);
+        assert_eq!(lengths(Difficulty::Nightmare), [4, 4, 3, 3, 3]);
+        assert_eq!(
+            lengths(Difficulty::Custom(DimensionLength::Six([2; 6]))),
+            [2; 6]
+        );
+
+        assert_eq!(Difficulty::Easy.fog(), FogOfWar::Off);
+        assert_eq!(Difficulty::Nightmare.fog(), FogOfWar::Hide);
+        assert!(Difficulty::Easy.braid() > Difficulty::Hard.braid());
+
+        let level = LoadLevel::from_difficulty(Difficulty::Medium, RngSource::Seeded(3));
+        assert_eq!(level.dimensions.lengths(), [6, 6, 4]);
+        assert_eq!(level.braid, Difficulty::Medium.braid());
+        assert!(matches!(level.difficulty, Some(Difficulty::Medium)));
+    }
+
+    #[test]
+    fn difficulties_parse_from_names_and_lengths() {
+        assert!(matches!("hard".parse(), Ok(Difficulty::Hard)));
+        assert!(matches!("Nightmare".parse(), Ok(Difficulty::Nightmare)));
+        assert!(matches!(
+            "4x15x2".parse(),
+            Ok(Difficulty::Custom(DimensionLength::Three([4, 15, 2])))
+        ));
+        assert!(matches!(
+            "1000x3".parse(),
+            Ok(Difficulty::Custom(DimensionLength::Two([1000, 3])))
+        ));
+        for invalid in ["impossible", "4", "4x0", "4x4x4x4x4x4x4", ""] {
+            assert_eq!(
+                invalid.parse::<Difficulty>().err(),
+                Some(UnknownDifficulty(invalid.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn bests_are_kept_per_preset() {
+        let stats = |steps, seconds| LevelStats {
+            steps,
+            elapsed: Duration::from_secs(seconds),
+            ..Default::default()
+        };
+        let mut bests = DifficultyBests::default();
+        assert!(bests.record(&Difficulty::Easy, &stats(30, 20)));
+        assert!(bests.record(&Difficulty::Easy, &stats(25, 40)));
+        assert!(bests.record(&Difficulty::Hard, &stats(90, 120)));
+        assert!(!bests.record(
+            &Difficulty::Custom(DimensionLength::Two([3, 3])),
+            &stats(1, 1)
+        ));
+        assert_eq!(
+            bests.get(&Difficulty::Easy),
+            Some(BestStats {
+                steps: 25,
+                elapsed: Duration::from_secs(20)
+            })
+        );
+        assert_eq!(bests.get(&Difficulty::Hard).unwrap().steps, 90);
+        assert_eq!(bests.get(&Difficulty::Medium), None);
+        assert_eq!(bests.0.len(), 2);
+    }
+
+    #[test]
+    fn a_level_without_a_preset_clears_the_last_ones_fog() {
+        let mut world = World::new();
+        world.insert_resource(FogOfWar::Off);
+        world.insert_resource(LastLoad(LoadLevel::from_difficulty(
+            Difficulty::Nightmare,
+            RngSource::Entropy,
+        )));
+        let mut stage = SystemStage::single(apply_difficulty_fog);
+        stage.run(&mut world);
+        assert_eq!(*world.resource::<FogOfWar>(), FogOfWar::Hide);
+
+        world.insert_resource(LastLoad(LoadLevel::default()));
+        stage.run(&mut world);
+        assert_eq!(*world.resource::<FogOfWar>(), FogOfWar::default());
+    }
+}
@@ -0,0 +1,109 @@
+use std::{fmt, io, path::PathBuf};
+
+use bevy::ecs::schedule::StateError;
+use maze::MazeGenError;
+
+use super::{code::LevelCodeError, preflight::LoadEstimate};
+
+/// Failures from fallible operations in the level module.
+#[derive(Debug)]
+pub enum LevelError {
+    /// The app could not be moved into the maze state.
+    StateTransition(StateError),
+    /// A file could not be read from or written to disk.
+    Io { path: PathBuf, error: io::Error },
+    /// A maze or campaign file is not valid RON or doesn't describe what it should.
+    Parse(ron::Error),
+    /// A value could not be written out as RON.
+    Serialize(ron::Error),
+    /// A maze or save file was written with a layout this build doesn't understand.
+    UnsupportedVersion(u32),
+    /// A saved level code could not be read back.
+    InvalidCode(LevelCodeError),
+    /// A saved game's maze regenerated differently, so the save can't be put back on it.
+    SaveMismatch,
+    /// A saved game puts the player somewhere its maze doesn't have, for the given reason.
+    InvalidSave(&'static str),
+    /// The campaign file lists no levels.
+    EmptyCampaign,
+    /// A level of the campaign file can't be played, for the given reason.
+    InvalidCampaignLevel { index: usize, reason: &'static str },
+    /// The maze generator rejected the requested lengths.
+    Generation(MazeGenError),
+    /// The requested shape is beyond the configured `LoadLimits`.
+    TooLarge {
+        estimate: LoadEstimate,
+        suggestion: Option<Vec<u16>>,
+    },
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::StateTransition(e) => write!(f, "could not enter the maze: {}", e),
+            LevelError::Io { path, error } => {
+                write!(f, "could not access {}: {}", path.display(), error)
+            }
+            LevelError::Parse(e) => write!(f, "could not parse maze file: {}", e),
+            LevelError::Serialize(e) => write!(f, "could not write RON: {}", e),
+            LevelError::UnsupportedVersion(v) => {
+                write!(f, "file version {} is not supported", v)
+            }
+            LevelError::InvalidCode(e) => write!(f, "could not read the level code: {}", e),
+            LevelError::SaveMismatch => write!(
+                f,
+                "the saved maze no longer matches its level code, so it can't be continued"
+            ),
+            LevelError::InvalidSave(reason) => write!(f, "the saved game {}", reason),
+            LevelError::EmptyCampaign => write!(f, "the campaign has no levels"),
+            LevelError::InvalidCampaignLevel { index, reason } => {
+                write!(f, "campaign level {} {}", index + 1, reason)
+            }
+            LevelError::Generation(e) => write!(f, "could not generate the maze: {}", e),
+            LevelError::TooLarge {
+                estimate,
+                suggestion: Some(shape),
+            } => write!(f, "maze is too large ({}), try {:?}", estimate, shape),
+            LevelError::TooLarge {
+                estimate,
+                suggestion: None,
+            } => write!(f, "maze is too large ({})", estimate),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LevelError::StateTransition(e) => Some(e),
+            LevelError::Io { error, .. } => Some(error),
+            LevelError::Parse(e) | LevelError::Serialize(e) => Some(e),
+            LevelError::Generation(e) => Some(e),
+            LevelError::InvalidCode(e) => Some(e),
+            LevelError::UnsupportedVersion(_)
+            | LevelError::SaveMismatch
+            | LevelError::InvalidSave(_)
+            | LevelError::EmptyCampaign
+            | LevelError::InvalidCampaignLevel { .. }
+            | LevelError::TooLarge { .. } => None,
+        }
+    }
+}
+
+impl From<StateError> for LevelError {
+    fn from(e: StateError) -> Self {
+        LevelError::StateTransition(e)
+    }
+}
+
+impl From<ron::Error> for LevelError {
+    fn from(e: ron::Error) -> Self {
+        LevelError::Parse(e)
+    }
+}
+
+impl From<MazeGenError> for LevelError {
+    fn from(e: MazeGenError) -> Self {
+        LevelError::Generation(e)
+    }
+}
@@ -0,0 +1,171 @@
+use std::{collections::HashMap, time::Duration};
+
+use bevy::prelude::*;
+
+use super::bindings::{Action, TriggeredActions};
+
+/// How far the stick has to lean before it counts as pointing somewhere.
+const DEADZONE: f32 = 0.5;
+/// How long a direction has to be held before it starts repeating.
+const REPEAT_DELAY: Duration = Duration::from_millis(500);
+/// Time between repeated moves while a direction stays held.
+const REPEAT_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Buttons that trigger an action when pressed.
+const BUTTONS: [(GamepadButtonType, Action); 10] = [
+    (GamepadButtonType::LeftTrigger, Action::RotatePrimaryBack),
+    (
+        GamepadButtonType::RightTrigger,
+        Action::RotatePrimaryForward,
+    ),
+    (GamepadButtonType::LeftTrigger2, Action::RotateSecondaryBack),
+    (
+        GamepadButtonType::RightTrigger2,
+        Action::RotateSecondaryForward,
+    ),
+    (GamepadButtonType::North, Action::Hint),
+    (GamepadButtonType::West, Action::Undo),
+    (GamepadButtonType::Select, Action::ToggleTrail),
+    (GamepadButtonType::Start, Action::Pause),
+    (GamepadButtonType::South, Action::Confirm),
+    (GamepadButtonType::RightThumb, Action::RecenterCamera),
+];
+
+/// The repeat state of each connected gamepad's stick.
+#[derive(Default)]
+pub struct GamepadSticks {
+    sticks: HashMap<Gamepad, StickRepeat>,
+}
+
+/// Turns a held direction into discrete moves: one when it is first pushed, then more at
+/// `REPEAT_INTERVAL` once it has been held for `REPEAT_DELAY`.
+#[derive(Default)]
+struct StickRepeat {
+    held: Option<Action>,
+    until_repeat: Duration,
+}
+
+impl StickRepeat {
+    fn update(&mut self, direction: Option<Action>, delta: Duration) -> Option<Action> {
+        if direction != self.held {
+            self.held = direction;
+            self.until_repeat = REPEAT_DELAY;
+            return direction;
+        }
+        let held = self.held?;
+        if delta < self.until_repeat {
+            self.until_repeat -= delta;
+            return None;
+        }
+        self.until_repeat = REPEAT_INTERVAL;
+        Some(held)
+    }
+}
+
+/// The move a stick or D-pad leaning by `x` and `y` points at, along its stronger axis.
+fn stick_direction(x: f32, y: f32) -> Option<Action> {
+    if x.abs().max(y.abs()) < DEADZONE {
+        None
+    } else if y.abs() >= x.abs() {
+        Some(if y > 0.0 {
+            Action::MoveNorth
+        } else {
+            Action::MoveSouth
+        })
+    } else {
+        Some(if x > 0.0 {
+            Action::MoveEast
+        } else {
+            Action::MoveWest
+        })
+    }
+}
+
+/// Triggers this frame's gamepad actions. Runs right after Bevy reads input, so every level
+/// system sees the same actions.
+pub fn read_gamepads(
+    time: Res<Time>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut events: EventReader<GamepadEvent>,
+    mut sticks: ResMut<GamepadSticks>,
+    mut actions: ResMut<TriggeredActions>,
+) {
+    for GamepadEvent(gamepad, event) in events.iter() {
+        match event {
+            GamepadEventType::Connected => info!("Gamepad {} connected", gamepad.0),
+            GamepadEventType::Disconnected => {
+                info!("Gamepad {} disconnected", gamepad.0);
+                sticks.sticks.remove(gamepad);
+            }
+            _ => {}
+        }
+    }
+
+    for gamepad in gamepads.iter() {
+        for (button, action) in BUTTONS {
+            if buttons.just_pressed(GamepadButton(*gamepad, button)) {
+                actions.trigger(action);
+            }
+        }
+
+        let axis = |axis| axes.get(GamepadAxis(*gamepad, axis)).unwrap_or(0.0);
+        let button = |button| {
+            if buttons.pressed(GamepadButton(*gamepad, button)) {
+                1.0
+            } else {
+                0.0
+            }
+        };
+        let x = axis(GamepadAxisType::LeftStickX)
+            + axis(GamepadAxisType::DPadX)
+            + button(GamepadButtonType::DPadRight)
+            - button(GamepadButtonType::DPadLeft);
+        let y = axis(GamepadAxisType::LeftStickY)
+            + axis(GamepadAxisType::DPadY)
+            + button(GamepadButtonType::DPadUp)
+            - button(GamepadButtonType::DPadDown);
+        let stick = sticks.sticks.entry(*gamepad).or_default();
+        if let Some(action) = stick.update(stick_direction(x, y), time.delta()) {
+            actions.trigger(action);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stick_points_along_its_stronger_axis() {
+        assert_eq!(stick_direction(0.2, -0.3), None);
+        assert_eq!(stick_direction(0.1, 0.9), Some(Action::MoveNorth));
+        assert_eq!(stick_direction(-0.8, 0.6), Some(Action::MoveWest));
+        assert_eq!(stick_direction(0.7, -0.7), Some(Action::MoveSouth));
+    }
+
+    #[test]
+    fn held_stick_moves_once_then_repeats_after_a_delay() {
+        let frame = Duration::from_millis(100);
+        let mut stick = StickRepeat::default();
+        let mut moves = Vec::new();
+        for _ in 0..10 {
+            moves.push(stick.update(Some(Action::MoveEast), frame));
+        }
+        let east = Some(Action::MoveEast);
+        assert_eq!(
+            moves,
+            [east, None, None, None, None, east, None, east, None, east]
+        );
+
+        // Letting go and flicking again moves straight away.
+        assert_eq!(stick.update(None, frame), None);
+        assert_eq!(stick.update(None, frame), None);
+        assert_eq!(stick.update(Some(Action::MoveEast), frame), east);
+        assert_eq!(
+            stick.update(Some(Action::MoveNorth), frame),
+            Some(Action::MoveNorth)
+        );
+    }
+}
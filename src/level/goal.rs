@@ -0,0 +1,287 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use super::{
+    bindings::{Action, ActionInput},
+    campaign::Campaign,
+    difficulty::{Difficulty, DifficultyBests},
+    loader::{LastLoad, LoadLevel},
+    maze_level::{MazeLevel, PositionChanged},
+    stats::{format_elapsed, LevelStats},
+    time_trial::Leaderboard,
+};
+use crate::{
+    toast::{ShowToast, ToastKind},
+    AppState,
+};
+
+/// Sent once the player reaches the goal cell.
+#[derive(Clone, Debug)]
+pub struct MazeCompleted;
+
+#[derive(Component)]
+pub struct CompletionScreen;
+
+#[derive(Component, Clone, Copy)]
+pub enum CompletionButton {
+    Replay,
+    NextLevel,
+    Menu,
+}
+
+pub fn detect_completion(
+    level: Res<MazeLevel>,
+    mut app_state: ResMut<State<AppState>>,
+    mut position_changed: EventReader<PositionChanged>,
+    mut completed: EventWriter<MazeCompleted>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if position_changed.iter().last().is_none() || !level.at_goal() {
+        return;
+    }
+    completed.send(MazeCompleted);
+    toasts.send(ShowToast::new(ToastKind::Info, "Maze completed!"));
+    // Pushing over InMaze stops its systems, which freezes movement and the timer.
+    if let Err(e) = app_state.push(AppState::Completed) {
+        error!("Could not complete the maze: {}", e);
+    }
+}
+
+/// The lines of the completion screen, between its title and buttons.
+fn completion_lines(stats: &LevelStats, optimal_moves: usize, coverage: f32) -> Vec<String> {
+    vec![
+        format!("{} steps, optimal {}", stats.steps, optimal_moves),
+        format!("explored {:.0}%", coverage * 100.0),
+        format!("time {}", format_elapsed(stats.elapsed)),
+        format!("{} blocked moves", stats.blocked_moves),
+        format!("{} axis rotations", stats.axis_rotations),
+        format!("{} hints used", stats.hints_used),
+        format!("{} undos used", stats.undos_used),
+    ]
+}
+
+/// The difficulty played and the best run on it, including the one just finished.
+fn difficulty_line(difficulty: &Difficulty, bests: &DifficultyBests) -> String {
+    match bests.get(difficulty) {
+        Some(best) => format!(
+            "{}, best {} steps in {}",
+            difficulty,
+            best.steps,
+            format_elapsed(best.elapsed)
+        ),
+        None => difficulty.to_string(),
+    }
+}
+
+/// The summary shown after the campaign's last level.
+fn campaign_summary(campaign: &Campaign) -> Vec<String> {
+    vec![
+        "Campaign completed".to_string(),
+        format!(
+            "{} levels, {} steps in total",
+            campaign.len(),
+            campaign.progress.total_steps
+        ),
+        format!(
+            "total time {}",
+            format_elapsed(campaign.progress.total_elapsed)
+        ),
+    ]
+}
+
+/// Everything kept across runs that the completion screen reports on.
+#[derive(SystemParam)]
+pub struct Records<'w, 's> {
+    campaign: Res<'w, Campaign>,
+    bests: Res<'w, DifficultyBests>,
+    leaderboard: Res<'w, Leaderboard>,
+    #[system_param(ignore)]
+    _marker: std::marker::PhantomData<&'s ()>,
+}
+
+pub fn spawn_completion_screen(
+    mut c: Commands,
+    assets: Res<AssetServer>,
+    level: Res<MazeLevel>,
+    stats: Res<LevelStats>,
+    records: Records,
+    last_load: Option<Res<LastLoad>>,
+) {
+    let campaign = &records.campaign;
+    let campaign_level = last_load
+        .as_ref()
+        .and_then(|last_load| last_load.0.campaign_level);
+    let trial_code = last_load
+        .as_ref()
+        .filter(|last_load| last_load.0.time_trial)
+        .and_then(|last_load| last_load.code());
+    let difficulty = last_load.and_then(|last_load| last_load.0.difficulty.clone());
+    let style = TextStyle {
+        font: assets.load("fonts/UnicaOne-Regular.ttf"),
+        font_size: 40.0,
+        color: Color::WHITE,
+    };
+    let text = |c: &mut ChildBuilder, value: String| {
+        c.spawn_bundle(TextBundle {
+            text: Text::with_section(value, style.clone(), Default::default()),
+            style: Style {
+                margin: Rect::all(Val::Px(5.0)),
+                ..default()
+            },
+            ..default()
+        });
+    };
+    c.spawn_bundle(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            flex_direction: FlexDirection::ColumnReverse,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+        ..default()
+    })
+    .insert(CompletionScreen)
+    .insert(Name::new("completion screen"))
+    .with_children(|c| {
+        text(c, "Maze completed".to_string());
+        for line in completion_lines(&stats, level.optimal_moves(), level.coverage()) {
+            text(c, line);
+        }
+        if let Some(difficulty) = &difficulty {
+            text(c, difficulty_line(difficulty, &records.bests));
+        }
+        if let Some(code) = &trial_code {
+            for line in records.leaderboard.lines(code) {
+                text(c, line);
+            }
+        }
+        let mut buttons = vec![("Replay", CompletionButton::Replay)];
+        match campaign_level {
+            Some(index) if index + 1 < campaign.len() => {
+                buttons.push(("Next level", CompletionButton::NextLevel));
+            }
+            Some(_) => {
+                for line in campaign_summary(campaign) {
+                    text(c, line);
+                }
+            }
+            None => {}
+        }
+        buttons.push(("Return to menu", CompletionButton::Menu));
+        for (label, button) in buttons {
+            c.spawn_bundle(ButtonBundle {
+                style: Style {
+                    padding: Rect::all(Val::Px(5.0)),
+                    margin: Rect::all(Val::Px(5.0)),
+                    ..default()
+                },
+                color: Color::DARK_GRAY.into(),
+                ..default()
+            })
+            .insert(button)
+            .with_children(|c| {
+                c.spawn_bundle(TextBundle {
+                    text: Text::with_section(label, style.clone(), Default::default()),
+                    ..default()
+                });
+            });
+        }
+    });
+}
+
+pub fn despawn_completion_screen(mut c: Commands, screens: Query<Entity, With<CompletionScreen>>) {
+    for screen in screens.iter() {
+        c.entity(screen).despawn_recursive();
+    }
+}
+
+pub fn completion_input(
+    input: ActionInput,
+    last_load: Option<Res<LastLoad>>,
+    campaign: Res<Campaign>,
+    mut app_state: ResMut<State<AppState>>,
+    mut load_level: EventWriter<LoadLevel>,
+    buttons: Query<(&Interaction, &CompletionButton), Changed<Interaction>>,
+) {
+    let clicked = buttons
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Clicked)
+        .map(|(_, button)| *button);
+    let pressed = if input.just_pressed(Action::Confirm) {
+        Some(CompletionButton::Menu)
+    } else {
+        clicked
+    };
+
+    match pressed {
+        // Loading unwinds the state stack into the same maze again.
+        Some(CompletionButton::Replay) => {
+            if let Some(last_load) = last_load {
+                load_level.send(last_load.restart());
+            }
+        }
+        Some(CompletionButton::NextLevel) => {
+            let next = last_load
+                .and_then(|last_load| last_load.0.campaign_level)
+                .and_then(|index| campaign.load_level(index + 1));
+            if let Some(next) = next {
+                load_level.send(next);
+            }
+        }
+        Some(CompletionButton::Menu) => {
+            if let Err(e) = app_state.replace(AppState::MainMenu) {
+                error!("Could not return to the menu: {}", e);
+            }
+        }
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn completion_lists_every_stat() {
+        let stats = LevelStats {
+            steps: 14,
+            blocked_moves: 3,
+            axis_rotations: 2,
+            hints_used: 1,
+            undos_used: 4,
+            elapsed: Duration::from_secs(42),
+        };
+        assert_eq!(
+            completion_lines(&stats, 11, 0.254),
+            [
+                "14 steps, optimal 11",
+                "explored 25%",
+                "time 0:42",
+                "3 blocked moves",
+                "2 axis rotations",
+                "1 hints used",
+                "4 undos used",
+            ]
+        );
+    }
+
+    #[test]
+    fn completion_names_the_difficulty_and_its_best() {
+        let mut bests = DifficultyBests::default();
+        assert_eq!(difficulty_line(&Difficulty::Hard, &bests), "hard");
+        bests.record(
+            &Difficulty::Hard,
+            &LevelStats {
+                steps: 40,
+                elapsed: Duration::from_secs(65),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            difficulty_line(&Difficulty::Hard, &bests),
+            "hard, best 40 steps in 1:05"
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::{
+    bindings::{Action, ActionInput},
+    loader::MazeAssets,
+    maze_level::{AxisChanged, Direction, MazeLevel, PositionChanged},
+    maze_renderer::{cell_center, MazeRenderer, FLOOR_HEIGHT},
+    maze_ui_renderer::{position_label_color, MazePositionLabel},
+    stats::LevelStats,
+};
+
+/// How long a hint stays up before it fades back out.
+const HINT_DURATION: Duration = Duration::from_secs(3);
+/// How long each on and off phase lasts while an axis label flashes.
+const FLASH_PERIOD: f32 = 0.25;
+
+/// Where the next step toward the goal leads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HintTarget {
+    /// The next cell is in the displayed slice, here.
+    Cell([u16; 2]),
+    /// The next step is along this hidden dimension, so it has to be rotated into view first.
+    Dimension(u8),
+}
+
+/// The hint currently shown, if any.
+#[derive(Default)]
+pub struct HintState {
+    active: Option<ActiveHint>,
+}
+
+struct ActiveHint {
+    target: HintTarget,
+    highlight: Option<Entity>,
+    timer: Timer,
+}
+
+/// Works out where the next step toward the goal leads from the current position.
+pub fn hint_target(level: &MazeLevel) -> Option<HintTarget> {
+    let (dimension, dir) = level.step_toward_goal()?;
+    let shown = match level.axis().iter().position(|shown| *shown == dimension) {
+        Some(shown) => shown,
+        None => return Some(HintTarget::Dimension(dimension)),
+    };
+    let mut cell = level.pos();
+    cell[shown] = match dir {
+        Direction::Positive => cell[shown] + 1,
+        Direction::Negative => cell[shown] - 1,
+    };
+    Some(HintTarget::Cell(cell))
+}
+
+pub fn show_hint(
+    mut c: Commands,
+    input: ActionInput,
+    level: Res<MazeLevel>,
+    assets: Res<MazeAssets>,
+    mut hints: ResMut<HintState>,
+    mut stats: ResMut<LevelStats>,
+    roots: Query<Entity, With<MazeRenderer>>,
+) {
+    // A hint already up stays until it expires, rather than costing another one.
+    if !input.just_pressed(Action::Hint) || hints.active.is_some() {
+        return;
+    }
+    let target = match hint_target(&level) {
+        Some(target) => target,
+        None => return,
+    };
+
+    let highlight = match (target, roots.get_single()) {
+        (HintTarget::Cell(cell), Ok(root)) => {
+            // Raised above the start and goal markers so the hint draws over them.
+            let transform =
+                Transform::from_translation(cell_center(cell) + Vec3::Y * (FLOOR_HEIGHT + 0.02));
+            let highlight = c
+                .spawn_bundle(assets.hint(transform))
+                .insert(Name::new("hint"))
+                .id();
+            c.entity(root).add_child(highlight);
+            Some(highlight)
+        }
+        _ => None,
+    };
+    hints.active = Some(ActiveHint {
+        target,
+        highlight,
+        timer: Timer::new(HINT_DURATION, false),
+    });
+    stats.hints_used += 1;
+}
+
+/// Flashes an axis hint, and takes any hint down once it expires or the view changes.
+pub fn update_hint(
+    mut c: Commands,
+    time: Res<Time>,
+    level: Res<MazeLevel>,
+    mut hints: ResMut<HintState>,
+    mut position_changed: EventReader<PositionChanged>,
+    mut axis_changed: EventReader<AxisChanged>,
+    mut labels: Query<(&MazePositionLabel, &mut Text)>,
+) {
+    let moved = position_changed.iter().count() + axis_changed.iter().count() > 0;
+    let active = match &mut hints.active {
+        Some(active) => active,
+        None => return,
+    };
+    if moved || active.timer.tick(time.delta()).finished() {
+        end_hint(&mut c, &mut hints, &level, &mut labels);
+        return;
+    }
+    if let HintTarget::Dimension(dimension) = active.target {
+        let lit = (active.timer.elapsed_secs() / (2.0 * FLASH_PERIOD)).fract() < 0.5;
+        for (label, mut text) in labels.iter_mut() {
+            if label.dimension == dimension as usize {
+                if let Some(section) = text.sections.first_mut() {
+                    section.style.color = if lit {
+                        Color::GOLD
+                    } else {
+                        position_label_color(&level, label.dimension)
+                    };
+                }
+            }
+        }
+    }
+}
+
+/// Forgets the hint when the level is left, its highlight going with the rest of the level.
+pub fn reset_hint(mut hints: ResMut<HintState>) {
+    hints.active = None;
+}
+
+fn end_hint(
+    c: &mut Commands,
+    hints: &mut HintState,
+    level: &MazeLevel,
+    labels: &mut Query<(&MazePositionLabel, &mut Text)>,
+) {
+    let active = match hints.active.take() {
+        Some(active) => active,
+        None => return,
+    };
+    if let Some(highlight) = active.highlight {
+        c.entity(highlight).despawn_recursive();
+    }
+    if let HintTarget::Dimension(dimension) = active.target {
+        for (label, mut text) in labels.iter_mut() {
+            if label.dimension == dimension as usize {
+                if let Some(section) = text.sections.first_mut() {
+                    section.style.color = position_label_color(level, label.dimension);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::maze_level::Axis;
+    use rand::prelude::*;
+
+    #[test]
+    fn hint_follows_the_shortest_path() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let mut level = MazeLevel::from_maze(maze::Maze::new(&[4, 3, 2], &mut rng));
+        let optimal = level.optimal_moves();
+        let mut moves = 0;
+        for _ in 0..100 {
+            let target = match hint_target(&level) {
+                Some(target) => target,
+                None => break,
+            };
+            match target {
+                HintTarget::Cell(cell) => {
+                    let axis = if cell[0] != level.pos()[0] {
+                        Axis::X
+                    } else {
+                        Axis::Y
+                    };
+                    let dir = if *axis.get(&cell) > *axis.get(&level.pos()) {
+                        Direction::Positive
+                    } else {
+                        Direction::Negative
+                    };
+                    assert!(level.move_pos(axis, dir));
+                    assert_eq!(level.pos(), cell);
+                    moves += 1;
+                }
+                HintTarget::Dimension(dimension) => {
+                    assert!(!level.axis().contains(&dimension));
+                    level.shift_axis(Axis::Y, Direction::Positive);
+                }
+            }
+        }
+        assert!(level.at_goal());
+        assert_eq!(moves, optimal);
+    }
+}
@@ -0,0 +1,171 @@
+use bevy::prelude::*;
+
+use super::{
+    loader::{LastLoad, LevelEntity},
+    maze_level::MazeLevel,
+    stats::{format_elapsed, LevelStats},
+};
+
+/// Stable names for each axis index, so a slice can be described the same way every time.
+const AXIS_NAMES: [&str; 6] = ["X", "Y", "Z", "W", "V", "U"];
+
+#[derive(Component)]
+pub struct HudText;
+
+pub fn axis_name(axis: usize) -> &'static str {
+    AXIS_NAMES.get(axis).copied().unwrap_or("?")
+}
+
+pub fn spawn_hud(
+    mut c: Commands,
+    level: Res<MazeLevel>,
+    stats: Res<LevelStats>,
+    last_load: Option<Res<LastLoad>>,
+    assets: Res<AssetServer>,
+) {
+    let code = last_load.and_then(|last_load| last_load.code());
+    c.spawn_bundle(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                right: Val::Px(10.0),
+                top: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    })
+    .insert(LevelEntity)
+    .insert(Name::new("hud"))
+    .with_children(|c| {
+        c.spawn_bundle(TextBundle {
+            text: Text::with_section(
+                hud_text(&level, &stats, code.as_deref()),
+                TextStyle {
+                    font: assets.load("fonts/UnicaOne-Regular.ttf"),
+                    font_size: 24.0,
+                    color: Color::WHITE,
+                },
+                Default::default(),
+            ),
+            ..default()
+        })
+        .insert(HudText);
+    });
+}
+
+/// Refreshes the text whenever the level or its stats change, which includes the timer.
+pub fn update_hud(
+    level: Res<MazeLevel>,
+    stats: Res<LevelStats>,
+    last_load: Option<Res<LastLoad>>,
+    mut query: Query<&mut Text, With<HudText>>,
+) {
+    let load_changed = last_load
+        .as_ref()
+        .is_some_and(|last_load| last_load.is_changed());
+    if !level.is_changed() && !stats.is_changed() && !load_changed {
+        return;
+    }
+    let code = last_load.and_then(|last_load| last_load.code());
+    let value = hud_text(&level, &stats, code.as_deref());
+    for mut text in query.iter_mut() {
+        if let Some(section) = text.sections.first_mut() {
+            // Only touched when the text differs, as the timer changes far less than once a frame.
+            if section.value != value {
+                section.value = value.clone();
+            }
+        }
+    }
+}
+
+fn hud_text(level: &MazeLevel, stats: &LevelStats, code: Option<&str>) -> String {
+    let mut text = format!(
+        "{}\n{}",
+        describe(
+            level.dims(),
+            level.dims_limit(),
+            level.axis(),
+            level.optimal_moves(),
+            level.seed(),
+        ),
+        describe_stats(stats)
+    );
+    if let Some(code) = code {
+        text.push_str("\ncode ");
+        text.push_str(code);
+    }
+    text
+}
+
+/// Live progress through the level, kept apart from `describe` so the layout there stays put.
+fn describe_stats(stats: &LevelStats) -> String {
+    format!(
+        "steps {}  time {}",
+        stats.steps,
+        format_elapsed(stats.elapsed)
+    )
+}
+
+fn describe(
+    position: &[u16],
+    lengths: &[u16],
+    axis: [u8; 2],
+    optimal_moves: usize,
+    seed: Option<u64>,
+) -> String {
+    let position = position
+        .iter()
+        .enumerate()
+        .map(|(dim, p)| format!("{} {}", axis_name(dim), p))
+        .collect::<Vec<_>>()
+        .join("  ");
+    let lengths = lengths
+        .iter()
+        .map(|l| l.to_string())
+        .collect::<Vec<_>>()
+        .join(" x ");
+    let seed = match seed {
+        Some(seed) => format!("seed {}", seed),
+        None => "loaded from file".to_string(),
+    };
+    format!(
+        "{}\nviewing axes {} x {}\nsize {}\noptimal: {} moves\n{}",
+        position,
+        axis_name(axis[0] as usize),
+        axis_name(axis[1] as usize),
+        lengths,
+        optimal_moves,
+        seed
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describe_lists_every_dimension() {
+        assert_eq!(
+            describe(&[1, 0, 3, 2, 0], &[4, 4, 5, 3, 2], [1, 3], 31, Some(42)),
+            "X 1  Y 0  Z 3  W 2  V 0\nviewing axes Y x W\nsize 4 x 4 x 5 x 3 x 2\n\
+             optimal: 31 moves\nseed 42"
+        );
+        assert_eq!(
+            describe(&[0, 0], &[2, 2], [0, 1], 2, None),
+            "X 0  Y 0\nviewing axes X x Y\nsize 2 x 2\noptimal: 2 moves\nloaded from file"
+        );
+    }
+
+    #[test]
+    fn describe_stats_shows_steps_and_time() {
+        let stats = LevelStats {
+            steps: 12,
+            elapsed: std::time::Duration::from_secs(75),
+            ..Default::default()
+        };
+        assert_eq!(describe_stats(&stats), "steps 12  time 1:15");
+    }
+}
@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+
+use super::{
+    loader::{MazeAssets, Player},
+    maze_level::{AxisChanged, Direction, MazeLevel, PositionChanged},
+};
+
+/// Space between the indicator columns of neighboring hidden dimensions.
+const COLUMN_SPACING: f32 = 0.2;
+/// Height of the indicators above the player, which is centered on the floor of its cell.
+const INDICATOR_HEIGHT: f32 = 0.9;
+
+/// A pip on the player showing whether it can step along a hidden dimension.
+#[derive(Component)]
+pub struct HiddenMoveIndicator;
+
+/// Every move along a dimension that isn't displayed, and whether its wall is open.
+fn hidden_moves(level: &MazeLevel) -> Vec<(u8, Direction, bool)> {
+    let axis = level.axis();
+    (0..level.dimension_count() as u8)
+        .filter(|dim| !axis.contains(dim))
+        .flat_map(|dim| {
+            [Direction::Negative, Direction::Positive]
+                .map(|dir| (dim, dir, level.can_move(dim, dir) == Some(true)))
+        })
+        .collect()
+}
+
+/// Where a hidden dimension's pip sits: one column per dimension, positive above negative.
+fn indicator_transform(column: usize, columns: usize, dir: Direction) -> Transform {
+    let x = (column as f32 - (columns as f32 - 1.0) / 2.0) * COLUMN_SPACING;
+    let y = match dir {
+        Direction::Positive => INDICATOR_HEIGHT + COLUMN_SPACING / 2.0,
+        Direction::Negative => INDICATOR_HEIGHT - COLUMN_SPACING / 2.0,
+    };
+    Transform::from_xyz(x, y, 0.0)
+}
+
+/// Respawns the pips on the player whenever the cell or displayed slice changes, showing only
+/// the moves that are open.
+pub fn update_indicators(
+    mut c: Commands,
+    level: Res<MazeLevel>,
+    assets: Res<MazeAssets>,
+    players: Query<Entity, With<Player>>,
+    indicators: Query<Entity, With<HiddenMoveIndicator>>,
+    mut position_changed: EventReader<PositionChanged>,
+    mut axis_changed: EventReader<AxisChanged>,
+) {
+    if axis_changed.iter().count() + position_changed.iter().count() == 0 {
+        return;
+    }
+    for indicator in indicators.iter() {
+        c.entity(indicator).despawn_recursive();
+    }
+
+    let moves = hidden_moves(&level);
+    let columns = moves.len() / 2;
+    for player in players.iter() {
+        c.entity(player).with_children(|c| {
+            for (i, (dim, dir, open)) in moves.iter().enumerate() {
+                c.spawn_bundle(PbrBundle {
+                    visibility: Visibility { is_visible: *open },
+                    ..assets.pip(*dim as usize, indicator_transform(i / 2, columns, *dir))
+                })
+                .insert(HiddenMoveIndicator)
+                .insert(Name::new(format!("indicator[{}]{:?}", dim, dir)));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::maze_level::Axis;
+    use rand::prelude::*;
+
+    #[test]
+    fn hidden_moves_cover_both_directions_of_hidden_dimensions() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let mut level = MazeLevel::from_maze(maze::Maze::new(&[3, 3, 3, 3], &mut rng));
+        let moves = hidden_moves(&level);
+        let dims: Vec<u8> = moves.iter().map(|(dim, _, _)| *dim).collect();
+        assert_eq!(dims, [2, 2, 3, 3]);
+        // Nothing is below the origin.
+        assert!(moves
+            .iter()
+            .filter(|(_, dir, _)| *dir == Direction::Negative)
+            .all(|(_, _, open)| !open));
+        for (dim, dir, open) in moves {
+            assert_eq!(level.can_move(dim, dir) == Some(true), open);
+        }
+
+        level.shift_axis(Axis::X, Direction::Positive);
+        let dims: Vec<u8> = hidden_moves(&level)
+            .iter()
+            .map(|(dim, _, _)| *dim)
+            .collect();
+        assert_eq!(dims, [0, 0, 3, 3]);
+    }
+
+    #[test]
+    fn indicators_stack_positive_above_negative() {
+        let up = indicator_transform(0, 2, Direction::Positive).translation;
+        let down = indicator_transform(0, 2, Direction::Negative).translation;
+        let next = indicator_transform(1, 2, Direction::Positive).translation;
+        assert_eq!(up.x, down.x);
+        assert!(up.y > down.y);
+        assert_eq!(up.x, -next.x);
+    }
+}
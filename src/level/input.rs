@@ -1,54 +1,230 @@
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
 
+use super::bindings::{Action, ActionInput, ActionInputMut};
 use super::maze_level::*;
 use super::maze_level::{Axis, Direction};
+use super::repeat::KeyRepeat;
+use super::undo::{self, MoveHistory, Snapshot};
+use crate::AppState;
+
+/// Actions that cycle which dimension is displayed on each axis.
+pub(super) const AXIS_SHIFTS: [(Action, Axis, Direction); 4] = [
+    (Action::RotatePrimaryBack, Axis::X, Direction::Negative),
+    (Action::RotatePrimaryForward, Axis::X, Direction::Positive),
+    (Action::RotateSecondaryBack, Axis::Y, Direction::Negative),
+    (Action::RotateSecondaryForward, Axis::Y, Direction::Positive),
+];
+
+/// Actions that cycle the depth axis of the volume view.
+pub(super) const DEPTH_SHIFTS: [(Action, Direction); 2] = [
+    (Action::RotateDepthBack, Direction::Negative),
+    (Action::RotateDepthForward, Direction::Positive),
+];
+
+/// Actions that step along each displayed axis.
+pub(super) const MOVES: [(Action, Axis, Direction); 4] = [
+    (Action::MoveNorth, Axis::X, Direction::Positive),
+    (Action::MoveSouth, Axis::X, Direction::Negative),
+    (Action::MoveEast, Axis::Y, Direction::Positive),
+    (Action::MoveWest, Axis::Y, Direction::Negative),
+];
+
+/// What a single move input turned into, so every input yields exactly one event.
+#[derive(Debug)]
+pub enum MoveOutcome {
+    Moved(PositionChanged),
+    Blocked(MoveBlocked),
+}
+
+/// Applies one move input to the level.
+pub fn resolve_move(level: &mut dyn MazeView, axis: Axis, dir: Direction) -> MoveOutcome {
+    let dimension = *axis.get(&level.axis());
+    let can_move = level.can_move(dimension, dir);
+    let from = level.pos();
+    if level.move_pos(axis, dir) {
+        MoveOutcome::Moved(PositionChanged {
+            from,
+            to: level.pos(),
+            dimension: Some(dimension as usize),
+            positive: Some(dir == Direction::Positive),
+            cause: ChangeCause::Moved,
+        })
+    } else {
+        MoveOutcome::Blocked(MoveBlocked {
+            position: from,
+            dimension: dimension as usize,
+            positive: dir == Direction::Positive,
+            out_of_bounds: can_move.is_none(),
+        })
+    }
+}
+
+/// Everything navigating the level can announce.
+#[derive(SystemParam)]
+pub struct NavigationEvents<'w, 's> {
+    position: EventWriter<'w, 's, PositionChanged>,
+    blocked: EventWriter<'w, 's, MoveBlocked>,
+    axis: EventWriter<'w, 's, AxisChanged>,
+}
 
 pub fn level_navigation(
     level: Option<ResMut<MazeLevel>>,
-    keys: Res<Input<KeyCode>>,
-    mut position_event: EventWriter<PositionChanged>,
-    mut axis_event: EventWriter<AxisChanged>,
+    mut input: ActionInputMut,
+    mut events: NavigationEvents,
+    mut app_state: ResMut<State<AppState>>,
+    mut history: ResMut<MoveHistory>,
+    mut repeat: ResMut<KeyRepeat>,
+    time: Res<Time>,
 ) {
     if let Some(mut level) = level {
-        if keys.just_pressed(KeyCode::Q) {
-            level.shift_axis(Axis::X, Direction::Negative);
-            axis_event.send(AxisChanged { axis: level.axis() });
-        }
-        if keys.just_pressed(KeyCode::E) {
-            level.shift_axis(Axis::X, Direction::Positive);
-            axis_event.send(AxisChanged { axis: level.axis() });
-        }
-        if keys.just_pressed(KeyCode::Z) {
-            level.shift_axis(Axis::Y, Direction::Negative);
-            axis_event.send(AxisChanged { axis: level.axis() });
-        }
-        if keys.just_pressed(KeyCode::X) {
-            level.shift_axis(Axis::Y, Direction::Positive);
-            axis_event.send(AxisChanged { axis: level.axis() });
-        }
-        if keys.just_pressed(KeyCode::W) {
-            level.move_pos(Axis::X, Direction::Positive);
-            position_event.send(PositionChanged {
-                position: level.pos(),
-            });
-        }
-        if keys.just_pressed(KeyCode::S) {
-            level.move_pos(Axis::X, Direction::Negative);
-            position_event.send(PositionChanged {
-                position: level.pos(),
-            });
-        }
-        if keys.just_pressed(KeyCode::D) {
-            level.move_pos(Axis::Y, Direction::Positive);
-            position_event.send(PositionChanged {
-                position: level.pos(),
-            });
-        }
-        if keys.just_pressed(KeyCode::A) {
-            level.move_pos(Axis::Y, Direction::Negative);
-            position_event.send(PositionChanged {
-                position: level.pos(),
-            });
+        // Pausing skips this frame's moves, so nothing is sent that the paused systems would
+        // only see after resuming. The press is consumed so the pause menu doesn't see it too.
+        if input.clear_just_pressed(Action::Pause) {
+            if let Err(e) = app_state.push(AppState::Paused) {
+                error!("Could not pause: {}", e);
+            }
+            return;
+        }
+        if input.just_pressed(Action::Undo) {
+            if let Some((position, axis)) = undo::undo(&mut **level, &mut history) {
+                if let Some(axis) = axis {
+                    repeat.reset();
+                    events.axis.send(axis);
+                }
+                events.position.send(position);
+            }
+        }
+        for (action, axis, dir) in AXIS_SHIFTS {
+            if input.just_pressed(action) {
+                if history.undo_rotations {
+                    history.push(Snapshot::of(&**level));
+                }
+                let (from, from_axis) = (level.pos(), level.axis());
+                level.shift_axis(axis, dir);
+                repeat.reset();
+                events.axis.send(AxisChanged {
+                    from: from_axis,
+                    to: level.axis(),
+                    cause: ChangeCause::Rotated,
+                });
+                // The player sits somewhere else in the newly displayed plane.
+                events.position.send(PositionChanged::jump(
+                    from,
+                    level.pos(),
+                    ChangeCause::Rotated,
+                ));
+            }
+        }
+        // Only the volume view shows the depth, and it watches the level for changes.
+        for (action, dir) in DEPTH_SHIFTS {
+            if input.just_pressed(action) {
+                level.shift_depth(dir);
+            }
+        }
+        // A fresh press moves at once and takes over the repeat from any key already held.
+        let repeated = match MOVES
+            .iter()
+            .map(|(action, _, _)| *action)
+            .find(|action| input.just_pressed(*action))
+        {
+            Some(pressed) => {
+                repeat.press(pressed);
+                None
+            }
+            None => repeat.tick(time.delta(), |action| input.pressed(action)),
+        };
+        for (action, axis, dir) in MOVES {
+            if !input.just_pressed(action) && repeated != Some(action) {
+                continue;
+            }
+            let before = Snapshot::of(&**level);
+            match resolve_move(&mut **level, axis, dir) {
+                MoveOutcome::Moved(changed) => {
+                    history.push(before);
+                    events.position.send(changed);
+                }
+                MoveOutcome::Blocked(blocked) => {
+                    repeat.stall();
+                    events.blocked.send(blocked);
+                }
+            }
+        }
+    }
+}
+
+pub fn log_blocked_moves(mut blocked: EventReader<MoveBlocked>) {
+    for blocked in blocked.iter() {
+        let sign = if blocked.positive { '+' } else { '-' };
+        if blocked.out_of_bounds {
+            debug!(
+                "Move {}{} from {:?} leaves the maze",
+                sign, blocked.dimension, blocked.position
+            );
+        } else {
+            debug!(
+                "Move {}{} from {:?} hit a wall",
+                sign, blocked.dimension, blocked.position
+            );
+        }
+    }
+}
+
+pub fn log_slice_on_debug_key(level: Option<Res<MazeLevel>>, input: ActionInput) {
+    if let Some(level) = level {
+        if input.just_pressed(Action::DebugSlice) {
+            info!(
+                "Player at {:?}, viewing dimensions {:?}:\n{}",
+                level.dims(),
+                level.axis(),
+                level.render_slice_ascii()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn resolve_move_yields_one_outcome() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let mut level = MazeLevel::from_maze(maze::Maze::new(&[5, 5, 2], &mut rng));
+        for _ in 0..200 {
+            let (_, axis, dir) = MOVES[rng.gen_range(0..MOVES.len())];
+            let before = level.pos();
+            let dimension = *axis.get(&level.axis());
+            let expected = level.can_move(dimension, dir);
+
+            match resolve_move(&mut *level, axis, dir) {
+                MoveOutcome::Moved(changed) => {
+                    assert_eq!(expected, Some(true));
+                    assert_eq!(changed.from, before);
+                    assert_ne!(changed.to, before);
+                    assert_eq!(changed.to, level.pos());
+                    assert_eq!(changed.dimension, Some(dimension as usize));
+                    assert_eq!(changed.positive, Some(dir == Direction::Positive));
+                }
+                MoveOutcome::Blocked(blocked) => {
+                    assert_ne!(expected, Some(true));
+                    assert_eq!(blocked.out_of_bounds, expected.is_none());
+                    assert_eq!(blocked.position, before);
+                    assert_eq!(blocked.dimension, dimension as usize);
+                    assert_eq!(blocked.positive, dir == Direction::Positive);
+                    assert_eq!(level.pos(), before);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn resolve_move_flags_leaving_the_maze() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let mut level = MazeLevel::from_maze(maze::Maze::new(&[3, 3], &mut rng));
+        match resolve_move(&mut *level, Axis::X, Direction::Negative) {
+            MoveOutcome::Blocked(blocked) => assert!(blocked.out_of_bounds),
+            MoveOutcome::Moved(_) => panic!("moved below 0"),
         }
     }
 }
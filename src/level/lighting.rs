@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+
+use super::{
+    bindings::{Action, ActionInput},
+    maze_renderer::CELL_SIZE,
+};
+
+/// How high above the player's origin its light floats, clear of the walls next to it.
+const PLAYER_LIGHT_HEIGHT: f32 = 1.5;
+
+/// How the maze is lit. The player carries the only point light, so lowering the ambient
+/// light and the light's range leaves only the cells around the player visible.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LightingSettings {
+    /// Brightness of the light that reaches every surface evenly.
+    pub ambient: f32,
+    /// Intensity of the player's light, in lumens.
+    pub player_intensity: f32,
+    /// How many cells away from the player its light reaches.
+    pub player_range: f32,
+    /// Whether the player's light casts shadows, which redraws every wall for each face of
+    /// its shadow map and gets expensive in large slices.
+    pub shadows: bool,
+}
+
+impl Default for LightingSettings {
+    fn default() -> Self {
+        Self {
+            ambient: 0.2,
+            player_intensity: 800.0,
+            player_range: 12.0,
+            shadows: true,
+        }
+    }
+}
+
+impl LightingSettings {
+    /// Lighting where only the few cells around the player can be seen.
+    pub fn dark() -> Self {
+        Self {
+            ambient: 0.0,
+            player_intensity: 150.0,
+            player_range: 3.0,
+            ..Default::default()
+        }
+    }
+
+    fn with_shadows(self, shadows: bool) -> Self {
+        Self { shadows, ..self }
+    }
+
+    fn apply(&self, light: &mut PointLight) {
+        light.intensity = self.player_intensity;
+        light.range = self.player_range * CELL_SIZE;
+        light.shadows_enabled = self.shadows;
+    }
+}
+
+/// Marks the light carried by the player.
+#[derive(Component)]
+pub struct PlayerLight;
+
+/// The light to add as a child of the player, so it follows the player's animations.
+pub fn player_light(settings: &LightingSettings) -> PointLightBundle {
+    let mut point_light = PointLight {
+        radius: 0.1,
+        ..default()
+    };
+    settings.apply(&mut point_light);
+    PointLightBundle {
+        point_light,
+        transform: Transform::from_xyz(0.0, PLAYER_LIGHT_HEIGHT, 0.0),
+        ..default()
+    }
+}
+
+/// Swaps between the default and dark lighting, keeping the shadow setting.
+pub fn toggle_dark_mode(input: ActionInput, mut settings: ResMut<LightingSettings>) {
+    if input.just_pressed(Action::ToggleDarkMode) {
+        let shadows = settings.shadows;
+        let next = if *settings == LightingSettings::dark().with_shadows(shadows) {
+            LightingSettings::default()
+        } else {
+            LightingSettings::dark()
+        };
+        *settings = next.with_shadows(shadows);
+    }
+}
+
+/// Pushes changed settings to the ambient light and every player light.
+pub fn apply_lighting(
+    settings: Res<LightingSettings>,
+    // Only present with the renderer, so missing in headless runs.
+    ambient: Option<ResMut<AmbientLight>>,
+    mut lights: Query<&mut PointLight, With<PlayerLight>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    if let Some(mut ambient) = ambient {
+        ambient.brightness = settings.ambient;
+    }
+    for mut light in lights.iter_mut() {
+        settings.apply(&mut light);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn player_light_follows_settings() {
+        let bundle = player_light(&LightingSettings::default());
+        assert_eq!(bundle.point_light.range, 12.0 * CELL_SIZE);
+        assert!(bundle.point_light.shadows_enabled);
+
+        let settings = LightingSettings {
+            shadows: false,
+            ..LightingSettings::dark()
+        };
+        let mut light = bundle.point_light;
+        settings.apply(&mut light);
+        assert_eq!(light.intensity, settings.player_intensity);
+        assert_eq!(light.range, 3.0 * CELL_SIZE);
+        assert!(!light.shadows_enabled);
+    }
+}
@@ -1,109 +1,632 @@
-use crate::AppState;
-use bevy::prelude::*;
-use rand::prelude::*;
+use crate::{
+    toast::{ShowToast, ToastKind},
+    AppState,
+};
+use bevy::{ecs::event::Events, prelude::*, tasks::AsyncComputeTaskPool};
+use maze::{GenAlgorithm, Maze, Progress};
+use rand::{prelude::*, rngs::OsRng};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
 
 use super::{
-    maze_level::{AxisChanged, PositionChanged},
+    bindings::{Action, ActionInput},
+    code::LevelCode,
+    difficulty::Difficulty,
+    error::LevelError,
+    lighting::{player_light, LightingSettings, PlayerLight},
+    loading::PendingLevel,
+    maze_level::{AxisChanged, MoveBlocked, PositionChanged},
+    maze_renderer::CELL_SIZE,
+    palette::AxisPalette,
+    preflight::LoadLimits,
+    theme::{load_theme, MazeTheme, PendingTextures, ThemeSettings},
     MazeLevel,
 };
 
+/// Request to build a maze and enter it. This event is the only way into a level: the menu
+/// sends one for a new maze, and replaying resends the `LastLoad` with its seed resolved.
 #[derive(Clone, Debug)]
 pub struct LoadLevel {
     pub rng_source: RngSource,
     pub dimensions: DimensionLength,
+    pub algorithm: GenAlgorithm,
+    /// Fraction of dead ends, from 0 to 1, to open into loops after generating the maze.
+    pub braid: f32,
+    /// Path of a saved `MazeFile` under `assets/` to load instead of generating a maze,
+    /// in which case the generation settings are ignored.
+    pub maze_file: Option<String>,
+    /// The index of the campaign level this is, or `None` for free play.
+    pub campaign_level: Option<usize>,
+    /// The difficulty the level was picked at, which sets its fog and keeps its best times.
+    pub difficulty: Option<Difficulty>,
+    /// Whether the level is played against the clock, with its best times on a leaderboard.
+    pub time_trial: bool,
+}
+
+/// Marks every entity spawned for a level, so the whole level can be torn down at once.
+#[derive(Component)]
+pub struct LevelEntity;
+
+/// The request the current level was loaded from, with its seed resolved so it replays.
+pub struct LastLoad(pub LoadLevel);
+
+impl LastLoad {
+    /// The same maze again, from the start.
+    pub fn restart(&self) -> LoadLevel {
+        self.0.clone()
+    }
+
+    /// A new maze the size of `level` from a fresh seed, even if `level` came from a file.
+    /// The new maze is free play, as it isn't the campaign's level any more.
+    pub fn regenerate(&self, level: &MazeLevel) -> LoadLevel {
+        LoadLevel {
+            rng_source: RngSource::Entropy,
+            dimensions: DimensionLength::from_lengths(level.dims_limit())
+                .unwrap_or_else(|| self.0.dimensions.clone()),
+            maze_file: None,
+            campaign_level: None,
+            ..self.0.clone()
+        }
+    }
+
+    /// A shareable code for this level, or `None` if it came from a file a code can't carry.
+    pub fn code(&self) -> Option<String> {
+        match self.0.maze_file {
+            Some(_) => None,
+            None => Some(LevelCode::encode(&self.0)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LevelLoadFailed {
+    pub error: LevelError,
 }
 
 #[derive(Clone, Debug)]
 pub enum RngSource {
     Seeded(u64),
+    /// Draws a fresh seed from OS entropy, recorded on the loaded `MazeLevel`.
+    Entropy,
 }
 
-// Remove this once construction methods for dimensions are found.
-#[allow(dead_code)]
-#[derive(Clone, Debug)]
+impl RngSource {
+    /// The seed a maze is generated from, so an entropy seeded maze can be replayed.
+    pub fn resolve(&self) -> u64 {
+        match self {
+            RngSource::Seeded(seed) => *seed,
+            RngSource::Entropy => OsRng.next_u64(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DimensionLength {
-    Two([u8; 2]),
-    Three([u8; 3]),
-    Four([u8; 4]),
-    Five([u8; 5]),
-    Six([u8; 6]),
+    Two([u16; 2]),
+    Three([u16; 3]),
+    Four([u16; 4]),
+    Five([u16; 5]),
+    Six([u16; 6]),
+}
+
+impl DimensionLength {
+    /// Picks the variant matching the number of lengths, if there is one.
+    pub fn from_lengths(lengths: &[u16]) -> Option<Self> {
+        Some(match lengths.len() {
+            2 => DimensionLength::Two(lengths.try_into().ok()?),
+            3 => DimensionLength::Three(lengths.try_into().ok()?),
+            4 => DimensionLength::Four(lengths.try_into().ok()?),
+            5 => DimensionLength::Five(lengths.try_into().ok()?),
+            6 => DimensionLength::Six(lengths.try_into().ok()?),
+            _ => return None,
+        })
+    }
+
+    pub fn lengths(&self) -> &[u16] {
+        match self {
+            DimensionLength::Two(lengths) => lengths,
+            DimensionLength::Three(lengths) => lengths,
+            DimensionLength::Four(lengths) => lengths,
+            DimensionLength::Five(lengths) => lengths,
+            DimensionLength::Six(lengths) => lengths,
+        }
+    }
 }
 
 impl Default for LoadLevel {
     fn default() -> Self {
         Self {
-            rng_source: RngSource::Seeded(123456789),
+            rng_source: RngSource::Entropy,
             dimensions: DimensionLength::Two([2, 2]),
+            algorithm: GenAlgorithm::default(),
+            braid: 0.0,
+            maze_file: None,
+            campaign_level: None,
+            difficulty: None,
+            time_trial: false,
         }
     }
 }
 
+/// Version of the `MazeFile` layout, bumped whenever a saved maze would read differently.
+const MAZE_FILE_VERSION: u32 = 1;
+
+/// A maze saved to disk, so the exact same level can be reloaded without regenerating it.
+#[derive(Serialize, Deserialize)]
+pub struct MazeFile {
+    pub version: u32,
+    pub maze: MazeData,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum MazeData {
+    Two(Maze<2>),
+    Three(Maze<3>),
+    Four(Maze<4>),
+    Five(Maze<5>),
+    Six(Maze<6>),
+}
+
+/// Read ahead of the rest of a `MazeFile` so version mismatches are reported as such.
+#[derive(Deserialize)]
+struct MazeFileHeader {
+    version: u32,
+}
+
 pub fn level_load_system(
     mut c: Commands,
     mut events: EventReader<LoadLevel>,
     mut app_state: ResMut<State<AppState>>,
+    mut load_failed: EventWriter<LevelLoadFailed>,
+    limits: Res<LoadLimits>,
+    pool: Res<AsyncComputeTaskPool>,
 ) {
     for level_loader in events.iter() {
-        let mut rng = match level_loader.rng_source {
-            RngSource::Seeded(seed) => StdRng::seed_from_u64(seed),
+        let result = match &level_loader.maze_file {
+            // Saved mazes load quickly enough to skip the loading screen.
+            Some(path) => read_maze_file(path).and_then(|level| {
+                c.insert_resource(LastLoad(level_loader.clone()));
+                c.insert_resource(level);
+                enter_maze(&mut app_state)
+            }),
+            None => limits
+                .check(level_loader.dimensions.lengths())
+                .and_then(|_| {
+                    let max_cells = usize::try_from(limits.max_cells).unwrap_or(usize::MAX);
+                    c.insert_resource(PendingLevel::spawn(&pool, level_loader, max_cells));
+                    enter_loading(&mut app_state)
+                }),
         };
-        c.insert_resource(match level_loader.dimensions {
-            DimensionLength::Two(lengths) => MazeLevel::new(&lengths, &mut rng),
-            DimensionLength::Three(lengths) => MazeLevel::new(&lengths, &mut rng),
-            DimensionLength::Four(lengths) => MazeLevel::new(&lengths, &mut rng),
-            DimensionLength::Five(lengths) => MazeLevel::new(&lengths, &mut rng),
-            DimensionLength::Six(lengths) => MazeLevel::new(&lengths, &mut rng),
-        });
-        app_state.push(AppState::InMaze).unwrap();
+        if let Err(error) = result {
+            load_failed.send(LevelLoadFailed { error });
+        }
     }
 }
 
+/// Generates the maze `request` describes from `seed`, reporting how far along it is to
+/// `progress`.
+pub fn generate_level(
+    request: &LoadLevel,
+    seed: u64,
+    max_cells: usize,
+    progress: &Progress,
+) -> Result<MazeLevel, LevelError> {
+    let rng = &mut StdRng::seed_from_u64(seed);
+    let level = match &request.dimensions {
+        DimensionLength::Two(lengths) => generate(lengths, request, max_cells, rng, progress),
+        DimensionLength::Three(lengths) => generate(lengths, request, max_cells, rng, progress),
+        DimensionLength::Four(lengths) => generate(lengths, request, max_cells, rng, progress),
+        DimensionLength::Five(lengths) => generate(lengths, request, max_cells, rng, progress),
+        DimensionLength::Six(lengths) => generate(lengths, request, max_cells, rng, progress),
+    }?;
+    Ok(level.with_seed(seed))
+}
+
+fn generate<const DIMS: usize>(
+    lengths: &[u16; DIMS],
+    request: &LoadLevel,
+    max_cells: usize,
+    rng: &mut StdRng,
+    progress: &Progress,
+) -> Result<MazeLevel, LevelError> {
+    let mut maze = Maze::try_generate(lengths, request.algorithm, max_cells, rng, progress)?;
+    if request.braid > 0.0 {
+        maze.braid(request.braid, rng);
+    }
+    Ok(MazeLevel::from_maze(maze))
+}
+
+fn read_maze_file(path: &str) -> Result<MazeLevel, LevelError> {
+    let path = Path::new("assets").join(path);
+    let text = fs::read_to_string(&path).map_err(|error| LevelError::Io {
+        path: path.clone(),
+        error,
+    })?;
+    parse_maze_file(&text)
+}
+
+fn parse_maze_file(text: &str) -> Result<MazeLevel, LevelError> {
+    let header: MazeFileHeader = ron::from_str(text)?;
+    if header.version != MAZE_FILE_VERSION {
+        return Err(LevelError::UnsupportedVersion(header.version));
+    }
+    let file: MazeFile = ron::from_str(text)?;
+    Ok(match file.maze {
+        MazeData::Two(maze) => MazeLevel::from_maze(maze),
+        MazeData::Three(maze) => MazeLevel::from_maze(maze),
+        MazeData::Four(maze) => MazeLevel::from_maze(maze),
+        MazeData::Five(maze) => MazeLevel::from_maze(maze),
+        MazeData::Six(maze) => MazeLevel::from_maze(maze),
+    })
+}
+
+/// Shows the loading screen in place of whatever was running, unless it already is, in which
+/// case the new `PendingLevel` has just replaced the one being waited on.
+fn enter_loading(app_state: &mut State<AppState>) -> Result<(), LevelError> {
+    if *app_state.current() != AppState::Loading {
+        app_state.replace(AppState::Loading)?;
+    }
+    Ok(())
+}
+
+/// Makes the maze the only state, unwinding a menu or pause underneath it, or restarts it
+/// if a maze is already being played. Either way the old level's entities are despawned.
+pub fn enter_maze(app_state: &mut State<AppState>) -> Result<(), LevelError> {
+    if *app_state.current() == AppState::InMaze {
+        app_state.restart()?;
+    } else {
+        app_state.replace(AppState::InMaze)?;
+    }
+    Ok(())
+}
+
+/// Restarts or regenerates the maze on their keys. Either way the level goes through the
+/// usual load, which despawns everything mid-animation and resets the player's progress.
+pub fn restart_input(
+    input: ActionInput,
+    level: Res<MazeLevel>,
+    last_load: Option<Res<LastLoad>>,
+    mut load_level: EventWriter<LoadLevel>,
+) {
+    let last_load = match last_load {
+        Some(last_load) => last_load,
+        None => return,
+    };
+    if input.just_pressed(Action::Regenerate) {
+        load_level.send(last_load.regenerate(&level));
+    } else if input.just_pressed(Action::Restart) {
+        load_level.send(last_load.restart());
+    }
+}
+
+pub fn despawn_level(mut c: Commands, entities: Query<Entity, With<LevelEntity>>) {
+    for entity in entities.iter() {
+        c.entity(entity).despawn_recursive();
+    }
+}
+
+pub fn remove_level_resources(mut c: Commands) {
+    c.remove_resource::<MazeLevel>();
+    c.remove_resource::<LastLoad>();
+}
+
+pub fn report_load_failures(
+    mut failures: EventReader<LevelLoadFailed>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    for failed in failures.iter() {
+        error!("Failed to load level: {}", failed.error);
+        let kind = match failed.error {
+            LevelError::TooLarge { .. } => ToastKind::Warn,
+            _ => ToastKind::Error,
+        };
+        toasts.send(ShowToast::new(kind, failed.error.to_string()));
+    }
+}
+
+/// Forgets navigation events the previous level sent that haven't expired yet. They describe
+/// a maze that has just been replaced, and reading them would count a step, or move the
+/// minimap's player, in the new one.
+pub fn drop_stale_events(
+    mut position_changed: ResMut<Events<PositionChanged>>,
+    mut axis_changed: ResMut<Events<AxisChanged>>,
+    mut blocked: ResMut<Events<MoveBlocked>>,
+) {
+    position_changed.clear();
+    axis_changed.clear();
+    blocked.clear();
+}
+
+/// Announces where the new level starts. The `MazeLevel` it reads is inserted by the same
+/// command buffer that enters the maze, which is applied before any of the state's systems
+/// run, and both systems that send it are ordered before `LevelInit`.
 pub fn initial_events_on_load(
     maze: Res<MazeLevel>,
     mut position_changed: EventWriter<PositionChanged>,
     mut axis_changed: EventWriter<AxisChanged>,
 ) {
-    position_changed.send(PositionChanged {
-        position: maze.pos(),
-    });
-    axis_changed.send(AxisChanged { axis: maze.axis() });
+    position_changed.send(PositionChanged::loaded(maze.pos()));
+    axis_changed.send(AxisChanged::loaded(maze.axis()));
 }
 
 pub fn load_maze_assets(
     mut c: Commands,
+    settings: Res<ThemeSettings>,
+    palette: Res<AxisPalette>,
+    server: Res<AssetServer>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    c.insert_resource(MazeAssets {
-        joint: meshes.add(Mesh::from(shape::Box::new(0.2, 1.0, 0.2))),
-        wall: meshes.add(Mesh::from(shape::Box::new(0.1, 0.6, 1.0))),
-        material: materials.add(Color::rgb(0.8, 0.7, 0.6).into()),
-    });
+    // Placeholders under stable handles, which the theme then fills in and can later replace.
+    let mut mesh = || meshes.add(Mesh::from(shape::Cube::default()));
+    let (joint, wall, floor, trail, pip, player) = (mesh(), mesh(), mesh(), mesh(), mesh(), mesh());
+    let mut material = || materials.add(StandardMaterial::default());
+    let assets = MazeAssets {
+        joint,
+        wall,
+        floor,
+        trail,
+        pip,
+        player,
+        material: material(),
+        floor_material: material(),
+        start_material: material(),
+        goal_material: material(),
+        hint_material: material(),
+        bump_material: material(),
+        trail_material: material(),
+        player_material: material(),
+        axes: Vec::new(),
+    };
+    let theme = load_theme(&settings.selected);
+    let textures = assets.restyle(&theme, &palette, &server, &mut meshes, &mut materials);
+    c.insert_resource(PendingTextures { textures });
+    c.insert_resource(assets);
+    c.insert_resource(theme);
 }
 
-pub fn spawn_player(
-    mut c: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
+/// Makes sure every dimension of the new level has its materials.
+pub fn add_axis_materials(
+    level: Res<MazeLevel>,
+    theme: Res<MazeTheme>,
+    palette: Res<AxisPalette>,
+    server: Res<AssetServer>,
+    mut assets: ResMut<MazeAssets>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut pending: ResMut<PendingTextures>,
 ) {
-    c.spawn_bundle(PbrBundle {
-        mesh: meshes.add(Mesh::from(shape::Capsule {
-            radius: 0.3,
-            ..default()
-        })),
-        material: materials.add(Color::rgb(0.5, 0.5, 0.8).into()),
-        ..Default::default()
-    });
+    let count = level.dimension_count();
+    let textures = assets.add_axes(count, &theme, &palette, &server, &mut materials);
+    pending.textures.extend(textures);
+}
+
+/// Recolors every dimension's materials, and so everything drawn with them, when the
+/// palette changes.
+pub fn restyle_axis_materials(
+    theme: Res<MazeTheme>,
+    palette: Res<AxisPalette>,
+    server: Res<AssetServer>,
+    assets: Res<MazeAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut pending: ResMut<PendingTextures>,
+) {
+    if !palette.is_changed() || palette.is_added() {
+        return;
+    }
+    let textures = assets.restyle_axes(&theme, &palette, &server, &mut materials);
+    pending.textures.extend(textures);
+}
+
+#[derive(Component)]
+pub struct Player;
+
+pub fn spawn_player(mut c: Commands, assets: Res<MazeAssets>, lighting: Res<LightingSettings>) {
+    c.spawn_bundle(assets.player())
+        .insert(Name::new("player#0"))
+        .insert(Player)
+        .insert(LevelEntity)
+        .with_children(|c| {
+            c.spawn_bundle(player_light(&lighting)).insert(PlayerLight);
+        });
 }
 
 #[derive(Component)]
 pub struct MazeAssets {
     joint: Handle<Mesh>,
     wall: Handle<Mesh>,
+    floor: Handle<Mesh>,
+    trail: Handle<Mesh>,
+    pip: Handle<Mesh>,
+    player: Handle<Mesh>,
     material: Handle<StandardMaterial>,
+    floor_material: Handle<StandardMaterial>,
+    start_material: Handle<StandardMaterial>,
+    goal_material: Handle<StandardMaterial>,
+    hint_material: Handle<StandardMaterial>,
+    /// Glowing walls, for the wall the player just walked into.
+    bump_material: Handle<StandardMaterial>,
+    trail_material: Handle<StandardMaterial>,
+    player_material: Handle<StandardMaterial>,
+    /// The materials of each dimension, added as levels with more dimensions load.
+    axes: Vec<AxisMaterials>,
+}
+
+/// The materials tinted with the color of one dimension.
+struct AxisMaterials {
+    wall: Handle<StandardMaterial>,
+    /// Faded walls, for the parts of a slice the player has not explored.
+    fog: Handle<StandardMaterial>,
+    /// Translucent walls, for the layers the volume view draws around the slice.
+    ghost: Handle<StandardMaterial>,
+    /// The move indicators over the player.
+    pip: Handle<StandardMaterial>,
+}
+
+/// A flat `color` material, drawn with the image at `path` if there is one, which is
+/// recorded in `textures` so it can fall back if the image fails to load.
+fn textured(
+    server: &AssetServer,
+    handle: &Handle<StandardMaterial>,
+    color: Color,
+    path: &Option<String>,
+    textures: &mut Vec<(Handle<StandardMaterial>, Handle<Image>)>,
+) -> StandardMaterial {
+    let texture = path.as_ref().map(|path| server.load(path.as_str()));
+    if let Some(texture) = &texture {
+        textures.push((handle.clone(), texture.clone()));
+    }
+    StandardMaterial {
+        base_color: color,
+        base_color_texture: texture,
+        ..default()
+    }
 }
 
 impl MazeAssets {
+    /// Rebuilds every mesh and material for `theme` under the handles already handed out,
+    /// returning the textures it started loading along with the material each is for.
+    pub fn restyle(
+        &self,
+        theme: &MazeTheme,
+        palette: &AxisPalette,
+        server: &AssetServer,
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> Vec<(Handle<StandardMaterial>, Handle<Image>)> {
+        let mut set_mesh = |handle: &Handle<Mesh>, mesh: Mesh| meshes.set_untracked(handle, mesh);
+        set_mesh(
+            &self.joint,
+            shape::Box::new(theme.joint_width, theme.joint_height, theme.joint_width).into(),
+        );
+        set_mesh(
+            &self.wall,
+            shape::Box::new(theme.wall_thickness, theme.wall_height, CELL_SIZE).into(),
+        );
+        set_mesh(
+            &self.floor,
+            shape::Box::new(CELL_SIZE, theme.floor_thickness, CELL_SIZE).into(),
+        );
+        set_mesh(&self.trail, shape::Box::new(0.2, 0.02, 0.2).into());
+        set_mesh(
+            &self.pip,
+            shape::Icosphere {
+                radius: 0.06,
+                subdivisions: 2,
+            }
+            .into(),
+        );
+        set_mesh(
+            &self.player,
+            shape::Capsule {
+                radius: theme.player_radius,
+                ..default()
+            }
+            .into(),
+        );
+
+        let mut textures = Vec::new();
+        let wall = textured(
+            server,
+            &self.material,
+            theme.wall,
+            &theme.wall_texture,
+            &mut textures,
+        );
+        let floor = textured(
+            server,
+            &self.floor_material,
+            theme.floor,
+            &theme.floor_texture,
+            &mut textures,
+        );
+        let glowing = |color| StandardMaterial {
+            base_color: color,
+            emissive: theme.highlight,
+            ..default()
+        };
+        let mut set = |handle: &Handle<StandardMaterial>, material: StandardMaterial| {
+            materials.set_untracked(handle, material)
+        };
+        set(&self.material, wall);
+        set(&self.floor_material, floor);
+        set(&self.start_material, theme.start.into());
+        set(&self.goal_material, theme.goal.into());
+        set(&self.hint_material, glowing(theme.hint));
+        set(&self.bump_material, glowing(theme.bump));
+        set(&self.trail_material, theme.trail.into());
+        set(&self.player_material, theme.player.into());
+        textures.extend(self.restyle_axes(theme, palette, server, materials));
+        textures
+    }
+
+    /// Adds materials for any of the first `count` dimensions that don't have them yet,
+    /// returning the textures they started loading.
+    pub fn add_axes(
+        &mut self,
+        count: usize,
+        theme: &MazeTheme,
+        palette: &AxisPalette,
+        server: &AssetServer,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> Vec<(Handle<StandardMaterial>, Handle<Image>)> {
+        if self.axes.len() >= count {
+            return Vec::new();
+        }
+        while self.axes.len() < count {
+            let mut material = || materials.add(StandardMaterial::default());
+            self.axes.push(AxisMaterials {
+                wall: material(),
+                fog: material(),
+                ghost: material(),
+                pip: material(),
+            });
+        }
+        self.restyle_axes(theme, palette, server, materials)
+    }
+
+    /// Recolors the materials of every dimension from `palette`.
+    pub fn restyle_axes(
+        &self,
+        theme: &MazeTheme,
+        palette: &AxisPalette,
+        server: &AssetServer,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> Vec<(Handle<StandardMaterial>, Handle<Image>)> {
+        let mut textures = Vec::new();
+        for (dimension, axis) in self.axes.iter().enumerate() {
+            let tint = palette.tint(theme.wall, dimension);
+            let wall = textured(server, &axis.wall, tint, &theme.wall_texture, &mut textures);
+            let [red, green, blue, _] = tint.as_rgba_f32();
+            materials.set_untracked(&axis.wall, wall);
+            materials.set_untracked(
+                &axis.fog,
+                StandardMaterial {
+                    base_color: Color::rgba(red / 2.0, green / 2.0, blue / 2.0, 0.35),
+                    alpha_mode: AlphaMode::Blend,
+                    ..default()
+                },
+            );
+            materials.set_untracked(
+                &axis.ghost,
+                StandardMaterial {
+                    base_color: Color::rgba(red, green, blue, 0.15),
+                    alpha_mode: AlphaMode::Blend,
+                    ..default()
+                },
+            );
+            materials.set_untracked(&axis.pip, palette.color(dimension).into());
+        }
+        textures
+    }
+
+    pub fn player(&self) -> PbrBundle {
+        PbrBundle {
+            mesh: self.player.clone(),
+            material: self.player_material.clone(),
+            ..Default::default()
+        }
+    }
+
     pub fn wall(&self, transform: Transform) -> PbrBundle {
         PbrBundle {
             mesh: self.wall.clone(),
@@ -113,6 +636,26 @@ impl MazeAssets {
         }
     }
 
+    /// The material for a wall blocking `dimension`, faded if the player has not `seen` it.
+    pub fn wall_material(&self, dimension: usize, seen: bool) -> Handle<StandardMaterial> {
+        match self.axes.get(dimension) {
+            Some(axis) if seen => axis.wall.clone(),
+            Some(axis) => axis.fog.clone(),
+            None => self.material.clone(),
+        }
+    }
+
+    /// The material for a wall blocking `dimension` in a layer beside the slice.
+    pub fn ghost_wall_material(&self, dimension: usize) -> Handle<StandardMaterial> {
+        self.axes
+            .get(dimension)
+            .map_or_else(|| self.material.clone(), |axis| axis.ghost.clone())
+    }
+
+    pub fn bump_material(&self) -> Handle<StandardMaterial> {
+        self.bump_material.clone()
+    }
+
     pub fn joint(&self, transform: Transform) -> PbrBundle {
         PbrBundle {
             mesh: self.joint.clone(),
@@ -121,4 +664,223 @@ impl MazeAssets {
             ..Default::default()
         }
     }
+
+    pub fn floor(&self, transform: Transform) -> PbrBundle {
+        self.tile(&self.floor_material, transform)
+    }
+
+    pub fn start(&self, transform: Transform) -> PbrBundle {
+        self.tile(&self.start_material, transform)
+    }
+
+    pub fn goal(&self, transform: Transform) -> PbrBundle {
+        self.tile(&self.goal_material, transform)
+    }
+
+    pub fn hint(&self, transform: Transform) -> PbrBundle {
+        self.tile(&self.hint_material, transform)
+    }
+
+    pub fn trail(&self, transform: Transform) -> PbrBundle {
+        PbrBundle {
+            mesh: self.trail.clone(),
+            material: self.trail_material.clone(),
+            transform,
+            ..Default::default()
+        }
+    }
+
+    /// A small marker in the color of `dimension`.
+    pub fn pip(&self, dimension: usize, transform: Transform) -> PbrBundle {
+        PbrBundle {
+            mesh: self.pip.clone(),
+            material: self
+                .axes
+                .get(dimension)
+                .map_or_else(|| self.material.clone(), |axis| axis.pip.clone()),
+            transform,
+            ..Default::default()
+        }
+    }
+
+    fn tile(&self, material: &Handle<StandardMaterial>, transform: Transform) -> PbrBundle {
+        PbrBundle {
+            mesh: self.floor.clone(),
+            material: material.clone(),
+            transform,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::maze_level::SliceWall;
+    use bevy::ecs::schedule::StateError;
+
+    fn maze_file_text(version: u32) -> String {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        ron::to_string(&MazeFile {
+            version,
+            maze: MazeData::Three(Maze::new(&[4, 3, 2], &mut rng)),
+        })
+        .unwrap()
+    }
+
+    fn generate_seeded(request: &LoadLevel, seed: u64) -> Result<MazeLevel, LevelError> {
+        generate_level(request, seed, usize::MAX, &Progress::default())
+    }
+
+    fn wall_set(level: &MazeLevel) -> std::collections::HashSet<SliceWall> {
+        level.walls_in_current_slice().collect()
+    }
+
+    #[test]
+    fn entropy_seed_replays_the_same_maze() {
+        let request = LoadLevel {
+            dimensions: DimensionLength::Two([8, 8]),
+            ..Default::default()
+        };
+        let seed = RngSource::Entropy.resolve();
+        let first = generate_seeded(&request, seed).ok().unwrap();
+        let replay = generate_seeded(&request, seed).ok().unwrap();
+        assert_eq!(replay.seed(), Some(seed));
+        assert_eq!(
+            first.walls_in_current_slice().collect::<Vec<_>>(),
+            replay.walls_in_current_slice().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn algorithm_changes_the_maze() {
+        let walls = |algorithm| {
+            let request = LoadLevel {
+                dimensions: DimensionLength::Two([8, 8]),
+                algorithm,
+                ..Default::default()
+            };
+            wall_set(&generate_seeded(&request, 7).ok().unwrap())
+        };
+        assert_ne!(
+            walls(GenAlgorithm::Kruskal),
+            walls(GenAlgorithm::RecursiveBacktracker)
+        );
+        assert_ne!(walls(GenAlgorithm::Kruskal), walls(GenAlgorithm::Wilson));
+    }
+
+    #[test]
+    fn braid_opens_walls() {
+        let walls = |braid| {
+            let request = LoadLevel {
+                dimensions: DimensionLength::Two([8, 8]),
+                braid,
+                ..Default::default()
+            };
+            wall_set(&generate_seeded(&request, 7).ok().unwrap())
+        };
+        let perfect = walls(0.0);
+        let braided = walls(1.0);
+        assert!(braided.len() < perfect.len());
+        assert!(braided.is_subset(&perfect));
+    }
+
+    #[test]
+    fn zero_length_fails_to_generate() {
+        let request = LoadLevel {
+            dimensions: DimensionLength::Two([0, 5]),
+            ..Default::default()
+        };
+        let error = generate_seeded(&request, 7).err().unwrap();
+        assert!(matches!(
+            error,
+            LevelError::Generation(maze::MazeGenError::ZeroLength { dimension: 0 })
+        ));
+    }
+
+    #[test]
+    fn parse_maze_file_round_trip() {
+        let level = parse_maze_file(&maze_file_text(MAZE_FILE_VERSION)).unwrap();
+        assert_eq!(level.dims_limit(), &[4, 3, 2]);
+        assert_eq!(level.dims(), &[0, 0, 0]);
+    }
+
+    #[test]
+    fn regenerating_keeps_the_size_of_a_saved_maze() {
+        let level = parse_maze_file(&maze_file_text(MAZE_FILE_VERSION)).unwrap();
+        let last_load = LastLoad(LoadLevel {
+            rng_source: RngSource::Seeded(3),
+            braid: 0.5,
+            maze_file: Some("mazes/saved.ron".into()),
+            ..Default::default()
+        });
+        let restart = last_load.restart();
+        assert_eq!(restart.maze_file.as_deref(), Some("mazes/saved.ron"));
+
+        let regenerated = last_load.regenerate(&level);
+        assert!(matches!(regenerated.rng_source, RngSource::Entropy));
+        assert!(matches!(
+            regenerated.dimensions,
+            DimensionLength::Three([4, 3, 2])
+        ));
+        assert_eq!(regenerated.braid, 0.5);
+        assert!(regenerated.maze_file.is_none());
+    }
+
+    #[test]
+    fn parse_maze_file_rejects_other_versions() {
+        let error = parse_maze_file(&maze_file_text(MAZE_FILE_VERSION + 1))
+            .err()
+            .unwrap();
+        assert!(matches!(error, LevelError::UnsupportedVersion(v) if v == MAZE_FILE_VERSION + 1));
+    }
+
+    #[test]
+    fn parse_maze_file_rejects_garbage() {
+        let error = parse_maze_file("(version: 1, maze: Seven(()))")
+            .err()
+            .unwrap();
+        assert!(matches!(error, LevelError::Parse(_)));
+    }
+
+    #[test]
+    fn read_missing_maze_file() {
+        let error = read_maze_file("levels/missing.maze.ron").err().unwrap();
+        assert!(matches!(error, LevelError::Io { .. }));
+        assert!(error.to_string().contains("missing.maze.ron"));
+    }
+
+    #[test]
+    fn enter_maze_from_menu() {
+        let mut state = State::new(AppState::MainMenu);
+        assert!(enter_maze(&mut state).is_ok());
+    }
+
+    #[test]
+    fn enter_maze_from_pause() {
+        let mut state = State::new(AppState::Paused);
+        assert!(enter_maze(&mut state).is_ok());
+    }
+
+    #[test]
+    fn enter_maze_while_in_maze_restarts() {
+        let mut state = State::new(AppState::InMaze);
+        assert!(enter_maze(&mut state).is_ok());
+    }
+
+    #[test]
+    fn enter_maze_twice_in_a_frame_fails() {
+        let mut state = State::new(AppState::MainMenu);
+        enter_maze(&mut state).unwrap();
+        let error = enter_maze(&mut state).unwrap_err();
+        assert!(matches!(
+            error,
+            LevelError::StateTransition(StateError::StateAlreadyQueued)
+        ));
+        assert_eq!(
+            error.to_string(),
+            "could not enter the maze: \
+             Attempted to queue a state change, but there was already a state queued."
+        );
+    }
 }
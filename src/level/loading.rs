@@ -0,0 +1,171 @@
+use std::sync::Arc;
+
+use bevy::{
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task},
+};
+use futures_lite::future;
+use maze::Progress;
+
+use super::{
+    bindings::{Action, ActionInputMut},
+    error::LevelError,
+    loader::{self, LastLoad, LevelLoadFailed, LoadLevel, RngSource},
+    MazeLevel,
+};
+use crate::AppState;
+
+/// A maze being generated off the main thread. Removing it drops the task, cancelling it.
+pub struct PendingLevel {
+    /// The request being generated, with its seed resolved so it replays.
+    request: LoadLevel,
+    progress: Arc<Progress>,
+    task: Task<Result<MazeLevel, LevelError>>,
+}
+
+impl PendingLevel {
+    /// Starts generating the maze `request` asks for on `pool`.
+    pub fn spawn(pool: &AsyncComputeTaskPool, request: &LoadLevel, max_cells: usize) -> Self {
+        let seed = request.rng_source.resolve();
+        let progress = Arc::new(Progress::default());
+        let task = pool.spawn({
+            let request = request.clone();
+            let progress = progress.clone();
+            async move { loader::generate_level(&request, seed, max_cells, &progress) }
+        });
+        Self {
+            request: LoadLevel {
+                rng_source: RngSource::Seeded(seed),
+                ..request.clone()
+            },
+            progress,
+            task,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct LoadingScreen;
+
+#[derive(Component)]
+pub struct LoadingText;
+
+pub fn spawn_loading_screen(mut c: Commands, assets: Res<AssetServer>) {
+    c.spawn_bundle(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    })
+    .insert(LoadingScreen)
+    .insert(Name::new("loading screen"))
+    .with_children(|c| {
+        c.spawn_bundle(TextBundle {
+            text: Text::with_section(
+                loading_label(0.0),
+                TextStyle {
+                    font: assets.load("fonts/UnicaOne-Regular.ttf"),
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                },
+                TextAlignment {
+                    horizontal: HorizontalAlign::Center,
+                    ..default()
+                },
+            ),
+            ..default()
+        })
+        .insert(LoadingText);
+    });
+}
+
+pub fn despawn_loading_screen(mut c: Commands, screens: Query<Entity, With<LoadingScreen>>) {
+    for screen in screens.iter() {
+        c.entity(screen).despawn_recursive();
+    }
+}
+
+/// Drops any generation still running, so a cancelled maze is never inserted.
+pub fn cancel_generation(mut c: Commands) {
+    c.remove_resource::<PendingLevel>();
+}
+
+pub fn update_loading_text(
+    pending: Option<Res<PendingLevel>>,
+    mut texts: Query<&mut Text, With<LoadingText>>,
+) {
+    let fraction = match pending {
+        Some(pending) => pending.progress.fraction(),
+        None => return,
+    };
+    for mut text in texts.iter_mut() {
+        if let Some(section) = text.sections.first_mut() {
+            section.value = loading_label(fraction);
+        }
+    }
+}
+
+/// Moves into the maze once its generation finishes, or back to the menu if it failed.
+pub fn poll_generation(
+    mut c: Commands,
+    // Inserted by command, so it can lag a frame behind entering the state.
+    pending: Option<ResMut<PendingLevel>>,
+    mut app_state: ResMut<State<AppState>>,
+    mut load_failed: EventWriter<LevelLoadFailed>,
+) {
+    let mut pending = match pending {
+        Some(pending) => pending,
+        None => return,
+    };
+    let result = match future::block_on(future::poll_once(&mut pending.task)) {
+        Some(result) => result,
+        None => return,
+    };
+    c.remove_resource::<PendingLevel>();
+
+    let result = result.and_then(|level| {
+        if let Some(seed) = level.seed() {
+            info!("Generated maze from seed {}", seed);
+        }
+        c.insert_resource(LastLoad(pending.request.clone()));
+        c.insert_resource(level);
+        loader::enter_maze(&mut app_state)
+    });
+    if let Err(error) = result {
+        load_failed.send(LevelLoadFailed { error });
+        if let Err(e) = app_state.replace(AppState::MainMenu) {
+            error!("Could not leave the loading screen: {}", e);
+        }
+    }
+}
+
+pub fn loading_input(mut input: ActionInputMut, mut app_state: ResMut<State<AppState>>) {
+    if input.clear_just_pressed(Action::Pause) {
+        if let Err(e) = app_state.replace(AppState::MainMenu) {
+            error!("Could not cancel loading: {}", e);
+        }
+    }
+}
+
+fn loading_label(fraction: f32) -> String {
+    format!(
+        "Generating maze... {:.0}%\npress escape to cancel",
+        fraction * 100.0
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loading_label_rounds_to_a_percentage() {
+        assert!(loading_label(0.0).starts_with("Generating maze... 0%"));
+        assert!(loading_label(0.426).starts_with("Generating maze... 43%"));
+        assert!(loading_label(1.0).starts_with("Generating maze... 100%"));
+    }
+}
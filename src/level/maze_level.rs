@@ -1,24 +1,147 @@
-use std::ops::{Deref, DerefMut};
+use std::{
+    collections::HashSet,
+    ops::{Deref, DerefMut},
+};
 
-use crate::maze;
+use super::error::LevelError;
 
 struct MazeImpl<const DIMS: usize> {
     maze: maze::Maze<DIMS>,
-    position: [u8; DIMS],
+    position: [u16; DIMS],
+    start: [u16; DIMS],
+    goal: [u16; DIMS],
+    /// Moves on a shortest path from the start to the goal.
+    optimal_moves: usize,
+    /// Every cell the player has entered, including the start.
+    visited: HashSet<[u16; DIMS]>,
+    /// The cells of `visited` in the order they were first entered.
+    trail: Vec<[u16; DIMS]>,
     axis: [u8; 2],
+    /// The hidden dimension whose neighboring layers are drawn around the slice. Meaningless
+    /// with only two dimensions, where nothing is hidden.
+    depth: u8,
 }
 
-#[derive(Clone, Debug)]
+/// Why the displayed axes or position changed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeCause {
+    /// The level was just loaded.
+    Loaded,
+    /// The player stepped along a displayed axis.
+    Moved,
+    /// The player changed which dimensions are displayed.
+    Rotated,
+    /// The player undid an earlier move.
+    Undone,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct AxisChanged {
-    pub axis: [u8; 2],
+    /// The dimensions displayed before the change, the same as `to` on load.
+    pub from: [u8; 2],
+    pub to: [u8; 2],
+    pub cause: ChangeCause,
 }
 
-#[derive(Clone, Debug)]
+impl AxisChanged {
+    /// The axes a level starts out displaying, with nothing displayed before them.
+    pub fn loaded(axis: [u8; 2]) -> Self {
+        Self {
+            from: axis,
+            to: axis,
+            cause: ChangeCause::Loaded,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct PositionChanged {
-    pub position: [u8; 2],
+    /// The displayed position before the change, along the axes displayed before it.
+    pub from: [u16; 2],
+    pub to: [u16; 2],
+    /// The dimension the player stepped along, or `None` when the position changed any
+    /// other way, like a load, rotation or undo.
+    pub dimension: Option<usize>,
+    /// Whether the step went up `dimension`, and `None` when it is.
+    pub positive: Option<bool>,
+    pub cause: ChangeCause,
+}
+
+impl PositionChanged {
+    /// A change from `from` to `to` without stepping between them.
+    pub fn jump(from: [u16; 2], to: [u16; 2], cause: ChangeCause) -> Self {
+        Self {
+            from,
+            to,
+            dimension: None,
+            positive: None,
+            cause,
+        }
+    }
+
+    /// Where a level starts the player, with nowhere before it.
+    pub fn loaded(position: [u16; 2]) -> Self {
+        Self::jump(position, position, ChangeCause::Loaded)
+    }
 }
 
-#[derive(PartialEq, Eq, Clone, Copy)]
+/// A move into a wall, or off the edge of the maze.
+#[derive(Clone, Debug)]
+pub struct MoveBlocked {
+    pub position: [u16; 2],
+    pub dimension: usize,
+    pub positive: bool,
+    /// The move would have left the maze rather than walked into a wall.
+    pub out_of_bounds: bool,
+}
+
+/// A closed wall inside the displayed slice, on the positive side of `cell` along `axis`. It
+/// says nothing about where the slice is drawn, so every view of the slice can share it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct SliceWall {
+    pub cell: [u16; 2],
+    pub axis: Axis,
+}
+
+impl SliceWall {
+    /// The cell on the other side of the wall from `cell`.
+    pub fn neighbor(&self) -> [u16; 2] {
+        let mut neighbor = self.cell;
+        *self.axis.get_mut(&mut neighbor) += 1;
+        neighbor
+    }
+
+    /// The maze dimension this wall blocks, given the displayed `axis`.
+    pub fn dimension(&self, axis: [u8; 2]) -> usize {
+        *self.axis.get(&axis) as usize
+    }
+
+    /// The wall a move into a wall of the displayed slice walked into, or `None` when it
+    /// left the maze, since the border isn't made of slice walls.
+    pub fn blocking(axis: [u8; 2], blocked: &MoveBlocked) -> Option<Self> {
+        if blocked.out_of_bounds {
+            return None;
+        }
+        let wall_axis = if blocked.dimension == axis[0] as usize {
+            Axis::X
+        } else if blocked.dimension == axis[1] as usize {
+            Axis::Y
+        } else {
+            return None;
+        };
+        let mut cell = blocked.position;
+        if !blocked.positive {
+            let coord = wall_axis.get_mut(&mut cell);
+            *coord = coord.checked_sub(1)?;
+        }
+        Some(Self {
+            cell,
+            axis: wall_axis,
+        })
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub enum Axis {
     X,
     Y,
@@ -46,7 +169,7 @@ impl Axis {
     }
 }
 
-#[derive(PartialEq, Eq, Clone, Copy)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum Direction {
     Positive,
     Negative,
@@ -66,19 +189,71 @@ impl<const DIMS: usize> Default for MazeImpl<DIMS> {
         Self {
             maze: Default::default(),
             axis: [0, 1],
+            depth: 2,
             position: [0; DIMS],
+            start: [0; DIMS],
+            goal: [0; DIMS],
+            optimal_moves: 0,
+            visited: HashSet::from([[0; DIMS]]),
+            trail: vec![[0; DIMS]],
         }
     }
 }
 
 impl<const DIMS: usize> MazeImpl<DIMS> {
-    pub fn new(lengths: &[u8; DIMS], rng: &mut impl rand::Rng) -> Self {
+    /// Starts at the origin, with the goal at the cell farthest away from it.
+    pub fn from_maze(maze: maze::Maze<DIMS>) -> Self {
+        let position = [0; DIMS];
+        let (goal, optimal_moves) = maze.farthest_from(&position).unwrap_or((position, 0));
         Self {
-            maze: crate::maze::Maze::new(lengths, rng),
+            maze,
             axis: [0, 1],
-            position: [0; DIMS],
+            depth: 2,
+            position,
+            start: position,
+            goal,
+            optimal_moves,
+            visited: HashSet::from([position]),
+            trail: vec![position],
         }
     }
+
+    fn visit(&mut self, cell: [u16; DIMS]) {
+        if self.visited.insert(cell) {
+            self.trail.push(cell);
+        }
+    }
+
+    /// `cell` as a cell of the maze, if it has a coordinate in range for each dimension.
+    fn cell(&self, cell: &[u16]) -> Option<[u16; DIMS]> {
+        let cell: [u16; DIMS] = cell.try_into().ok()?;
+        let lengths = self.maze.lengths();
+        cell.iter()
+            .zip(lengths)
+            .all(|(coordinate, length)| coordinate < length)
+            .then_some(cell)
+    }
+
+    /// Where `cell` is in the displayed slice, if the slice contains it.
+    fn project(&self, cell: &[u16; DIMS]) -> Option<[u16; 2]> {
+        let hidden_match = (0..DIMS)
+            .filter(|dim| !self.axis.contains(&(*dim as u8)))
+            .all(|dim| self.position[dim] == cell[dim]);
+        hidden_match.then(|| [cell[self.axis[0] as usize], cell[self.axis[1] as usize]])
+    }
+
+    /// The dimensions not shown on either axis, in order.
+    fn hidden_dimensions(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..DIMS as u8).filter(|dim| !self.axis.contains(dim))
+    }
+
+    /// The player's position moved to `cell` of the displayed slice.
+    fn cursor(&self, cell: [u16; 2]) -> [u16; DIMS] {
+        let mut cursor = self.position;
+        cursor[self.axis[0] as usize] = cell[0];
+        cursor[self.axis[1] as usize] = cell[1];
+        cursor
+    }
 }
 
 impl<const DIMS: usize> MazeView for MazeImpl<DIMS> {
@@ -103,33 +278,52 @@ impl<const DIMS: usize> MazeView for MazeImpl<DIMS> {
             new_off_axis
         };
 
-        *axis.get_mut(&mut self.axis) = dest;
+        // Bringing the depth dimension into view sends the one it replaced into the depth.
+        let vacated = std::mem::replace(axis.get_mut(&mut self.axis), dest);
+        if self.depth == dest {
+            self.depth = vacated;
+        }
+    }
+
+    fn depth_axis(&self) -> Option<u8> {
+        (DIMS > 2).then_some(self.depth)
+    }
+
+    fn shift_depth(&mut self, dir: Direction) {
+        let hidden: Vec<u8> = self.hidden_dimensions().collect();
+        if let Some(index) = hidden.iter().position(|dim| *dim == self.depth) {
+            let count = hidden.len();
+            self.depth = hidden[match dir {
+                Direction::Positive => (index + 1) % count,
+                Direction::Negative => (index + count - 1) % count,
+            }];
+        }
     }
 
-    fn dims_limit(&self) -> &[u8] {
+    fn dims_limit(&self) -> &[u16] {
         self.maze.lengths()
     }
 
-    fn dims(&self) -> &[u8] {
+    fn dims(&self) -> &[u16] {
         &self.position
     }
 
     // assume dim_x and dim_y are both together.
-    fn pos_limit(&self) -> [u8; 2] {
+    fn pos_limit(&self) -> [u16; 2] {
         [
             self.maze.lengths()[self.axis[0] as usize],
             self.maze.lengths()[self.axis[1] as usize],
         ]
     }
 
-    fn pos(&self) -> [u8; 2] {
+    fn pos(&self) -> [u16; 2] {
         [
             self.position[self.axis[0] as usize],
             self.position[self.axis[1] as usize],
         ]
     }
 
-    fn move_pos(&mut self, axis: Axis, dir: Direction) {
+    fn move_pos(&mut self, axis: Axis, dir: Direction) -> bool {
         let dim = *axis.get(&self.axis) as usize;
         if let Some(true) = self.can_move(dim as u8, dir) {
             if let Some(new_pos) = if dir == Direction::Positive {
@@ -138,68 +332,205 @@ impl<const DIMS: usize> MazeView for MazeImpl<DIMS> {
                 self.position[dim].checked_sub(1)
             } {
                 self.position[dim] = new_pos;
+                self.visit(self.position);
+                return true;
             }
         }
+        false
     }
 
     fn can_move(&self, dim: u8, dir: Direction) -> Option<bool> {
-        let dim = dim as usize;
-        let mut pos = self.position;
-        if dir == Direction::Negative {
-            if let Some(new_pos) = pos[dim].checked_sub(1) {
-                pos[dim] = new_pos;
-            } else {
-                return None;
-            }
+        self.maze
+            .can_move_signed(&self.position, dim as usize, dir == Direction::Positive)
+    }
+
+    fn at_goal(&self) -> bool {
+        self.position == self.goal
+    }
+
+    fn optimal_moves(&self) -> usize {
+        self.optimal_moves
+    }
+
+    fn step_toward_goal(&self) -> Option<(u8, Direction)> {
+        let path = self.maze.shortest_path(&self.position, &self.goal)?;
+        let next = path.get(1)?;
+        let dim = (0..DIMS).find(|dim| next[*dim] != self.position[*dim])?;
+        let dir = if next[dim] > self.position[dim] {
+            Direction::Positive
+        } else {
+            Direction::Negative
+        };
+        Some((dim as u8, dir))
+    }
+
+    fn start_in_current(&self) -> Option<[u16; 2]> {
+        self.project(&self.start)
+    }
+
+    fn goal_in_current(&self) -> Option<[u16; 2]> {
+        self.project(&self.goal)
+    }
+
+    fn restore(&mut self, position: &[u16], axis: [u8; 2]) -> Result<(), LevelError> {
+        if axis[0] == axis[1] || axis.iter().any(|&a| a as usize >= DIMS) {
+            return Err(LevelError::InvalidSave(
+                "displays dimensions the maze doesn't have",
+            ));
         }
-        self.maze.can_move(&pos, dim)
+        let position = self
+            .cell(position)
+            .ok_or(LevelError::InvalidSave("stands outside the maze"))?;
+        self.position = position;
+        self.axis = axis;
+        if axis.contains(&self.depth) {
+            let hidden = self.hidden_dimensions().next();
+            self.depth = hidden.unwrap_or(self.depth);
+        }
+        self.visit(position);
+        Ok(())
     }
 
-    fn wall_in_current(&self, position: [u8; 2], axis: Axis) -> bool {
-        let mut cursor = self.position;
-        cursor[self.axis[0] as usize] = position[0];
-        cursor[self.axis[1] as usize] = position[1];
+    fn visited_count(&self) -> usize {
+        self.visited.len()
+    }
+
+    fn trail(&self) -> Vec<Vec<u16>> {
+        self.trail.iter().map(|cell| cell.to_vec()).collect()
+    }
+
+    fn restore_trail(&mut self, trail: &[Vec<u16>]) -> Result<(), LevelError> {
+        let cells = trail
+            .iter()
+            .map(|cell| self.cell(cell))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(LevelError::InvalidSave("visited cells outside the maze"))?;
+        for cell in cells {
+            self.visit(cell);
+        }
+        Ok(())
+    }
+
+    fn fingerprint(&self) -> u64 {
+        self.maze.fingerprint()
+    }
+
+    fn trail_in_current(&self) -> Vec<[u16; 2]> {
+        self.trail
+            .iter()
+            .filter_map(|cell| self.project(cell))
+            .collect()
+    }
+
+    fn visited_in_current(&self, cell: [u16; 2]) -> bool {
+        self.visited.contains(&self.cursor(cell))
+    }
+
+    fn wall_in_current(&self, position: [u16; 2], axis: Axis) -> bool {
+        let cursor = self.cursor(position);
         if let Some(walkable) = self.maze.can_move(&cursor, *axis.get(&self.axis) as usize) {
             !walkable
         } else {
             false
         }
     }
+
+    fn wall_in_layer(&self, position: [u16; 2], axis: Axis, layer: u16) -> bool {
+        let mut cursor = self.cursor(position);
+        match self.depth_axis() {
+            Some(depth) => cursor[depth as usize] = layer,
+            None => return false,
+        }
+        self.maze.can_move(&cursor, *axis.get(&self.axis) as usize) == Some(false)
+    }
 }
 
 pub trait MazeView: Sync + Send {
     fn axis(&self) -> [u8; 2];
+    /// Cycles the dimension shown on `axis`, skipping the one shown on the other axis.
     fn shift_axis(&mut self, axis: Axis, dir: Direction);
-
-    fn dims_limit(&self) -> &[u8];
-    fn dims(&self) -> &[u8];
-    fn pos_limit(&self) -> [u8; 2];
-    fn pos(&self) -> [u8; 2];
-    fn move_pos(&mut self, axis: Axis, dir: Direction);
+    /// The hidden dimension whose neighboring layers can be drawn above and below the slice,
+    /// or `None` when the maze has nothing hidden. Never one of the displayed dimensions.
+    fn depth_axis(&self) -> Option<u8>;
+    /// Cycles the depth axis through the hidden dimensions.
+    fn shift_depth(&mut self, dir: Direction);
+
+    fn dims_limit(&self) -> &[u16];
+    fn dims(&self) -> &[u16];
+    fn pos_limit(&self) -> [u16; 2];
+    fn pos(&self) -> [u16; 2];
+    /// Steps along a displayed axis if the wall that way is open, returning whether it moved.
+    fn move_pos(&mut self, axis: Axis, dir: Direction) -> bool;
 
     fn can_move(&self, dim: u8, dir: Direction) -> Option<bool>;
 
-    fn wall_in_current(&self, position: [u8; 2], axis: Axis) -> bool;
+    /// Whether the player is on the goal in every dimension, displayed or not.
+    fn at_goal(&self) -> bool;
+    /// Moves on a shortest path from the start to the goal.
+    fn optimal_moves(&self) -> usize;
+    /// The dimension and direction of the next move on a shortest path to the goal, or
+    /// `None` if the player is already there.
+    fn step_toward_goal(&self) -> Option<(u8, Direction)>;
+    /// Where the start is in the displayed slice, if the slice contains it.
+    fn start_in_current(&self) -> Option<[u16; 2]>;
+    /// Where the goal is in the displayed slice, if the slice contains it.
+    fn goal_in_current(&self) -> Option<[u16; 2]>;
+    /// Jumps back to a `position` and `axis` saved from `dims` and `axis`, refusing a
+    /// position outside the maze or axes that aren't two of its dimensions.
+    fn restore(&mut self, position: &[u16], axis: [u8; 2]) -> Result<(), LevelError>;
+    /// How many distinct cells the player has entered, counting the start.
+    fn visited_count(&self) -> usize;
+    /// Every visited cell in the order it was first entered, across all dimensions.
+    fn trail(&self) -> Vec<Vec<u16>>;
+    /// Marks each cell of a saved `trail` as visited, after any already visited, refusing
+    /// the whole trail if any cell is outside the maze.
+    fn restore_trail(&mut self, trail: &[Vec<u16>]) -> Result<(), LevelError>;
+    /// The generated maze's `Maze::fingerprint`, which ignores where the player is.
+    fn fingerprint(&self) -> u64;
+    /// The visited cells in the displayed slice, in the order they were first entered.
+    fn trail_in_current(&self) -> Vec<[u16; 2]>;
+    /// Whether the player has entered `cell` of the displayed slice.
+    fn visited_in_current(&self, cell: [u16; 2]) -> bool;
+
+    fn wall_in_current(&self, position: [u16; 2], axis: Axis) -> bool;
+    /// Like `wall_in_current`, but in the layer at `layer` along the depth axis rather than
+    /// the player's, and never a wall without a depth axis.
+    fn wall_in_layer(&self, position: [u16; 2], axis: Axis, layer: u16) -> bool;
 }
 
 pub struct MazeLevel {
     inner: Box<dyn MazeView>,
+    /// The seed the maze was generated from, if it was generated rather than loaded.
+    seed: Option<u64>,
 }
 
 impl Default for MazeLevel {
     fn default() -> Self {
         Self {
             inner: Box::new(MazeImpl::<2>::default()),
+            seed: None,
         }
     }
 }
 
 impl MazeLevel {
-    pub fn new<const DIMS: usize>(lengths: &[u8; DIMS], rng: &mut impl rand::Rng) -> Self {
+    pub fn from_maze<const DIMS: usize>(maze: maze::Maze<DIMS>) -> Self {
+        Self {
+            inner: Box::new(MazeImpl::from_maze(maze)),
+            seed: None,
+        }
+    }
+
+    pub fn with_seed(self, seed: u64) -> Self {
         Self {
-            inner: Box::new(MazeImpl::new(lengths, rng)),
+            seed: Some(seed),
+            ..self
         }
     }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
 }
 
 impl Deref for MazeLevel {
@@ -217,25 +548,377 @@ impl DerefMut for MazeLevel {
 }
 
 impl MazeLevel {
-    pub fn iter_walls(&self) -> impl std::iter::Iterator<Item = ([u8; 2], [u8; 2])> + '_ {
-        let [length_x, length_y] = self.pos_limit();
+    /// The fraction of the maze's cells the player has entered.
+    pub fn coverage(&self) -> f32 {
+        let cells: usize = self.dims_limit().iter().map(|l| *l as usize).product();
+        self.visited_count() as f32 / cells.max(1) as f32
+    }
 
+    /// How many dimensions the maze has, displayed or not.
+    pub fn dimension_count(&self) -> usize {
+        self.dims_limit().len()
+    }
+
+    /// The maze's length along the dimension shown on `axis`.
+    pub fn length_of_axis(&self, axis: Axis) -> u16 {
+        *axis.get(&self.pos_limit())
+    }
+
+    /// The closed walls between cells of the displayed slice, leaving out its border, in
+    /// order along the first displayed axis and then the second. The renderer, minimap and
+    /// debug view all draw from this.
+    pub fn walls_in_current_slice(&self) -> impl Iterator<Item = SliceWall> + '_ {
+        self.slice_walls(move |wall| self.wall_in_current(wall.cell, wall.axis))
+    }
+
+    /// The coordinate along the depth axis `offset` layers from the player's, if there is a
+    /// depth axis and the maze reaches that far along it.
+    pub fn layer(&self, offset: i32) -> Option<u16> {
+        let depth = self.depth_axis()? as usize;
+        let layer = self.dims()[depth] as i32 + offset;
+        (0..self.dims_limit()[depth] as i32)
+            .contains(&layer)
+            .then_some(layer as u16)
+    }
+
+    /// Like `walls_in_current_slice`, for the layer at `layer` along the depth axis.
+    pub fn walls_in_layer(&self, layer: u16) -> impl Iterator<Item = SliceWall> + '_ {
+        self.slice_walls(move |wall| self.wall_in_layer(wall.cell, wall.axis, layer))
+    }
+
+    fn slice_walls<'a>(
+        &'a self,
+        closed: impl Fn(&SliceWall) -> bool + 'a,
+    ) -> impl Iterator<Item = SliceWall> + 'a {
+        let (length_x, length_y) = (self.length_of_axis(Axis::X), self.length_of_axis(Axis::Y));
         (0..length_x)
             .flat_map(move |x| (0..length_y).map(move |y| [x, y]))
-            .flat_map(move |cursor| {
-                [
-                    if self.wall_in_current(cursor, Axis::X) {
-                        Some((cursor, [cursor[0] + 1, cursor[1]]))
-                    } else {
-                        None
-                    },
-                    if self.wall_in_current(cursor, Axis::Y) {
-                        Some((cursor, [cursor[0], cursor[1] + 1]))
-                    } else {
-                        None
-                    },
-                ]
-            })
-            .flatten()
+            .flat_map(move |cell| [Axis::X, Axis::Y].map(|axis| SliceWall { cell, axis }))
+            .filter(closed)
+    }
+
+    /// The displayed slice drawn as text, with the first displayed axis across, using `+`,
+    /// `-` and `|` for walls and gaps for open ones.
+    pub fn render_slice_ascii(&self) -> String {
+        let walls: HashSet<SliceWall> = self.walls_in_current_slice().collect();
+        let closed = |cell, axis| walls.contains(&SliceWall { cell, axis });
+        let (width, height) = (self.length_of_axis(Axis::X), self.length_of_axis(Axis::Y));
+        let border = format!("+{}\n", "-+".repeat(width as usize));
+
+        let mut out = border.clone();
+        for y in 0..height {
+            out.push('|');
+            for x in 0..width {
+                out.push(' ');
+                let wall = x + 1 == width || closed([x, y], Axis::X);
+                out.push(if wall { '|' } else { ' ' });
+            }
+            out.push('\n');
+            if y + 1 == height {
+                break;
+            }
+            out.push('+');
+            for x in 0..width {
+                out.push(if closed([x, y], Axis::Y) { '-' } else { ' ' });
+                out.push('+');
+            }
+            out.push('\n');
+        }
+        out.push_str(&border);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    fn seeded_level<const DIMS: usize>(lengths: &[u16; DIMS]) -> MazeLevel {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        MazeLevel::from_maze(maze::Maze::new(lengths, &mut rng))
+    }
+
+    fn check_axis_cycle<const DIMS: usize>() {
+        let mut level = seeded_level(&[2; DIMS]);
+        for (axis, dir) in [
+            (Axis::X, Direction::Positive),
+            (Axis::X, Direction::Negative),
+            (Axis::Y, Direction::Positive),
+            (Axis::Y, Direction::Negative),
+        ] {
+            let other = *axis.invert().get(&level.axis());
+            let mut seen = Vec::new();
+            for _ in 0..DIMS - 1 {
+                level.shift_axis(axis, dir);
+                let [x, y] = level.axis();
+                assert_ne!(x, y);
+                assert!((x as usize) < DIMS && (y as usize) < DIMS);
+                assert_eq!(*axis.invert().get(&level.axis()), other);
+                seen.push(*axis.get(&level.axis()));
+            }
+            seen.sort_unstable();
+            let expected: Vec<u8> = (0..DIMS as u8).filter(|d| *d != other).collect();
+            assert_eq!(seen, expected);
+        }
+    }
+
+    #[test]
+    fn shift_axis_cycles_other_dimensions() {
+        check_axis_cycle::<2>();
+        check_axis_cycle::<3>();
+        check_axis_cycle::<4>();
+        check_axis_cycle::<6>();
+    }
+
+    #[test]
+    fn depth_axis_stays_hidden() {
+        assert_eq!(seeded_level(&[3, 3]).depth_axis(), None);
+
+        let mut level = seeded_level(&[3, 3, 3, 3]);
+        assert_eq!(level.depth_axis(), Some(2));
+        level.shift_depth(Direction::Positive);
+        assert_eq!(level.depth_axis(), Some(3));
+        level.shift_depth(Direction::Positive);
+        assert_eq!(level.depth_axis(), Some(2));
+        level.shift_depth(Direction::Negative);
+        assert_eq!(level.depth_axis(), Some(3));
+
+        // Rotating the depth dimension into view swaps the displayed one into the depth.
+        level.shift_axis(Axis::Y, Direction::Negative);
+        assert_eq!(level.axis(), [0, 3]);
+        assert_eq!(level.depth_axis(), Some(1));
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..50 {
+            let axis = if rng.gen() { Axis::X } else { Axis::Y };
+            level.shift_axis(axis, Direction::Positive);
+            let depth = level.depth_axis().unwrap();
+            assert!(!level.axis().contains(&depth));
+        }
+        level.restore(&[0, 0, 0, 0], [0, 1]).unwrap();
+        assert!(!level.axis().contains(&level.depth_axis().unwrap()));
+    }
+
+    #[test]
+    fn layers_stop_at_the_edge_of_the_maze() {
+        let mut level = seeded_level(&[4, 4, 3]);
+        assert_eq!(level.layer(-1), None);
+        assert_eq!(level.layer(0), Some(0));
+        assert_eq!(level.layer(1), Some(1));
+        level.restore(&[0, 0, 2], [0, 1]).unwrap();
+        assert_eq!(level.layer(1), None);
+        assert_eq!(level.layer(-1), Some(1));
+        assert_eq!(seeded_level(&[4, 4]).layer(0), None);
+
+        // The player's own layer is the displayed slice.
+        assert_eq!(
+            level.walls_in_layer(2).collect::<Vec<_>>(),
+            level.walls_in_current_slice().collect::<Vec<_>>()
+        );
+        let below: Vec<_> = level.walls_in_layer(1).collect();
+        level.restore(&[0, 0, 1], [0, 1]).unwrap();
+        assert_eq!(below, level.walls_in_current_slice().collect::<Vec<_>>());
+    }
+
+    fn seeded_impl<const DIMS: usize>(lengths: &[u16; DIMS]) -> MazeImpl<DIMS> {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        MazeImpl::from_maze(maze::Maze::new(lengths, &mut rng))
+    }
+
+    #[test]
+    fn goal_is_deterministic() {
+        let first = seeded_impl(&[5, 4, 3]);
+        let second = seeded_impl(&[5, 4, 3]);
+        assert_eq!(first.goal, second.goal);
+        assert_ne!(first.goal, first.position);
+        assert_eq!(
+            first.maze.farthest_from(&first.position),
+            Some((
+                first.goal,
+                first.maze.distance(&[0; 3], &first.goal).unwrap()
+            ))
+        );
+        assert_eq!(
+            Some(first.optimal_moves),
+            first.maze.distance(&first.start, &first.goal)
+        );
+    }
+
+    #[test]
+    fn single_cell_goal_is_the_start() {
+        let level = seeded_impl(&[1, 1]);
+        assert_eq!(level.goal, level.start);
+        assert_eq!(level.optimal_moves, 0);
+        assert!(level.at_goal());
+    }
+
+    #[test]
+    fn goal_checks_hidden_dimensions() {
+        let mut level = seeded_impl(&[3, 3, 3]);
+        level.goal = [2, 1, 2];
+        level.position = [2, 1, 0];
+        assert_eq!(level.pos(), [2, 1]);
+        assert!(!level.at_goal());
+        assert_eq!(level.goal_in_current(), None);
+
+        level.position = [0, 0, 2];
+        assert!(!level.at_goal());
+        assert_eq!(level.goal_in_current(), Some([2, 1]));
+
+        level.position = [2, 1, 2];
+        assert!(level.at_goal());
+        assert_eq!(level.start_in_current(), None);
+
+        level.position = [1, 1, 0];
+        assert_eq!(level.start_in_current(), Some([0, 0]));
+    }
+
+    #[test]
+    fn move_pos_follows_walls() {
+        let mut level = seeded_level(&[6, 6]);
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..200 {
+            let axis = if rng.gen() { Axis::X } else { Axis::Y };
+            let dir = if rng.gen() {
+                Direction::Positive
+            } else {
+                Direction::Negative
+            };
+            let dim = *axis.get(&level.axis());
+            let before = level.pos();
+            let open = level.can_move(dim, dir) == Some(true);
+
+            assert_eq!(level.move_pos(axis, dir), open);
+            let mut expected = before;
+            if open {
+                let coord = axis.get_mut(&mut expected);
+                *coord = match dir {
+                    Direction::Positive => *coord + 1,
+                    Direction::Negative => *coord - 1,
+                };
+            }
+            assert_eq!(level.pos(), expected);
+        }
+    }
+
+    #[test]
+    fn move_pos_stops_at_zero() {
+        let mut level = seeded_level(&[4, 4]);
+        assert!(!level.move_pos(Axis::X, Direction::Negative));
+        assert!(!level.move_pos(Axis::Y, Direction::Negative));
+        assert_eq!(level.pos(), [0, 0]);
+    }
+
+    #[test]
+    fn move_pos_on_unit_axis() {
+        let mut level = seeded_level(&[4, 1]);
+        assert!(!level.move_pos(Axis::Y, Direction::Positive));
+        assert!(!level.move_pos(Axis::Y, Direction::Negative));
+        assert!(level.move_pos(Axis::X, Direction::Positive));
+        assert_eq!(level.pos(), [1, 0]);
+    }
+
+    #[test]
+    fn render_slice_follows_displayed_axes() {
+        let mut level = seeded_level(&[5, 1, 1]);
+        assert_eq!(
+            level.render_slice_ascii(),
+            "+-+-+-+-+-+\n|         |\n+-+-+-+-+-+\n"
+        );
+        level.shift_axis(Axis::X, Direction::Positive);
+        assert_eq!(level.axis(), [2, 1]);
+        assert_eq!(level.render_slice_ascii(), "+-+\n| |\n+-+\n");
+    }
+
+    #[test]
+    fn slice_walls_match_the_maze() {
+        let maze = || {
+            let mut rng = StdRng::seed_from_u64(684153987);
+            maze::Maze::new(&[5, 4, 3], &mut rng)
+        };
+        let (mut level, maze) = (MazeLevel::from_maze(maze()), maze());
+        level.restore(&[1, 2, 1], [0, 1]).unwrap();
+        for (axis, dir) in [
+            (Axis::Y, Direction::Positive),
+            (Axis::X, Direction::Positive),
+            (Axis::X, Direction::Positive),
+            (Axis::Y, Direction::Negative),
+        ] {
+            let displayed = level.axis().map(|dim| dim as usize);
+            let position: [u16; 3] = level.dims().try_into().unwrap();
+            // The maze's own drawing agrees with the one made from the slice walls.
+            assert_eq!(
+                level.render_slice_ascii(),
+                maze.render_slice_ascii(displayed[0], displayed[1], &position)
+                    .unwrap()
+            );
+            for wall in level.walls_in_current_slice() {
+                let mut cell = position;
+                cell[displayed[0]] = wall.cell[0];
+                cell[displayed[1]] = wall.cell[1];
+                let dimension = wall.dimension(level.axis());
+                assert_eq!(maze.can_move(&cell, dimension), Some(false));
+            }
+            level.shift_axis(axis, dir);
+        }
+    }
+
+    #[test]
+    fn blocked_moves_find_their_wall() {
+        let blocked = |dimension, positive, out_of_bounds| MoveBlocked {
+            position: [3, 2],
+            dimension,
+            positive,
+            out_of_bounds,
+        };
+        let wall = |x, y, axis| Some(SliceWall { cell: [x, y], axis });
+        assert_eq!(
+            SliceWall::blocking([1, 2], &blocked(1, true, false)),
+            wall(3, 2, Axis::X)
+        );
+        assert_eq!(
+            SliceWall::blocking([1, 2], &blocked(2, false, false)),
+            wall(3, 1, Axis::Y)
+        );
+        assert_eq!(SliceWall::blocking([1, 2], &blocked(1, true, true)), None);
+        assert_eq!(SliceWall::blocking([1, 2], &blocked(0, true, false)), None);
+    }
+
+    #[test]
+    fn slice_queries_follow_displayed_axes() {
+        let mut level = seeded_level(&[5, 4, 3]);
+        assert_eq!(level.dimension_count(), 3);
+        assert_eq!(level.length_of_axis(Axis::X), 5);
+        assert_eq!(level.length_of_axis(Axis::Y), 4);
+        level.shift_axis(Axis::Y, Direction::Positive);
+        assert_eq!(level.length_of_axis(Axis::Y), 3);
+
+        let wall = SliceWall {
+            cell: [2, 1],
+            axis: Axis::Y,
+        };
+        assert_eq!(wall.neighbor(), [2, 2]);
+        assert_eq!(wall.dimension(level.axis()), 2);
+    }
+
+    #[test]
+    fn visited_tracks_entered_cells() {
+        let mut level = seeded_level(&[5, 1, 1]);
+        assert_eq!(level.visited_count(), 1);
+        assert!(level.move_pos(Axis::X, Direction::Positive));
+        assert!(level.move_pos(Axis::X, Direction::Positive));
+        assert!(level.move_pos(Axis::X, Direction::Negative));
+        assert_eq!(level.visited_count(), 3);
+        assert_eq!(level.coverage(), 3.0 / 5.0);
+        assert!(level.visited_in_current([2, 0]));
+        assert!(!level.visited_in_current([3, 0]));
+
+        // The new slice only holds the cell under the player, which has been visited.
+        level.shift_axis(Axis::X, Direction::Positive);
+        assert!(level.visited_in_current([0, 0]));
+        assert_eq!(level.trail_in_current(), [[0, 0]]);
+        level.restore(&[4, 0, 0], [0, 1]).unwrap();
+        assert_eq!(level.visited_count(), 4);
+        assert_eq!(level.trail_in_current(), [[0, 0], [1, 0], [2, 0], [4, 0]]);
     }
 }
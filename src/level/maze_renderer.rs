@@ -1,14 +1,73 @@
-use std::f32::consts::PI;
+use std::{
+    collections::{HashMap, HashSet},
+    f32::consts::PI,
+    time::Duration,
+};
 
-use super::{loader::MazeAssets, maze_level::*};
-use bevy::prelude::*;
+use super::{
+    animation::{Despawning, MoveAnimation, Resize, Slide},
+    bindings::{Action, ActionInput},
+    bump::WallFlashes,
+    loader::{LevelEntity, MazeAssets},
+    maze_level::{Axis, *},
+    trail::TrailMarkers,
+};
+use crate::toast::{ShowToast, ToastKind};
+use bevy::{ecs::system::SystemParam, pbr::NotShadowCaster, prelude::*};
+
+/// Width of a cell in world units; everything in a slice is laid out on this grid.
+pub const CELL_SIZE: f32 = 1.0;
+
+/// Height of the floor tiles, at the bottom of the walls.
+pub const FLOOR_HEIGHT: f32 = -0.3;
+
+/// How far above or below the slice, in cells, the volume view draws each neighboring layer.
+pub const LAYER_SPACING: f32 = 1.5;
+
+/// The layers the volume view draws, by how far along the depth axis they are from the
+/// player's.
+const GHOST_LAYERS: [i32; 2] = [-1, 1];
+
+/// Center of `cell` in the displayed slice, at floor level.
+pub fn cell_center(cell: [u16; 2]) -> Vec3 {
+    Vec3::new(cell[0] as f32, 0.0, cell[1] as f32) * CELL_SIZE
+}
+
+/// Where the layer `offset` layers along the depth axis from the player's is drawn, relative
+/// to the slice. Higher layers are drawn higher.
+pub fn layer_offset(offset: i32) -> Vec3 {
+    Vec3::Y * offset as f32 * LAYER_SPACING * CELL_SIZE
+}
+
+/// How walls are drawn around cells the player has not entered yet.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FogOfWar {
+    /// Every wall is drawn normally.
+    Off,
+    /// Unexplored walls are drawn faded.
+    #[default]
+    Dim,
+    /// Unexplored walls are not drawn at all, so the maze reveals itself as it is walked.
+    Hide,
+}
+
+/// Whether the layers either side of the slice along the depth axis are drawn as well.
+#[derive(Default)]
+pub struct VolumeView {
+    pub shown: bool,
+}
 
 pub fn spawn_maze_root(mut c: Commands) {
     c.spawn_bundle(MazeRendererBundle {
-        renderer: MazeRenderer { last_axis: [0, 0] },
+        renderer: Default::default(),
         transform: Default::default(),
         global_transform: Default::default(),
-    });
+    })
+    .insert(Name::new("maze"))
+    .insert(TrailMarkers::default())
+    .insert(WallFlashes::default())
+    .insert(GhostLayers::default())
+    .insert(LevelEntity);
 }
 
 #[derive(Bundle)]
@@ -18,106 +77,608 @@ pub struct MazeRendererBundle {
     pub global_transform: GlobalTransform,
 }
 
-#[derive(Component)]
+#[derive(Component, Default)]
 pub struct MazeRenderer {
-    last_axis: [u8; 2],
+    /// The slice currently spawned, if any.
+    slice: Option<Slice>,
+    /// The border, joint and floor groups, which only depend on the slice size.
+    frame: Vec<Entity>,
+    walls_group: Option<Entity>,
+    /// Each spawned wall, and the material it was drawn with.
+    walls: HashMap<MazeWall, (Entity, Handle<StandardMaterial>)>,
+    /// The start and goal tiles, when the slice contains them.
+    markers: Vec<Entity>,
+}
+
+impl MazeRenderer {
+    /// The spawned entity for `wall`, and the material it is drawn with.
+    pub fn wall(&self, wall: &MazeWall) -> Option<(Entity, Handle<StandardMaterial>)> {
+        self.walls.get(wall).cloned()
+    }
+}
+
+/// Everything the spawned slice entities depend on.
+#[derive(Clone, PartialEq)]
+struct Slice {
+    axis: [u8; 2],
+    limit: [u16; 2],
+    /// The player position with the displayed coordinates zeroed.
+    hidden: Vec<u16>,
+    /// Cells entered so far, which only grows, so it changes whenever the fog does.
+    visited: usize,
+}
+
+impl Slice {
+    fn of(level: &MazeLevel) -> Self {
+        let axis = level.axis();
+        let mut hidden = level.dims().to_vec();
+        for dim in axis {
+            hidden[dim as usize] = 0;
+        }
+        Self {
+            axis,
+            limit: level.pos_limit(),
+            hidden,
+            visited: level.visited_count(),
+        }
+    }
+}
+
+/// The layers spawned for the volume view under a maze root.
+#[derive(Component, Default)]
+pub struct GhostLayers {
+    layers: Option<Layers>,
+    groups: Vec<Entity>,
+}
+
+/// Everything the spawned layers depend on.
+#[derive(Clone, Debug, PartialEq)]
+struct Layers {
+    axis: [u8; 2],
+    depth: u8,
+    /// The player position with the displayed coordinates zeroed, which holds the layer
+    /// the others are drawn around.
+    hidden: Vec<u16>,
+}
+
+impl Layers {
+    /// The layers to draw, or `None` with the view off, nothing hidden to draw layers of, or
+    /// fog that hides whatever the player hasn't walked through.
+    fn of(level: &MazeLevel, view: &VolumeView, fog: FogOfWar) -> Option<Self> {
+        if !view.shown || fog == FogOfWar::Hide {
+            return None;
+        }
+        let depth = level.depth_axis()?;
+        let Slice { axis, hidden, .. } = Slice::of(level);
+        Some(Self {
+            axis,
+            depth,
+            hidden,
+        })
+    }
+}
+
+/// The slice wall a wall entity was spawned for.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct MazeWall(pub SliceWall);
+
+/// The walls of the displayed slice to draw, and whether each is next to a visited cell.
+fn visible_walls(level: &MazeLevel, fog: FogOfWar) -> HashMap<MazeWall, bool> {
+    level
+        .walls_in_current_slice()
+        .map(|wall| {
+            let seen =
+                level.visited_in_current(wall.cell) || level.visited_in_current(wall.neighbor());
+            (MazeWall(wall), seen || fog == FogOfWar::Off)
+        })
+        .filter(|(_, seen)| *seen || fog != FogOfWar::Hide)
+        .collect()
+}
+
+/// Walls to despawn and walls to spawn to turn the `current` slice into `next`.
+fn diff_walls(
+    current: &HashSet<MazeWall>,
+    next: &HashSet<MazeWall>,
+) -> (Vec<MazeWall>, Vec<MazeWall>) {
+    let removed = current.difference(next).copied().collect();
+    let added = next.difference(current).copied().collect();
+    (removed, added)
 }
 
 pub fn update_maze_offset(
+    mut c: Commands,
     level: Res<MazeLevel>,
-    mut maze_query: Query<(&MazeRenderer, &mut Transform)>,
+    animation: Res<MoveAnimation>,
+    mut maze_query: Query<(Entity, &mut Transform, Option<&mut Slide>), With<MazeRenderer>>,
     mut position_changed: EventReader<PositionChanged>,
     mut axis_changed: EventReader<AxisChanged>,
 ) {
-    let mut update_pos = || {
-        for (_, mut trs) in maze_query.iter_mut() {
-            let p = level.pos();
-            trs.translation = Vec3::new(-(p[0] as f32), 0.0, -(p[1] as f32))
-        }
+    let moved_to = position_changed.iter().last().map(|changed| changed.to);
+    let (position, duration) = if axis_changed.iter().count() > 0 {
+        (level.pos(), animation.axis_change.unwrap_or(Duration::ZERO))
+    } else if let Some(position) = moved_to {
+        (position, animation.movement)
+    } else {
+        return;
     };
-    for _ in position_changed.iter() {
-        update_pos();
+
+    // The player stays put while the maze slides underneath it.
+    let target = -cell_center(position);
+    for (entity, mut transform, slide) in maze_query.iter_mut() {
+        if duration.is_zero() {
+            transform.translation = target;
+        }
+        match slide {
+            Some(mut slide) => slide.retarget(target, duration),
+            None => {
+                c.entity(entity).insert(Slide::new(
+                    transform.translation,
+                    target,
+                    duration,
+                    animation.easing,
+                ));
+            }
+        }
     }
-    for _ in axis_changed.iter() {
-        update_pos();
+}
+
+/// How walls are drawn: faded by the fog, and growing into and shrinking out of the slice
+/// when the displayed axes change.
+#[derive(SystemParam)]
+pub struct WallStyle<'w, 's> {
+    fog: Res<'w, FogOfWar>,
+    animation: Res<'w, MoveAnimation>,
+    resizes: Query<'w, 's, &'static Resize>,
+}
+
+impl<'w, 's> WallStyle<'w, 's> {
+    /// The scale `wall` is drawn at, partway through growing in if it only just entered.
+    fn scale(&self, wall: Entity) -> f32 {
+        self.resizes.get(wall).map_or(1.0, Resize::scale)
     }
 }
 
 pub fn maze_level_renderer(
     level: Res<MazeLevel>,
     assets: Res<MazeAssets>,
+    style: WallStyle,
     mut commands: Commands,
     mut render_query: Query<(Entity, &mut MazeRenderer)>,
     mut axis_changed: EventReader<AxisChanged>,
+    mut position_changed: EventReader<PositionChanged>,
 ) {
-    for _ in axis_changed.iter() {
-        for (entity, mut renderer) in render_query.iter_mut() {
-            if renderer.last_axis == level.axis() {
-                continue;
+    // Moves reveal walls through the fog, and an undo can jump to another slice.
+    let axis_changes: Vec<&AxisChanged> = axis_changed.iter().collect();
+    if axis_changes.len() + position_changed.iter().count() == 0 {
+        return;
+    }
+    let slice = Slice::of(&level);
+    // Only walls swapped by a change of axes animate. Fog walls appear beside the player,
+    // and a fresh level has nothing to transition from.
+    let rotated = axis_changes
+        .iter()
+        .any(|changed| changed.from != changed.to);
+    let transition = match style.animation.wall_transition {
+        duration if rotated && !duration.is_zero() => Some(duration),
+        _ => None,
+    };
+    let easing = style.animation.easing;
+
+    for (entity, mut renderer) in render_query.iter_mut() {
+        if renderer.slice.as_ref() == Some(&slice) {
+            continue;
+        }
+        let resized = renderer.slice.as_ref().map(|s| s.limit) != Some(slice.limit);
+        renderer.slice = Some(slice.clone());
+
+        if resized {
+            for group in renderer.frame.drain(..) {
+                commands.entity(group).despawn_recursive();
+            }
+            let mut frame = Vec::new();
+            commands.entity(entity).with_children(|builder| {
+                frame.push(spawn_borders(builder, &assets, slice.limit));
+                frame.push(spawn_joints(builder, &assets, slice.limit));
+                frame.push(spawn_floor(builder, &assets, slice.limit));
+            });
+            renderer.frame = frame;
+        }
+
+        let walls_group = match renderer.walls_group {
+            Some(group) => group,
+            None => {
+                let mut group = None;
+                commands.entity(entity).with_children(|builder| {
+                    group = Some(spawn_group(builder, "walls", |_| {}));
+                });
+                let group = group.unwrap();
+                renderer.walls_group = Some(group);
+                group
             }
-            renderer.last_axis = level.axis();
-
-            let mut entity = commands.entity(entity);
-            entity.despawn_descendants();
-            entity.with_children(|builder| {
-                // borders
-                let [px, py] = level.pos_limit();
-                let lx = px as f32;
-                let ly = py as f32;
-                builder.spawn_bundle(
-                    assets.wall(
-                        Transform::from_xyz((lx / 2.0) - 0.5, 0.0, -0.5)
-                            .with_scale(Vec3::new(1.0, 1.0, lx))
-                            .with_rotation(Quat::from_rotation_y(PI / 2.0)),
-                    ),
-                );
-                builder.spawn_bundle(
-                    assets.wall(
-                        Transform::from_xyz((lx / 2.0) - 0.5, 0.0, ly - 0.5)
-                            .with_scale(Vec3::new(1.0, 1.0, lx))
-                            .with_rotation(Quat::from_rotation_y(PI / 2.0)),
-                    ),
-                );
-                builder.spawn_bundle(
-                    assets.wall(
-                        Transform::from_xyz(-0.5, 0.0, (ly / 2.0) - 0.5)
-                            .with_scale(Vec3::new(1.0, 1.0, ly)),
-                    ),
-                );
-                builder.spawn_bundle(
-                    assets.wall(
-                        Transform::from_xyz(lx - 0.5, 0.0, (ly / 2.0) - 0.5)
-                            .with_scale(Vec3::new(1.0, 1.0, ly)),
-                    ),
-                );
-
-                // joints
-                let [psx, psy] = level.pos_limit();
-                for x in 0..psx + 1 {
-                    for y in 0..psy + 1 {
-                        builder.spawn_bundle(assets.joint(Transform::from_xyz(
-                            x as f32 - 0.5,
-                            0.0,
-                            y as f32 - 0.5,
-                        )));
+        };
+        let visible = visible_walls(&level, *style.fog);
+        let next: HashSet<MazeWall> = visible.keys().copied().collect();
+        let current: HashSet<MazeWall> = renderer.walls.keys().copied().collect();
+        let (removed, added) = diff_walls(&current, &next);
+        // A leaving wall is forgotten at once, so coming back spawns a fresh one rather than
+        // racing its despawn, and despawns once it has shrunk away.
+        for key in removed {
+            if let Some((wall, _)) = renderer.walls.remove(&key) {
+                match transition {
+                    Some(duration) => {
+                        let shrink = Resize::shrink(style.scale(wall), duration, easing);
+                        commands
+                            .entity(wall)
+                            .remove::<MazeWall>()
+                            .insert_bundle((Despawning::after(duration), shrink));
                     }
+                    None => commands.entity(wall).despawn_recursive(),
                 }
+            }
+        }
+        // Walls kept across an axis change may now block another dimension.
+        let material =
+            |key: &MazeWall| assets.wall_material(key.0.dimension(slice.axis), visible[key]);
+        for (key, (wall, drawn)) in renderer.walls.iter_mut() {
+            let material = material(key);
+            if material != *drawn {
+                *drawn = material.clone();
+                commands.entity(*wall).insert(material);
+            }
+        }
+        for key in added {
+            let material = material(&key);
+            let mut bundle = PbrBundle {
+                material: material.clone(),
+                ..wall_bundle(&assets, key)
+            };
+            let grow = transition.map(|duration| Resize::new(0.0, 1.0, duration, easing));
+            if let Some(grow) = &grow {
+                grow.apply(&mut bundle.transform);
+            }
+            let mut wall = commands.spawn_bundle(bundle);
+            wall.insert(wall_name(key.0.cell, key.0.axis)).insert(key);
+            if let Some(grow) = grow {
+                wall.insert(grow);
+            }
+            let wall = wall.id();
+            commands.entity(walls_group).add_child(wall);
+            renderer.walls.insert(key, (wall, material));
+        }
 
-                // walls
-                for (v1, v2) in level.iter_walls() {
-                    let p1 = Vec3::new(v1[0] as f32, 0.0, v1[1] as f32);
-                    let p2 = Vec3::new(v2[0] as f32, 0.0, v2[1] as f32);
-                    let rotation = if v1[0] != v2[0] {
-                        Quat::IDENTITY
-                    } else {
-                        Quat::from_rotation_y(PI / 2.0)
-                    };
-                    let position = p1.lerp(p2, 0.5);
-                    builder.spawn_bundle(
-                        assets.wall(Transform::from_translation(position).with_rotation(rotation)),
-                    );
-                }
+        for marker in renderer.markers.drain(..) {
+            commands.entity(marker).despawn_recursive();
+        }
+        // Raised slightly so the markers draw over the floor tile they sit on.
+        let marker_transform =
+            |cell| Transform::from_translation(cell_center(cell) + Vec3::Y * (FLOOR_HEIGHT + 0.01));
+        let mut markers = Vec::new();
+        if let Some(start) = level.start_in_current() {
+            markers.push(
+                commands
+                    .spawn_bundle(assets.start(marker_transform(start)))
+                    .insert(Name::new("start"))
+                    .id(),
+            );
+        }
+        if let Some(goal) = level.goal_in_current() {
+            markers.push(
+                commands
+                    .spawn_bundle(assets.goal(marker_transform(goal)))
+                    .insert(Name::new("goal"))
+                    .id(),
+            );
+        }
+        commands.entity(entity).push_children(&markers);
+        renderer.markers = markers;
+    }
+}
+
+pub fn toggle_volume_view(
+    input: ActionInput,
+    level: Res<MazeLevel>,
+    mut view: ResMut<VolumeView>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if !input.just_pressed(Action::ToggleVolumeView) {
+        return;
+    }
+    view.shown = !view.shown;
+    if view.shown && level.depth_axis().is_none() {
+        toasts.send(ShowToast::new(
+            ToastKind::Info,
+            "Layers can only be shown with a third dimension",
+        ));
+    }
+}
+
+/// Draws the layers next to the slice along the depth axis, with translucent walls offset
+/// above and below it, respawning them whenever the player leaves the layer they surround.
+/// Every wall shares the one wall mesh, and each dimension's walls one material.
+pub fn maze_layer_renderer(
+    level: Res<MazeLevel>,
+    assets: Res<MazeAssets>,
+    fog: Res<FogOfWar>,
+    view: Res<VolumeView>,
+    mut commands: Commands,
+    mut roots: Query<(Entity, &mut GhostLayers), With<MazeRenderer>>,
+) {
+    let layers = Layers::of(&level, &view, *fog);
+    for (root, mut ghosts) in roots.iter_mut() {
+        if ghosts.layers == layers {
+            continue;
+        }
+        for group in ghosts.groups.drain(..) {
+            commands.entity(group).despawn_recursive();
+        }
+        ghosts.layers = layers.clone();
+        let axis = match &layers {
+            Some(layers) => layers.axis,
+            None => continue,
+        };
+        for offset in GHOST_LAYERS {
+            let layer = match level.layer(offset) {
+                Some(layer) => layer,
+                None => continue,
+            };
+            let mut group = None;
+            commands.entity(root).with_children(|builder| {
+                group = Some(spawn_group(
+                    builder,
+                    &format!("layer{:+}", offset),
+                    |builder| {
+                        for wall in level.walls_in_layer(layer) {
+                            builder
+                                .spawn_bundle(PbrBundle {
+                                    material: assets.ghost_wall_material(wall.dimension(axis)),
+                                    ..wall_bundle(&assets, MazeWall(wall))
+                                })
+                                .insert(wall_name(wall.cell, wall.axis))
+                                .insert(NotShadowCaster);
+                        }
+                    },
+                ));
             });
+            let group = group.unwrap();
+            commands
+                .entity(group)
+                .insert(Transform::from_translation(layer_offset(offset)));
+            ghosts.groups.push(group);
+        }
+    }
+}
+
+fn spawn_borders(builder: &mut ChildBuilder, assets: &MazeAssets, limit: [u16; 2]) -> Entity {
+    let [px, py] = limit;
+    let lx = px as f32;
+    let ly = py as f32;
+    let at = |x: f32, z: f32| Transform::from_translation(Vec3::new(x, 0.0, z) * CELL_SIZE);
+    spawn_group(builder, "borders", |builder| {
+        builder
+            .spawn_bundle(
+                assets.wall(
+                    at((lx / 2.0) - 0.5, -0.5)
+                        .with_scale(Vec3::new(1.0, 1.0, lx))
+                        .with_rotation(Quat::from_rotation_y(PI / 2.0)),
+                ),
+            )
+            .insert(Name::new("border-y"));
+        builder
+            .spawn_bundle(
+                assets.wall(
+                    at((lx / 2.0) - 0.5, ly - 0.5)
+                        .with_scale(Vec3::new(1.0, 1.0, lx))
+                        .with_rotation(Quat::from_rotation_y(PI / 2.0)),
+                ),
+            )
+            .insert(Name::new("border+y"));
+        builder
+            .spawn_bundle(
+                assets.wall(at(-0.5, (ly / 2.0) - 0.5).with_scale(Vec3::new(1.0, 1.0, ly))),
+            )
+            .insert(Name::new("border-x"));
+        builder
+            .spawn_bundle(
+                assets.wall(at(lx - 0.5, (ly / 2.0) - 0.5).with_scale(Vec3::new(1.0, 1.0, ly))),
+            )
+            .insert(Name::new("border+x"));
+    })
+}
+
+fn spawn_joints(builder: &mut ChildBuilder, assets: &MazeAssets, limit: [u16; 2]) -> Entity {
+    let [psx, psy] = limit;
+    let corner = Vec3::new(0.5, 0.0, 0.5) * CELL_SIZE;
+    spawn_group(builder, "joints", |builder| {
+        for x in 0..psx + 1 {
+            for y in 0..psy + 1 {
+                builder
+                    .spawn_bundle(
+                        assets.joint(Transform::from_translation(cell_center([x, y]) - corner)),
+                    )
+                    .insert(joint_name([x, y]));
+            }
+        }
+    })
+}
+
+fn spawn_floor(builder: &mut ChildBuilder, assets: &MazeAssets, limit: [u16; 2]) -> Entity {
+    let [px, py] = limit;
+    spawn_group(builder, "floor", |builder| {
+        for x in 0..px {
+            for y in 0..py {
+                builder
+                    .spawn_bundle(assets.floor(Transform::from_translation(
+                        cell_center([x, y]) + Vec3::Y * FLOOR_HEIGHT,
+                    )))
+                    .insert(floor_name([x, y]))
+                    // Nothing sits under the floor, and its shadows would cost as much as the walls'.
+                    .insert(NotShadowCaster);
+            }
+        }
+    })
+}
+
+fn wall_bundle(assets: &MazeAssets, MazeWall(wall): MazeWall) -> PbrBundle {
+    let (offset, rotation) = match wall.axis {
+        Axis::X => (Vec3::new(0.5, 0.0, 0.0), Quat::IDENTITY),
+        Axis::Y => (Vec3::new(0.0, 0.0, 0.5), Quat::from_rotation_y(PI / 2.0)),
+    };
+    let position = cell_center(wall.cell) + offset * CELL_SIZE;
+    assets.wall(Transform::from_translation(position).with_rotation(rotation))
+}
+
+/// Spawns a named, transform-only parent so the level's entities stay grouped by category.
+fn spawn_group(
+    builder: &mut ChildBuilder,
+    name: &str,
+    spawn: impl FnOnce(&mut ChildBuilder),
+) -> Entity {
+    builder
+        .spawn_bundle(TransformBundle::default())
+        .insert(Name::new(name.to_string()))
+        .with_children(spawn)
+        .id()
+}
+
+/// Name of the wall on the positive side of `cell` along `axis` of the displayed slice.
+fn wall_name(cell: [u16; 2], axis: Axis) -> Name {
+    let axis = match axis {
+        Axis::X => "x",
+        Axis::Y => "y",
+    };
+    Name::new(format!("wall[{},{}]+{}", cell[0], cell[1], axis))
+}
+
+/// Name of the joint at the lattice point on the negative corner of `cell`.
+fn joint_name(point: [u16; 2]) -> Name {
+    Name::new(format!("joint[{},{}]", point[0], point[1]))
+}
+
+/// Name of the floor tile under `cell`.
+fn floor_name(cell: [u16; 2]) -> Name {
+    Name::new(format!("floor[{},{}]", cell[0], cell[1]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_keyed_by_slice_position() {
+        assert_eq!(wall_name([3, 2], Axis::X).as_str(), "wall[3,2]+x");
+        assert_eq!(wall_name([0, 14], Axis::Y).as_str(), "wall[0,14]+y");
+        assert_eq!(joint_name([3, 2]).as_str(), "joint[3,2]");
+        assert_eq!(floor_name([3, 2]).as_str(), "floor[3,2]");
+    }
+
+    #[test]
+    fn diff_walls_keeps_shared_walls() {
+        let wall = |x, y, axis| MazeWall(SliceWall { cell: [x, y], axis });
+        let current = HashSet::from([
+            wall(0, 0, Axis::X),
+            wall(1, 0, Axis::Y),
+            wall(2, 2, Axis::X),
+        ]);
+        let next = HashSet::from([
+            wall(0, 0, Axis::X),
+            wall(2, 2, Axis::Y),
+            wall(2, 2, Axis::X),
+        ]);
+
+        let (removed, added) = diff_walls(&current, &next);
+        assert_eq!(removed, [wall(1, 0, Axis::Y)]);
+        assert_eq!(added, [wall(2, 2, Axis::Y)]);
+
+        let (removed, added) = diff_walls(&next, &next);
+        assert!(removed.is_empty() && added.is_empty());
+    }
+
+    #[test]
+    fn diff_walls_between_overlapping_slices() {
+        use crate::level::maze_level::Direction;
+        use rand::prelude::*;
+
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let mut level = MazeLevel::from_maze(maze::Maze::new(&[30, 30, 30], &mut rng));
+        let walls = |level: &MazeLevel| -> HashSet<MazeWall> {
+            level.walls_in_current_slice().map(MazeWall).collect()
+        };
+        let before = walls(&level);
+        level.shift_axis(Axis::Y, Direction::Positive);
+        let after = walls(&level);
+
+        let (removed, added) = diff_walls(&before, &after);
+        assert_eq!(before.len() - removed.len() + added.len(), after.len());
+        assert!(added.len() * 2 < after.len());
+    }
+
+    #[test]
+    fn layers_follow_the_depth_axis() {
+        use crate::level::maze_level::Direction;
+        use rand::prelude::*;
+
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let mut level = MazeLevel::from_maze(maze::Maze::new(&[4, 4, 3, 3], &mut rng));
+        let flat = MazeLevel::from_maze(maze::Maze::new(&[4, 4], &mut rng));
+        let shown = VolumeView { shown: true };
+        assert_eq!(
+            Layers::of(&level, &VolumeView::default(), FogOfWar::Dim),
+            None
+        );
+        assert_eq!(Layers::of(&level, &shown, FogOfWar::Hide), None);
+        assert_eq!(Layers::of(&flat, &shown, FogOfWar::Dim), None);
+
+        let layers = Layers::of(&level, &shown, FogOfWar::Dim).unwrap();
+        assert_eq!(layers.depth, 2);
+        // Walking around the slice keeps the layers around it.
+        for dir in [Direction::Positive, Direction::Negative] {
+            level.move_pos(Axis::X, dir);
+            level.move_pos(Axis::Y, dir);
+        }
+        assert_eq!(
+            Layers::of(&level, &shown, FogOfWar::Off),
+            Some(layers.clone())
+        );
+        level.shift_depth(Direction::Positive);
+        assert_eq!(Layers::of(&level, &shown, FogOfWar::Dim).unwrap().depth, 3);
+        level.shift_axis(Axis::Y, Direction::Positive);
+        assert_ne!(Layers::of(&level, &shown, FogOfWar::Dim), Some(layers));
+        assert_eq!(layer_offset(-1), -layer_offset(1));
+    }
+
+    #[test]
+    fn fog_hides_walls_away_from_visited_cells() {
+        use crate::level::maze_level::Direction;
+        use rand::prelude::*;
+
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let mut level = MazeLevel::from_maze(maze::Maze::new(&[6, 6, 3], &mut rng));
+        let all = visible_walls(&level, FogOfWar::Off);
+        assert!(all.values().all(|seen| *seen));
+
+        let dim = visible_walls(&level, FogOfWar::Dim);
+        assert_eq!(dim.keys().collect::<HashSet<_>>(), all.keys().collect());
+        let hidden = visible_walls(&level, FogOfWar::Hide);
+        assert!(hidden.values().all(|seen| *seen));
+        assert_eq!(hidden.len(), dim.values().filter(|seen| **seen).count());
+        // Only the start is visited, which has at most two walls inside the slice.
+        assert!(hidden.len() <= 2);
+
+        let before = hidden.len();
+        for (axis, dir) in [
+            (Axis::X, Direction::Positive),
+            (Axis::Y, Direction::Positive),
+        ] {
+            level.move_pos(axis, dir);
+        }
+        assert!(visible_walls(&level, FogOfWar::Hide).len() >= before);
+
+        // A fresh slice through the player only shows walls around cells entered in it.
+        level.shift_axis(Axis::Y, Direction::Positive);
+        for (MazeWall(wall), _) in visible_walls(&level, FogOfWar::Hide) {
+            assert!(
+                level.visited_in_current(wall.cell) || level.visited_in_current(wall.neighbor())
+            );
         }
     }
 }
@@ -1,4 +1,8 @@
-use super::maze_level::{self, *};
+use super::{
+    loader::LevelEntity,
+    maze_level::{self, *},
+    palette::AxisPalette,
+};
 use bevy::prelude::*;
 
 // Current dimension status text layout:
@@ -30,7 +34,12 @@ use bevy::prelude::*;
 //   (false, None) -> Greyed out circle,
 // }
 
-pub fn spawn_ui(mut c: Commands, maze: Res<MazeLevel>, assets: Res<AssetServer>) {
+pub fn spawn_ui(
+    mut c: Commands,
+    maze: Res<MazeLevel>,
+    assets: Res<AssetServer>,
+    palette: Res<AxisPalette>,
+) {
     let style = TextStyle {
         font: assets.load("fonts\\UnicaOne-Regular.ttf"),
         font_size: 50.0,
@@ -56,11 +65,12 @@ pub fn spawn_ui(mut c: Commands, maze: Res<MazeLevel>, assets: Res<AssetServer>)
         ..default()
     };
 
+    let palette = *palette;
     let dimension_col = |dimension: usize| {
         move |c: &mut ChildBuilder| {
             c.spawn_bundle(NodeBundle::default())
                 .with_children(|c| {
-                    c.spawn_bundle(label("-", Color::DARK_GRAY))
+                    c.spawn_bundle(label("-", palette.color(dimension)))
                         .insert(MazeAxisLabel {
                             dim: dimension as u8,
                             dir: maze_level::Direction::Negative,
@@ -75,7 +85,7 @@ pub fn spawn_ui(mut c: Commands, maze: Res<MazeLevel>, assets: Res<AssetServer>)
 
             c.spawn_bundle(NodeBundle::default())
                 .with_children(|c| {
-                    c.spawn_bundle(label("-", Color::DARK_GRAY))
+                    c.spawn_bundle(label("-", palette.color(dimension)))
                         .insert(MazeAxisLabel {
                             dim: dimension as u8,
                             dir: maze_level::Direction::Positive,
@@ -96,6 +106,7 @@ pub fn spawn_ui(mut c: Commands, maze: Res<MazeLevel>, assets: Res<AssetServer>)
         color: Color::NONE.into(),
         ..default()
     })
+    .insert(LevelEntity)
     .with_children(|c| {
         c.spawn_bundle(NodeBundle {
             style: Style {
@@ -159,7 +170,7 @@ pub fn spawn_ui(mut c: Commands, maze: Res<MazeLevel>, assets: Res<AssetServer>)
         })
         .with_children(|c| {
             c.spawn_bundle(label("[", Color::WHITE));
-            for (i, _) in maze.dims_limit().iter().enumerate() {
+            for i in 0..maze.dimension_count() {
                 c.spawn_bundle(NodeBundle {
                     style: Style {
                         flex_direction: FlexDirection::Column,
@@ -210,18 +221,32 @@ pub fn maze_axis_label_background_updater(
     }
 }
 
+pub fn recolor_axis_labels(
+    palette: Res<AxisPalette>,
+    mut query: Query<(&MazeAxisLabel, &mut Text)>,
+) {
+    if !palette.is_changed() {
+        return;
+    }
+    for (label, mut text) in query.iter_mut() {
+        for section in text.sections.iter_mut() {
+            section.style.color = palette.color(label.dim as usize);
+        }
+    }
+}
+
 pub fn maze_axis_label_update_listener(
     mut query: Query<(&MazeAxisLabel, &mut Text)>,
     mut axis_changed: EventReader<AxisChanged>,
 ) {
     for changed in axis_changed.iter() {
         for (label, mut text) in query.iter_mut() {
-            if changed.axis[0] == label.dim {
+            if changed.to[0] == label.dim {
                 text.sections[0].value = match label.dir {
                     maze_level::Direction::Positive => "W".into(),
                     maze_level::Direction::Negative => "S".into(),
                 };
-            } else if changed.axis[1] == label.dim {
+            } else if changed.to[1] == label.dim {
                 text.sections[0].value = match label.dir {
                     maze_level::Direction::Positive => "D".into(),
                     maze_level::Direction::Negative => "A".into(),
@@ -235,7 +260,17 @@ pub fn maze_axis_label_update_listener(
 
 #[derive(Component)]
 pub struct MazePositionLabel {
-    dimension: usize,
+    pub dimension: usize,
+}
+
+/// Color of the position label for `dimension`, highlighted at the far end of its axis.
+pub fn position_label_color(maze: &MazeLevel, dimension: usize) -> Color {
+    match maze.dims().get(dimension) {
+        Some(position) if maze.dims_limit().get(dimension) == Some(&(position + 1)) => {
+            Color::LIME_GREEN
+        }
+        _ => Color::WHITE,
+    }
 }
 
 pub fn maze_position_label_update_listener(
@@ -247,14 +282,8 @@ pub fn maze_position_label_update_listener(
         for (label, mut text) in query.iter_mut() {
             if let Some(section) = text.sections.first_mut() {
                 if let Some(target) = maze.dims().get(label.dimension) {
-                    let position = target + 1;
-                    section.value = format!("{}", position);
-                    section.style.color =
-                        if maze.dims_limit().get(label.dimension) == Some(&position) {
-                            Color::LIME_GREEN
-                        } else {
-                            Color::WHITE
-                        };
+                    section.value = format!("{}", target + 1);
+                    section.style.color = position_label_color(&maze, label.dimension);
                 }
             }
         }
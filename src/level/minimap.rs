@@ -0,0 +1,217 @@
+use bevy::prelude::*;
+
+use super::{
+    loader::LevelEntity,
+    maze_level::{Axis, AxisChanged, MazeLevel, PositionChanged, SliceWall},
+};
+
+/// Length of the longer side of the minimap, in pixels.
+const MINIMAP_SIZE: f32 = 192.0;
+/// Thickness of a wall line, as a fraction of a cell.
+const WALL_THICKNESS: f32 = 0.15;
+/// Diameter of the player and goal dots, as a fraction of a cell.
+const DOT_SIZE: f32 = 0.5;
+
+#[derive(Component)]
+pub struct Minimap;
+
+#[derive(Component)]
+pub struct MinimapPlayer;
+
+/// A rectangle on the minimap in pixels, measured from its bottom left corner.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct MapRect {
+    left: f32,
+    bottom: f32,
+    width: f32,
+    height: f32,
+}
+
+impl MapRect {
+    fn style(&self) -> Style {
+        Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                left: Val::Px(self.left),
+                bottom: Val::Px(self.bottom),
+                ..default()
+            },
+            size: Size::new(Val::Px(self.width), Val::Px(self.height)),
+            ..default()
+        }
+    }
+}
+
+/// Pixel width of a cell, so the slice's longer side fills `MINIMAP_SIZE`.
+fn cell_size(limit: [u16; 2]) -> f32 {
+    MINIMAP_SIZE / limit[0].max(limit[1]).max(1) as f32
+}
+
+fn dot_rect(cell: [u16; 2], cell_size: f32) -> MapRect {
+    let size = cell_size * DOT_SIZE;
+    let inset = (cell_size - size) / 2.0;
+    MapRect {
+        left: cell[0] as f32 * cell_size + inset,
+        bottom: cell[1] as f32 * cell_size + inset,
+        width: size,
+        height: size,
+    }
+}
+
+/// The line between a wall's cell and its neighbor.
+fn wall_rect(wall: SliceWall, cell_size: f32) -> MapRect {
+    let thickness = cell_size * WALL_THICKNESS;
+    let [a, b] = [wall.cell, wall.neighbor()];
+    match wall.axis {
+        Axis::X => MapRect {
+            left: b[0] as f32 * cell_size - thickness / 2.0,
+            bottom: a[1] as f32 * cell_size,
+            width: thickness,
+            height: cell_size,
+        },
+        Axis::Y => MapRect {
+            left: a[0] as f32 * cell_size,
+            bottom: b[1] as f32 * cell_size - thickness / 2.0,
+            width: cell_size,
+            height: thickness,
+        },
+    }
+}
+
+pub fn spawn_minimap(mut c: Commands) {
+    c.spawn_bundle(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                left: Val::Px(10.0),
+                bottom: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        },
+        color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+        ..default()
+    })
+    .insert(Minimap)
+    .insert(LevelEntity)
+    .insert(Name::new("minimap"));
+}
+
+/// Redraws the minimap for the current slice.
+pub fn rebuild_minimap(
+    mut c: Commands,
+    level: Res<MazeLevel>,
+    mut axis_changed: EventReader<AxisChanged>,
+    mut minimap: Query<(Entity, &mut Style), With<Minimap>>,
+) {
+    if axis_changed.iter().count() == 0 {
+        return;
+    }
+    let limit = level.pos_limit();
+    let cell_size = cell_size(limit);
+
+    for (entity, mut style) in minimap.iter_mut() {
+        style.size = Size::new(
+            Val::Px(limit[0] as f32 * cell_size),
+            Val::Px(limit[1] as f32 * cell_size),
+        );
+        let mut map = c.entity(entity);
+        map.despawn_descendants();
+        map.with_children(|c| {
+            for wall in level.walls_in_current_slice() {
+                c.spawn_bundle(NodeBundle {
+                    style: wall_rect(wall, cell_size).style(),
+                    color: Color::WHITE.into(),
+                    ..default()
+                });
+            }
+            if let Some(goal) = level.goal_in_current() {
+                c.spawn_bundle(NodeBundle {
+                    style: dot_rect(goal, cell_size).style(),
+                    color: Color::LIME_GREEN.into(),
+                    ..default()
+                });
+            }
+            c.spawn_bundle(NodeBundle {
+                style: dot_rect(level.pos(), cell_size).style(),
+                color: Color::rgb(0.5, 0.5, 0.8).into(),
+                ..default()
+            })
+            .insert(MinimapPlayer);
+        });
+    }
+}
+
+pub fn move_minimap_player(
+    level: Res<MazeLevel>,
+    mut position_changed: EventReader<PositionChanged>,
+    mut players: Query<&mut Style, With<MinimapPlayer>>,
+) {
+    if let Some(changed) = position_changed.iter().last() {
+        let cell_size = cell_size(level.pos_limit());
+        for mut style in players.iter_mut() {
+            *style = dot_rect(changed.to, cell_size).style();
+        }
+    }
+}
+
+pub fn hide_minimap(minimap: Query<&mut Style, With<Minimap>>) {
+    set_minimap_display(minimap, Display::None);
+}
+
+pub fn show_minimap(minimap: Query<&mut Style, With<Minimap>>) {
+    set_minimap_display(minimap, Display::Flex);
+}
+
+fn set_minimap_display(mut minimap: Query<&mut Style, With<Minimap>>, display: Display) {
+    for mut style in minimap.iter_mut() {
+        style.display = display;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimap_fits_largest_slice() {
+        let size = cell_size([32, 32]) * 32.0;
+        assert_eq!(size, MINIMAP_SIZE);
+        // Well under a quarter of a small 1280x720 window.
+        assert!(size * size < 1280.0 * 720.0 / 4.0);
+        assert_eq!(cell_size([4, 15]) * 15.0, MINIMAP_SIZE);
+    }
+
+    #[test]
+    fn walls_sit_between_cells() {
+        let thickness = 10.0 * WALL_THICKNESS;
+        let wall = |axis| SliceWall { cell: [2, 3], axis };
+        assert_eq!(
+            wall_rect(wall(Axis::X), 10.0),
+            MapRect {
+                left: 30.0 - thickness / 2.0,
+                bottom: 30.0,
+                width: thickness,
+                height: 10.0,
+            }
+        );
+        assert_eq!(
+            wall_rect(wall(Axis::Y), 10.0),
+            MapRect {
+                left: 20.0,
+                bottom: 40.0 - thickness / 2.0,
+                width: 10.0,
+                height: thickness,
+            }
+        );
+        assert_eq!(
+            dot_rect([1, 0], 10.0),
+            MapRect {
+                left: 12.5,
+                bottom: 2.5,
+                width: 5.0,
+                height: 5.0,
+            }
+        );
+    }
+}
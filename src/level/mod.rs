@@ -1,11 +1,48 @@
+mod animation;
+mod audio;
+mod bindings;
+mod bump;
+mod camera;
+mod campaign;
+mod code;
+mod demo;
+mod difficulty;
+mod error;
+mod gamepad;
+mod goal;
+mod hint;
+mod hud;
+mod indicators;
 mod input;
+mod lighting;
 mod loader;
+mod loading;
 mod maze_level;
 mod maze_renderer;
 mod maze_ui_renderer;
+mod minimap;
+mod palette;
+mod pause;
 mod plugin;
+mod pointer;
+mod preflight;
+mod repeat;
+mod save;
+#[cfg(test)]
+mod simulation;
+mod stats;
+mod theme;
+mod time_trial;
+mod trail;
+mod undo;
 
 use maze_level::MazeLevel;
 
-pub use loader::{DimensionLength, LoadLevel};
+pub use camera::OrbitCamera;
+pub use campaign::{Campaign, StartCampaign};
+pub use code::{LevelCode, LevelCodeError};
+pub use difficulty::Difficulty;
+pub use loader::{DimensionLength, LoadLevel, RngSource};
 pub use plugin::LevelPlugin;
+pub use preflight::{LoadEstimate, LoadLimits};
+pub use save::{ContinueGame, SavedGame};
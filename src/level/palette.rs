@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::bindings::{Action, ActionInput};
+
+/// A set of colors that tell the dimensions apart.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum PaletteKind {
+    #[default]
+    Standard,
+    /// The Okabe-Ito colors, which stay distinct under the common kinds of color blindness.
+    Colorblind,
+}
+
+impl PaletteKind {
+    fn colors(&self) -> &'static [Color; 8] {
+        const STANDARD: [Color; 8] = [
+            Color::CRIMSON,
+            Color::DARK_GREEN,
+            Color::NAVY,
+            Color::PURPLE,
+            Color::ORANGE_RED,
+            Color::TEAL,
+            Color::OLIVE,
+            Color::MAROON,
+        ];
+        const COLORBLIND: [Color; 8] = [
+            Color::rgb(0.902, 0.624, 0.0),
+            Color::rgb(0.337, 0.706, 0.914),
+            Color::rgb(0.0, 0.62, 0.451),
+            Color::rgb(0.941, 0.894, 0.259),
+            Color::rgb(0.0, 0.447, 0.698),
+            Color::rgb(0.835, 0.369, 0.0),
+            Color::rgb(0.8, 0.475, 0.655),
+            Color::rgb(0.6, 0.6, 0.6),
+        ];
+        match self {
+            PaletteKind::Standard => &STANDARD,
+            PaletteKind::Colorblind => &COLORBLIND,
+        }
+    }
+}
+
+/// The color of each dimension, shared by the walls, the HUD and the move indicators.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AxisPalette {
+    pub kind: PaletteKind,
+}
+
+impl AxisPalette {
+    /// The color of `dimension`, repeating once there are more dimensions than colors.
+    pub fn color(&self, dimension: usize) -> Color {
+        let colors = self.kind.colors();
+        colors[dimension % colors.len()]
+    }
+
+    /// A wall of color `wall` tinted toward the color of `dimension`, the axis it blocks.
+    pub fn tint(&self, wall: Color, dimension: usize) -> Color {
+        let [r1, g1, b1, a] = wall.as_rgba_f32();
+        let [r2, g2, b2, _] = self.color(dimension).as_rgba_f32();
+        Color::rgba((r1 + r2) / 2.0, (g1 + g2) / 2.0, (b1 + b2) / 2.0, a)
+    }
+}
+
+pub fn cycle_palette(input: ActionInput, mut palette: ResMut<AxisPalette>) {
+    if input.just_pressed(Action::NextPalette) {
+        palette.kind = match palette.kind {
+            PaletteKind::Standard => PaletteKind::Colorblind,
+            PaletteKind::Colorblind => PaletteKind::Standard,
+        };
+        info!("Axis palette: {:?}", palette.kind);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_are_stable_per_dimension() {
+        let palette = AxisPalette::default();
+        assert_eq!(palette.color(0), Color::CRIMSON);
+        assert_eq!(palette.color(9), palette.color(1));
+        for kind in [PaletteKind::Standard, PaletteKind::Colorblind] {
+            let colors = kind.colors();
+            for (i, a) in colors.iter().enumerate() {
+                assert!(!colors[i + 1..].contains(a), "{:?} repeats {:?}", kind, a);
+            }
+        }
+        let colorblind = AxisPalette {
+            kind: PaletteKind::Colorblind,
+        };
+        assert_ne!(colorblind.color(0), palette.color(0));
+    }
+
+    #[test]
+    fn tint_keeps_the_wall_alpha() {
+        let palette = AxisPalette::default();
+        let tinted = palette.tint(Color::rgba(1.0, 1.0, 1.0, 0.5), 2);
+        let [_, _, blue, alpha] = tinted.as_rgba_f32();
+        assert_eq!(alpha, 0.5);
+        assert!(blue > 0.5);
+    }
+}
@@ -0,0 +1,148 @@
+use bevy::prelude::*;
+
+use super::{
+    bindings::{Action, ActionInputMut},
+    loader::LastLoad,
+};
+use crate::{
+    clipboard,
+    toast::{ShowToast, ToastKind},
+    AppState,
+};
+
+#[derive(Component)]
+pub struct PauseMenu;
+
+#[derive(Component, Clone, Copy)]
+pub enum PauseButton {
+    Resume,
+    Restart,
+    CopyCode,
+    QuitToMenu,
+}
+
+pub fn spawn_pause_menu(
+    mut c: Commands,
+    assets: Res<AssetServer>,
+    last_load: Option<Res<LastLoad>>,
+) {
+    let code = last_load.and_then(|last_load| last_load.code());
+    let style = TextStyle {
+        font: assets.load("fonts/UnicaOne-Regular.ttf"),
+        font_size: 40.0,
+        color: Color::WHITE,
+    };
+    c.spawn_bundle(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            flex_direction: FlexDirection::ColumnReverse,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+        ..default()
+    })
+    .insert(PauseMenu)
+    .insert(Name::new("pause menu"))
+    .with_children(|c| {
+        if let Some(code) = &code {
+            c.spawn_bundle(TextBundle {
+                text: Text::with_section(
+                    format!("level code: {}", code),
+                    style.clone(),
+                    Default::default(),
+                ),
+                ..default()
+            });
+        }
+        let mut buttons = vec![
+            ("Resume", PauseButton::Resume),
+            ("Restart", PauseButton::Restart),
+        ];
+        if code.is_some() {
+            buttons.push(("Copy code", PauseButton::CopyCode));
+        }
+        buttons.push(("Quit to menu", PauseButton::QuitToMenu));
+        for (label, button) in buttons {
+            c.spawn_bundle(ButtonBundle {
+                style: Style {
+                    padding: Rect::all(Val::Px(5.0)),
+                    margin: Rect::all(Val::Px(5.0)),
+                    ..default()
+                },
+                color: Color::DARK_GRAY.into(),
+                ..default()
+            })
+            .insert(button)
+            .with_children(|c| {
+                c.spawn_bundle(TextBundle {
+                    text: Text::with_section(label, style.clone(), Default::default()),
+                    ..default()
+                });
+            });
+        }
+    });
+}
+
+pub fn despawn_pause_menu(mut c: Commands, menus: Query<Entity, With<PauseMenu>>) {
+    for menu in menus.iter() {
+        c.entity(menu).despawn_recursive();
+    }
+}
+
+pub fn pause_menu_input(
+    mut input: ActionInputMut,
+    last_load: Option<Res<LastLoad>>,
+    mut app_state: ResMut<State<AppState>>,
+    mut load_level: EventWriter<super::LoadLevel>,
+    mut toasts: EventWriter<ShowToast>,
+    buttons: Query<(&Interaction, &PauseButton), Changed<Interaction>>,
+) {
+    let clicked = buttons
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Clicked)
+        .map(|(_, button)| *button);
+    // Consumed so the resumed maze doesn't pause again this frame.
+    let pressed = if input.clear_just_pressed(Action::Pause) {
+        Some(PauseButton::Resume)
+    } else {
+        clicked
+    };
+
+    let result = match pressed {
+        Some(PauseButton::Resume) => app_state.pop(),
+        // Loading unwinds the state stack back into a fresh maze.
+        Some(PauseButton::Restart) => {
+            if let Some(last_load) = last_load {
+                load_level.send(last_load.restart());
+            }
+            Ok(())
+        }
+        Some(PauseButton::CopyCode) => {
+            if let Some(code) = last_load.and_then(|last_load| last_load.code()) {
+                toasts.send(copy_code(&code));
+            }
+            Ok(())
+        }
+        Some(PauseButton::QuitToMenu) => app_state.replace(AppState::MainMenu),
+        None => Ok(()),
+    };
+    if let Err(e) = result {
+        error!("Could not leave the pause menu: {}", e);
+    }
+}
+
+/// Copies a level code, falling back to showing it when there's no clipboard to copy it to.
+fn copy_code(code: &str) -> ShowToast {
+    match clipboard::copy(code) {
+        Ok(()) => ShowToast::new(ToastKind::Info, format!("Copied level code {}", code)),
+        Err(e) => {
+            warn!("Could not copy the level code: {}", e);
+            ShowToast::new(
+                ToastKind::Warn,
+                format!("No clipboard, level code is {}", code),
+            )
+        }
+    }
+}
@@ -1,26 +1,179 @@
 use super::*;
 use crate::AppState;
-use bevy::prelude::*;
+use bevy::{input::InputSystem, prelude::*};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
 struct LevelInit;
 
+/// The whole level: its logic, and everything drawn and played for it.
 pub struct LevelPlugin;
 
 impl Plugin for LevelPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(loader::load_maze_assets)
+        app.add_plugin(LevelLogicPlugin)
+            .add_plugin(LevelRenderPlugin);
+    }
+}
+
+/// Loading, input, navigation and the level's progress, with nothing that needs a window or
+/// assets, so a level can be played without either.
+pub struct LevelLogicPlugin;
+
+impl Plugin for LevelLogicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<preflight::LoadLimits>()
+            .init_resource::<hint::HintState>()
+            .init_resource::<stats::LevelStats>()
+            .init_resource::<undo::MoveHistory>()
+            .init_resource::<save::SaveLocation>()
+            .init_resource::<save::SavedGame>()
+            .init_resource::<campaign::Campaign>()
+            .init_resource::<difficulty::DifficultyBests>()
+            .init_resource::<demo::DemoMode>()
+            .init_resource::<time_trial::TimeTrialSettings>()
+            .init_resource::<time_trial::Leaderboard>()
+            .init_resource::<bindings::TriggeredActions>()
+            .init_resource::<gamepad::GamepadSticks>()
+            .add_startup_system(bindings::load_input_config)
+            .add_startup_system(save::load_saved_game)
+            .add_startup_system(campaign::load_campaign)
+            .add_startup_system(difficulty::load_difficulty_bests)
+            .add_startup_system(time_trial::load_leaderboard)
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                bindings::clear_triggered_actions.after(InputSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                gamepad::read_gamepads.after(bindings::clear_triggered_actions),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                demo::demo_input.after(gamepad::read_gamepads),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                demo::play_demo.after(demo::demo_input),
+            )
+            .add_system(bindings::warn_on_conflicts)
             .add_system(loader::level_load_system.before(LevelInit))
+            .add_system(loader::report_load_failures)
+            .add_system(save::continue_game.before(loader::level_load_system))
+            .add_system(save::save_on_close)
+            .add_system(campaign::start_campaign.before(loader::level_load_system))
             .add_event::<loader::LoadLevel>()
+            .add_event::<loader::LevelLoadFailed>()
             .add_event::<maze_level::AxisChanged>()
             .add_event::<maze_level::PositionChanged>()
+            .add_event::<maze_level::MoveBlocked>()
+            .add_event::<goal::MazeCompleted>()
+            .add_event::<save::ContinueGame>()
+            .add_event::<campaign::StartCampaign>()
+            .add_system_set(
+                SystemSet::on_enter(AppState::InMaze)
+                    .label(LevelInit)
+                    .with_system(loader::drop_stale_events)
+                    .with_system(loader::initial_events_on_load.after(loader::drop_stale_events))
+                    .with_system(stats::reset_stats)
+                    .with_system(
+                        save::resume_saved_game
+                            .after(stats::reset_stats)
+                            .after(loader::initial_events_on_load),
+                    )
+                    .with_system(undo::clear_history),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::InMaze)
+                    .after(LevelInit)
+                    .with_system(input::level_navigation)
+                    .with_system(loader::restart_input)
+                    .with_system(input::log_blocked_moves)
+                    .with_system(input::log_slice_on_debug_key)
+                    .with_system(hint::update_hint.after(input::level_navigation))
+                    .with_system(goal::detect_completion)
+                    .with_system(
+                        time_trial::run_out_of_time
+                            .after(stats::tick_elapsed)
+                            .after(goal::detect_completion),
+                    )
+                    .with_system(stats::track_stats)
+                    .with_system(stats::tick_elapsed),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Loading)
+                    .with_system(loading::poll_generation.before(LevelInit))
+                    .with_system(loading::loading_input),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Loading).with_system(loading::cancel_generation),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::Paused).with_system(save::save_on_pause))
+            .add_system_set(
+                SystemSet::on_update(AppState::Paused).with_system(pause::pause_menu_input),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::Completed)
+                    .with_system(campaign::record_campaign_level)
+                    .with_system(difficulty::record_difficulty_best)
+                    .with_system(time_trial::record_time_trial)
+                    .with_system(save::delete_completed_save),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Completed).with_system(goal::completion_input),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Failed).with_system(time_trial::failed_input),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::InMaze).with_system(hint::reset_hint))
+            .add_system_set(
+                SystemSet::on_enter(AppState::MainMenu)
+                    .with_system(loader::remove_level_resources)
+                    .with_system(save::drop_pending_resume),
+            );
+    }
+}
+
+/// The meshes, UI, camera and sounds that show a level, drawn from what `LevelLogicPlugin`
+/// leaves in the world.
+pub struct LevelRenderPlugin;
+
+impl Plugin for LevelRenderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<animation::MoveAnimation>()
+            .init_resource::<maze_renderer::FogOfWar>()
+            .init_resource::<maze_renderer::VolumeView>()
+            .init_resource::<trail::TrailSettings>()
+            .init_resource::<camera::CameraMode>()
+            .init_resource::<camera::CameraSettings>()
+            .init_resource::<lighting::LightingSettings>()
+            .init_resource::<theme::ThemeSettings>()
+            .init_resource::<palette::AxisPalette>()
+            .add_startup_system(loader::load_maze_assets)
+            .add_startup_system(audio::load_level_sounds)
+            // Clicks are another device, so they trigger actions before the demo looks for
+            // the player's.
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                pointer::click_to_move
+                    .after(bindings::clear_triggered_actions)
+                    .before(demo::demo_input),
+            )
+            .add_system(lighting::apply_lighting)
+            .add_system(theme::cycle_theme)
+            .add_system(palette::cycle_palette)
+            .add_system(loader::restyle_axis_materials.after(palette::cycle_palette))
+            .add_system(theme::drop_failed_textures.after(theme::cycle_theme))
             .add_system_set(
                 SystemSet::on_enter(AppState::InMaze)
                     .label(LevelInit)
                     .with_system(maze_renderer::spawn_maze_root)
                     .with_system(maze_ui_renderer::spawn_ui)
-                    .with_system(loader::initial_events_on_load)
-                    .with_system(loader::spawn_player),
+                    .with_system(hud::spawn_hud)
+                    .with_system(time_trial::spawn_countdown)
+                    .with_system(minimap::spawn_minimap)
+                    .with_system(loader::spawn_player)
+                    .with_system(loader::add_axis_materials)
+                    .with_system(difficulty::apply_difficulty_fog),
             )
             .add_system_set(
                 SystemSet::on_update(AppState::InMaze)
@@ -28,9 +181,477 @@ impl Plugin for LevelPlugin {
                     .with_system(maze_ui_renderer::maze_axis_label_update_listener)
                     .with_system(maze_ui_renderer::maze_position_label_update_listener)
                     .with_system(maze_ui_renderer::maze_axis_label_background_updater)
+                    .with_system(maze_ui_renderer::recolor_axis_labels)
                     .with_system(maze_renderer::maze_level_renderer)
+                    .with_system(maze_renderer::toggle_volume_view)
+                    .with_system(
+                        maze_renderer::maze_layer_renderer
+                            .after(maze_renderer::toggle_volume_view)
+                            .after(input::level_navigation),
+                    )
                     .with_system(maze_renderer::update_maze_offset)
-                    .with_system(input::level_navigation),
+                    .with_system(indicators::update_indicators)
+                    .with_system(trail::toggle_trail)
+                    .with_system(lighting::toggle_dark_mode)
+                    .with_system(trail::update_trail.after(trail::toggle_trail))
+                    .with_system(animation::animate_slides.after(maze_renderer::update_maze_offset))
+                    .with_system(
+                        animation::animate_resizes.after(maze_renderer::maze_level_renderer),
+                    )
+                    .with_system(animation::finish_despawns)
+                    .with_system(camera::camera_input)
+                    .with_system(camera::cycle_camera_mode)
+                    .with_system(
+                        camera::frame_camera
+                            .after(camera::camera_input)
+                            .after(camera::cycle_camera_mode)
+                            .after(animation::animate_slides),
+                    )
+                    .with_system(bump::start_bumps.after(input::level_navigation))
+                    .with_system(bump::animate_bumps.after(bump::start_bumps))
+                    .with_system(
+                        bump::animate_wall_flashes
+                            .after(bump::start_bumps)
+                            .after(maze_renderer::maze_level_renderer),
+                    )
+                    .with_system(hint::show_hint.after(hint::update_hint))
+                    .with_system(time_trial::update_countdown.after(stats::tick_elapsed))
+                    // After completion, which leaves InMaze before the next frame's sounds.
+                    .with_system(
+                        audio::play_level_sounds
+                            .after(goal::detect_completion)
+                            .after(input::level_navigation),
+                    )
+                    .with_system(
+                        hud::update_hud
+                            .after(stats::track_stats)
+                            .after(stats::tick_elapsed),
+                    )
+                    .with_system(minimap::rebuild_minimap)
+                    .with_system(minimap::move_minimap_player.after(minimap::rebuild_minimap)),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::Loading).with_system(loading::spawn_loading_screen),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Loading).with_system(loading::update_loading_text),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Loading).with_system(loading::despawn_loading_screen),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::Paused).with_system(pause::spawn_pause_menu),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Paused).with_system(pause::despawn_pause_menu),
+            )
+            // Drawn once the completion has been recorded, so it shows any new bests.
+            .add_system_set(
+                SystemSet::on_enter(AppState::Completed).with_system(
+                    goal::spawn_completion_screen
+                        .after(campaign::record_campaign_level)
+                        .after(difficulty::record_difficulty_best)
+                        .after(time_trial::record_time_trial),
+                ),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Completed)
+                    .with_system(goal::despawn_completion_screen),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::Failed).with_system(time_trial::spawn_failed_screen),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Failed).with_system(time_trial::despawn_failed_screen),
+            )
+            .add_system_set(
+                SystemSet::on_pause(AppState::InMaze).with_system(minimap::hide_minimap),
+            )
+            .add_system_set(
+                SystemSet::on_resume(AppState::InMaze).with_system(minimap::show_minimap),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::InMaze).with_system(loader::despawn_level),
             );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::maze_level::{ChangeCause, PositionChanged};
+    use crate::toast::ShowToast;
+    use bevy::{
+        asset::AssetPlugin,
+        ecs::event::Events,
+        input::mouse::{MouseMotion, MouseWheel},
+        window::WindowCloseRequested,
+    };
+
+    /// The level plugin on top of just enough of Bevy to run without a window.
+    fn headless_app() -> App {
+        headless_app_saving_to(None)
+    }
+
+    /// A headless app that saves to `path`, which is never the real save.
+    fn headless_app_saving_to(path: Option<std::path::PathBuf>) -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin)
+            .add_asset::<Mesh>()
+            .add_asset::<StandardMaterial>()
+            .add_asset::<Font>()
+            .init_resource::<Input<KeyCode>>()
+            .init_resource::<Input<MouseButton>>()
+            .init_resource::<Gamepads>()
+            .init_resource::<Input<GamepadButton>>()
+            .init_resource::<Axis<GamepadAxis>>()
+            .add_event::<GamepadEvent>()
+            .add_event::<MouseMotion>()
+            .add_event::<MouseWheel>()
+            .add_event::<ShowToast>()
+            .add_event::<WindowCloseRequested>()
+            .add_state(AppState::MainMenu)
+            .add_plugin(LevelPlugin)
+            .insert_resource(save::SaveLocation(path));
+        // Run startup and enter the menu before anything is loaded.
+        app.update();
+        app
+    }
+
+    fn load(app: &mut App) {
+        app.world
+            .resource_mut::<Events<LoadLevel>>()
+            .send(LoadLevel {
+                rng_source: RngSource::Seeded(7),
+                dimensions: DimensionLength::Three([4, 5, 2]),
+                ..Default::default()
+            });
+        // Generation runs on another thread, so give it a bounded number of frames to finish.
+        for _ in 0..1000 {
+            app.update();
+            if app.world.resource::<State<AppState>>().current() != &AppState::Loading {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        app.update();
+    }
+
+    /// How many times `MazeLevel` has been inserted or replaced. Replacing a resource only
+    /// marks it changed, and without input nothing else changes it.
+    #[derive(Default)]
+    struct LevelInsertions(usize);
+
+    fn count_insertions(level: Option<Res<MazeLevel>>, mut insertions: ResMut<LevelInsertions>) {
+        if level.is_some_and(|level| level.is_changed()) {
+            insertions.0 += 1;
+        }
+    }
+
+    #[test]
+    fn a_load_request_inserts_one_level() {
+        let mut app = headless_app();
+        app.init_resource::<LevelInsertions>()
+            .add_system_to_stage(CoreStage::Last, count_insertions);
+        load(&mut app);
+        for _ in 0..5 {
+            app.update();
+        }
+        assert_eq!(app.world.resource::<LevelInsertions>().0, 1);
+
+        load(&mut app);
+        assert_eq!(app.world.resource::<LevelInsertions>().0, 2);
+    }
+
+    /// Taps `key` for a frame, then waits for whatever it loads.
+    fn tap(app: &mut App, key: KeyCode) {
+        app.world.resource_mut::<Input<KeyCode>>().press(key);
+        app.update();
+        let mut keys = app.world.resource_mut::<Input<KeyCode>>();
+        keys.release(key);
+        keys.clear();
+        for _ in 0..1000 {
+            app.update();
+            if app.world.resource::<State<AppState>>().current() == &AppState::InMaze {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        app.update();
+    }
+
+    #[test]
+    fn restart_replays_the_maze_from_the_start() {
+        let mut app = headless_app();
+        load(&mut app);
+        let entities = app.world.entities().len();
+
+        // Walk out of the start cell through whichever side is open.
+        for key in [KeyCode::W, KeyCode::S, KeyCode::D, KeyCode::A] {
+            tap(&mut app, key);
+        }
+        assert!(app.world.resource::<MazeLevel>().visited_count() > 1);
+        tap(&mut app, KeyCode::R);
+        let level = app.world.resource::<MazeLevel>();
+        assert_eq!(level.seed(), Some(7));
+        assert_eq!(level.dims(), &[0, 0, 0]);
+        assert_eq!(level.visited_count(), 1);
+        assert_eq!(app.world.entities().len(), entities);
+
+        app.world
+            .resource_mut::<Input<KeyCode>>()
+            .press(KeyCode::LShift);
+        tap(&mut app, KeyCode::R);
+        let level = app.world.resource::<MazeLevel>();
+        assert_ne!(level.seed(), Some(7));
+        assert_eq!(level.dims_limit(), &[4, 5, 2]);
+    }
+
+    #[test]
+    fn the_volume_view_spawns_the_layer_beside_the_slice() {
+        let mut app = headless_app();
+        load(&mut app);
+        let entities = app.world.entities().len();
+
+        // The player starts on the lower of the maze's two layers, so only the upper is drawn.
+        tap(&mut app, KeyCode::V);
+        let walls = {
+            let level = app.world.resource::<MazeLevel>();
+            assert_eq!(level.layer(-1), None);
+            level.walls_in_layer(level.layer(1).unwrap()).count()
+        };
+        assert_eq!(app.world.entities().len(), entities + 1 + walls as u32);
+
+        tap(&mut app, KeyCode::V);
+        assert_eq!(app.world.entities().len(), entities);
+    }
+
+    #[test]
+    fn walls_swapped_out_mid_transition_are_despawned() {
+        use animation::{Despawning, Resize};
+
+        let mut app = headless_app();
+        // Long enough that no frame finishes a transition on its own.
+        let transition = std::time::Duration::from_secs(60 * 60);
+        app.world
+            .resource_mut::<animation::MoveAnimation>()
+            .wall_transition = transition;
+        load(&mut app);
+        let entities = app.world.entities().len();
+
+        // The second axis cycles between the two hidden dimensions, so two turns come back
+        // to the first slice while the first turn's walls are still shrinking.
+        tap(&mut app, KeyCode::X);
+        tap(&mut app, KeyCode::X);
+        assert!(app.world.entities().len() > entities);
+
+        // Run every transition to its end, rather than waiting it out on the real clock.
+        let mut resizes = app.world.query::<&mut Resize>();
+        for mut resize in resizes.iter_mut(&mut app.world) {
+            resize.advance(transition);
+        }
+        let mut despawns = app.world.query::<&mut Despawning>();
+        for mut despawning in despawns.iter_mut(&mut app.world) {
+            despawning.tick(transition);
+        }
+        app.update();
+        app.update();
+        assert_eq!(app.world.entities().len(), entities);
+    }
+
+    #[test]
+    fn loads_on_consecutive_frames_end_on_the_last() {
+        let mut app = headless_app();
+        load(&mut app);
+        for (seed, dimensions) in [
+            (8, DimensionLength::Two([6, 6])),
+            (9, DimensionLength::Three([3, 4, 5])),
+        ] {
+            app.world
+                .resource_mut::<Events<LoadLevel>>()
+                .send(LoadLevel {
+                    rng_source: RngSource::Seeded(seed),
+                    dimensions,
+                    ..Default::default()
+                });
+            app.update();
+        }
+        for _ in 0..1000 {
+            app.update();
+            if app.world.resource::<State<AppState>>().current() == &AppState::InMaze {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        app.update();
+        let level = app.world.resource::<MazeLevel>();
+        assert_eq!(level.seed(), Some(9));
+        assert_eq!(level.dims_limit(), &[3, 4, 5]);
+    }
+
+    #[test]
+    fn a_new_level_ignores_the_last_ones_moves() {
+        use rand::SeedableRng;
+
+        let dir = std::env::temp_dir().join(format!("nothing-moves-load-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("maze.ron");
+        let mut rng = rand::rngs::StdRng::seed_from_u64(684153987);
+        let file = loader::MazeFile {
+            version: 1,
+            maze: loader::MazeData::Two(maze::Maze::new(&[5, 5], &mut rng)),
+        };
+        std::fs::write(&path, ron::to_string(&file).unwrap()).unwrap();
+
+        let mut app = headless_app();
+        load(&mut app);
+        app.world
+            .resource_mut::<Input<KeyCode>>()
+            .press(KeyCode::Escape);
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<AppState>>().current(),
+            &AppState::Paused
+        );
+        // Nothing reads moves while paused, and a maze file loads within the frame, so the
+        // old level's last move is still waiting to be read when the new one starts.
+        app.world
+            .resource_mut::<Events<PositionChanged>>()
+            .send(PositionChanged {
+                from: [0, 0],
+                to: [1, 0],
+                dimension: Some(0),
+                positive: Some(true),
+                cause: ChangeCause::Moved,
+            });
+        app.world
+            .resource_mut::<Events<LoadLevel>>()
+            .send(LoadLevel {
+                maze_file: Some(path.to_string_lossy().into_owned()),
+                ..Default::default()
+            });
+        app.update();
+        app.update();
+        assert_eq!(app.world.resource::<MazeLevel>().dims_limit(), &[5, 5]);
+        assert_eq!(app.world.resource::<stats::LevelStats>().steps, 0);
+        let events = app.world.resource::<Events<PositionChanged>>();
+        let causes: Vec<_> = events
+            .get_reader()
+            .iter(events)
+            .map(|changed| changed.cause)
+            .collect();
+        assert_eq!(causes, [ChangeCause::Loaded]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn reloading_does_not_accumulate_entities() {
+        let mut app = headless_app();
+        let empty = app.world.entities().len();
+        load(&mut app);
+        assert_eq!(
+            app.world.resource::<State<AppState>>().current(),
+            &AppState::InMaze
+        );
+        let first = app.world.entities().len();
+
+        load(&mut app);
+        load(&mut app);
+        assert_eq!(
+            app.world.resource::<State<AppState>>().current(),
+            &AppState::InMaze
+        );
+        assert_eq!(app.world.entities().len(), first);
+
+        app.world
+            .resource_mut::<State<AppState>>()
+            .replace(AppState::MainMenu)
+            .unwrap();
+        app.update();
+        assert_eq!(app.world.entities().len(), empty);
+        assert!(app.world.get_resource::<MazeLevel>().is_none());
+    }
+
+    #[test]
+    fn cancelling_a_load_drops_the_pending_maze() {
+        let mut app = headless_app();
+        let empty = app.world.entities().len();
+        app.world
+            .resource_mut::<Events<LoadLevel>>()
+            .send(LoadLevel {
+                rng_source: RngSource::Seeded(7),
+                dimensions: DimensionLength::Three([40, 40, 40]),
+                ..Default::default()
+            });
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<AppState>>().current(),
+            &AppState::Loading
+        );
+        assert!(app.world.get_resource::<loading::PendingLevel>().is_some());
+
+        app.world
+            .resource_mut::<State<AppState>>()
+            .replace(AppState::MainMenu)
+            .unwrap();
+        for _ in 0..3 {
+            app.update();
+        }
+        assert_eq!(
+            app.world.resource::<State<AppState>>().current(),
+            &AppState::MainMenu
+        );
+        assert!(app.world.get_resource::<loading::PendingLevel>().is_none());
+        assert!(app.world.get_resource::<MazeLevel>().is_none());
+        assert_eq!(app.world.entities().len(), empty);
+    }
+
+    #[test]
+    fn pausing_saves_a_game_that_continues_where_it_left_off() {
+        let path = std::env::temp_dir()
+            .join(format!("nothing-moves-{}", std::process::id()))
+            .join("save.ron");
+        let mut app = headless_app_saving_to(Some(path.clone()));
+        load(&mut app);
+        for key in [KeyCode::W, KeyCode::S, KeyCode::D, KeyCode::A] {
+            tap(&mut app, key);
+        }
+        let (position, visited) = {
+            let level = app.world.resource::<MazeLevel>();
+            (level.dims().to_vec(), level.visited_count())
+        };
+        assert!(visited > 1);
+
+        app.world
+            .resource_mut::<Input<KeyCode>>()
+            .press(KeyCode::Escape);
+        app.update();
+        app.world
+            .resource_mut::<State<AppState>>()
+            .replace(AppState::MainMenu)
+            .unwrap();
+        app.update();
+        assert!(path.exists());
+
+        // A fresh launch finds the save on disk.
+        let mut app = headless_app_saving_to(Some(path.clone()));
+        assert!(app.world.resource::<SavedGame>().0.is_some());
+        app.world
+            .resource_mut::<Events<ContinueGame>>()
+            .send(ContinueGame);
+        for _ in 0..1000 {
+            app.update();
+            if app.world.resource::<State<AppState>>().current() == &AppState::InMaze {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        app.update();
+        let level = app.world.resource::<MazeLevel>();
+        assert_eq!(level.dims(), position);
+        assert_eq!(level.visited_count(), visited);
+        assert_eq!(level.seed(), Some(7));
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
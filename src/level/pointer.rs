@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+
+use super::{
+    bindings::{Action, TriggeredActions},
+    maze_level::MazeLevel,
+    maze_renderer::{MazeRenderer, CELL_SIZE, FLOOR_HEIGHT},
+};
+use crate::AppState;
+
+/// Where the cursor points, as a ray from the near plane of `camera` into the scene.
+fn cursor_ray(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    window: &Window,
+) -> Option<(Vec3, Vec3)> {
+    let cursor = window.cursor_position()?;
+    let size = Vec2::new(window.width(), window.height());
+    if size.x <= 0.0 || size.y <= 0.0 {
+        return None;
+    }
+    // The cursor is measured from the bottom left, like normalized device coordinates.
+    let ndc = cursor / size * 2.0 - Vec2::ONE;
+    Some(ndc_ray(ndc, camera.projection_matrix, camera_transform))
+}
+
+fn ndc_ray(ndc: Vec2, projection: Mat4, camera_transform: &GlobalTransform) -> (Vec3, Vec3) {
+    let ndc_to_world = camera_transform.compute_matrix() * projection.inverse();
+    // Bevy's depth runs from 1 at the near plane toward 0 at infinity.
+    let near = ndc_to_world.project_point3(ndc.extend(1.0));
+    let far = ndc_to_world.project_point3(ndc.extend(0.5));
+    (near, (far - near).normalize())
+}
+
+/// The slice cell under a point where a ray, in the maze's own space, crosses the floor.
+fn floor_cell(origin: Vec3, direction: Vec3, limit: [u16; 2]) -> Option<[u16; 2]> {
+    if direction.y.abs() < f32::EPSILON {
+        return None;
+    }
+    let distance = (FLOOR_HEIGHT - origin.y) / direction.y;
+    if distance < 0.0 {
+        return None;
+    }
+    let hit = (origin + direction * distance) / CELL_SIZE;
+    let cell = [hit.x.round(), hit.z.round()];
+    let inside = |coord: f32, limit: u16| coord >= 0.0 && coord < limit as f32;
+    (inside(cell[0], limit[0]) && inside(cell[1], limit[1]))
+        .then(|| [cell[0] as u16, cell[1] as u16])
+}
+
+/// The move that steps from `player` onto `clicked`, if they are next to each other.
+fn move_toward(player: [u16; 2], clicked: [u16; 2]) -> Option<Action> {
+    let dx = clicked[0] as i32 - player[0] as i32;
+    let dy = clicked[1] as i32 - player[1] as i32;
+    match (dx, dy) {
+        (1, 0) => Some(Action::MoveNorth),
+        (-1, 0) => Some(Action::MoveSouth),
+        (0, 1) => Some(Action::MoveEast),
+        (0, -1) => Some(Action::MoveWest),
+        _ => None,
+    }
+}
+
+/// Turns a left click on a floor tile next to the player into the move onto it. Clicks
+/// anywhere else, or while the maze isn't the active state, are ignored.
+pub fn click_to_move(
+    app_state: Res<State<AppState>>,
+    level: Option<Res<MazeLevel>>,
+    buttons: Res<Input<MouseButton>>,
+    windows: Option<Res<Windows>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<PerspectiveProjection>>,
+    roots: Query<&GlobalTransform, With<MazeRenderer>>,
+    mut actions: ResMut<TriggeredActions>,
+) {
+    if !buttons.just_pressed(MouseButton::Left) || app_state.current() != &AppState::InMaze {
+        return;
+    }
+    let (level, window) = match (level, windows.as_ref().and_then(|w| w.get_primary())) {
+        (Some(level), Some(window)) => (level, window),
+        _ => return,
+    };
+    let (camera, camera_transform) = match cameras.iter().next() {
+        Some(camera) => camera,
+        None => return,
+    };
+    let (origin, direction) = match cursor_ray(camera, camera_transform, window) {
+        Some(ray) => ray,
+        None => return,
+    };
+    for root in roots.iter() {
+        // The maze slides under the player, so the ray is followed in the maze's own space.
+        let to_maze = root.compute_matrix().inverse();
+        let clicked = floor_cell(
+            to_maze.transform_point3(origin),
+            to_maze.transform_vector3(direction),
+            level.pos_limit(),
+        );
+        if let Some(action) = clicked.and_then(|cell| move_toward(level.pos(), cell)) {
+            actions.trigger(action);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ndc_rays_leave_the_camera_through_the_cursor() {
+        use bevy::render::camera::CameraProjection;
+
+        let projection = PerspectiveProjection::default().get_projection_matrix();
+        let camera = GlobalTransform::from_xyz(0.0, 10.0, 0.0).looking_at(Vec3::ZERO, Vec3::Z);
+        let (origin, direction) = ndc_ray(Vec2::ZERO, projection, &camera);
+        assert!((origin - Vec3::new(0.0, 10.0, 0.0)).length() < 0.5);
+        assert!((direction - Vec3::new(0.0, -1.0, 0.0)).length() < 1e-4);
+
+        // Toward the right of the screen, which is -x when looking down with z up.
+        let (_, right) = ndc_ray(Vec2::new(0.5, 0.0), projection, &camera);
+        assert!(right.x < 0.0 && right.y < 0.0 && right.z.abs() < 1e-4);
+    }
+
+    #[test]
+    fn rays_hit_the_cell_under_them() {
+        let down = Vec3::new(0.0, -1.0, 0.0);
+        assert_eq!(
+            floor_cell(Vec3::new(2.2, 5.0, 2.9), down, [4, 4]),
+            Some([2, 3])
+        );
+        assert_eq!(
+            floor_cell(Vec3::new(-0.4, 5.0, 0.0), down, [4, 4]),
+            Some([0, 0])
+        );
+        assert_eq!(floor_cell(Vec3::new(3.6, 5.0, 0.0), down, [4, 4]), None);
+        assert_eq!(floor_cell(Vec3::new(1.0, 5.0, 1.0), -down, [4, 4]), None);
+
+        let slanted = Vec3::new(1.0, -1.0, 0.0).normalize();
+        assert_eq!(
+            floor_cell(Vec3::new(0.0, 2.0 + FLOOR_HEIGHT, 1.0), slanted, [4, 4]),
+            Some([2, 1])
+        );
+    }
+
+    #[test]
+    fn only_neighbors_are_moved_to() {
+        assert_eq!(move_toward([2, 2], [3, 2]), Some(Action::MoveNorth));
+        assert_eq!(move_toward([2, 2], [1, 2]), Some(Action::MoveSouth));
+        assert_eq!(move_toward([2, 2], [2, 3]), Some(Action::MoveEast));
+        assert_eq!(move_toward([2, 2], [2, 1]), Some(Action::MoveWest));
+        assert_eq!(move_toward([2, 2], [2, 2]), None);
+        assert_eq!(move_toward([2, 2], [3, 3]), None);
+        assert_eq!(move_toward([0, 0], [2, 0]), None);
+    }
+}
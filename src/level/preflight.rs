@@ -0,0 +1,191 @@
+use std::fmt;
+
+use super::error::LevelError;
+
+/// Heap bytes generation needs per cell regardless of dimension count (union-find node and wall set).
+const BYTES_PER_CELL: u64 = 20;
+/// Extra heap bytes per cell for each dimension (candidate edge and open wall entry).
+const BYTES_PER_CELL_PER_DIM: u64 = 27;
+/// Generation time per cell, measured on a `[50, 50, 50]` maze in a release build.
+const NANOS_PER_CELL: u64 = 1_600;
+
+/// Rough cost of generating a maze with the given side lengths, computed without allocating it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoadEstimate {
+    pub cells: u64,
+    pub bytes: u64,
+    pub nanos: u64,
+}
+
+impl LoadEstimate {
+    pub fn new(lengths: &[u16]) -> Self {
+        let cells = lengths
+            .iter()
+            .try_fold(1u64, |acc, l| acc.checked_mul(*l as u64))
+            .unwrap_or(u64::MAX);
+        let per_cell = BYTES_PER_CELL + BYTES_PER_CELL_PER_DIM * lengths.len() as u64;
+        Self {
+            cells,
+            bytes: cells.saturating_mul(per_cell),
+            nanos: cells.saturating_mul(NANOS_PER_CELL),
+        }
+    }
+}
+
+impl fmt::Display for LoadEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "~{} cells, ~{}, ~{}",
+            approx_count(self.cells),
+            approx_bytes(self.bytes),
+            approx_duration(self.nanos)
+        )
+    }
+}
+
+/// Upper bounds a `LoadLevel` request has to fit in before the maze is generated.
+#[derive(Clone, Debug)]
+pub struct LoadLimits {
+    pub max_cells: u64,
+    pub max_bytes: u64,
+}
+
+impl Default for LoadLimits {
+    fn default() -> Self {
+        Self {
+            max_cells: 1_000_000,
+            max_bytes: 512 * 1024 * 1024,
+        }
+    }
+}
+
+impl LoadLimits {
+    pub fn allows(&self, estimate: &LoadEstimate) -> bool {
+        estimate.cells <= self.max_cells && estimate.bytes <= self.max_bytes
+    }
+
+    pub fn check(&self, lengths: &[u16]) -> Result<(), LevelError> {
+        let estimate = LoadEstimate::new(lengths);
+        if self.allows(&estimate) {
+            Ok(())
+        } else {
+            Err(LevelError::TooLarge {
+                estimate,
+                suggestion: self.suggest(lengths),
+            })
+        }
+    }
+
+    /// Finds the nearest shape within the limits by repeatedly shrinking the largest axis,
+    /// preferring the lowest axis index on ties.
+    pub fn suggest(&self, lengths: &[u16]) -> Option<Vec<u16>> {
+        let mut shape = lengths.to_vec();
+        loop {
+            if self.allows(&LoadEstimate::new(&shape)) {
+                return Some(shape);
+            }
+            let largest = shape
+                .iter_mut()
+                .rev()
+                .max_by_key(|l| **l)
+                .filter(|l| **l > 1)?;
+            *largest -= 1;
+        }
+    }
+}
+
+fn approx_count(n: u64) -> String {
+    const UNITS: [(u64, &str); 4] = [
+        (1_000_000_000_000, "trillion"),
+        (1_000_000_000, "billion"),
+        (1_000_000, "million"),
+        (1_000, "thousand"),
+    ];
+    for (scale, name) in UNITS {
+        if n >= scale {
+            return format!("{:.1} {}", n as f64 / scale as f64, name);
+        }
+    }
+    format!("{}", n)
+}
+
+fn approx_bytes(n: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = n as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.0} {}", value, UNITS[unit])
+}
+
+fn approx_duration(nanos: u64) -> String {
+    let seconds = nanos as f64 / 1e9;
+    if seconds < 1e-3 {
+        format!("{:.0} µs", seconds * 1e6)
+    } else if seconds < 1.0 {
+        format!("{:.0} ms", seconds * 1e3)
+    } else if seconds < 60.0 {
+        format!("{:.0} s", seconds)
+    } else if seconds < 60.0 * 60.0 {
+        format!("{:.0} min", seconds / 60.0)
+    } else if seconds < 60.0 * 60.0 * 48.0 {
+        format!("{:.0} h", seconds / (60.0 * 60.0))
+    } else {
+        format!("{:.0} days", seconds / (60.0 * 60.0 * 24.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate_counts_cells() {
+        assert_eq!(LoadEstimate::new(&[4, 15, 2]).cells, 120);
+        assert_eq!(LoadEstimate::new(&[64; 6]).cells, 68_719_476_736);
+        assert_eq!(LoadEstimate::new(&[255; 6]).cells, 255u64.pow(6));
+        assert_eq!(LoadEstimate::new(&[5, 0]).cells, 0);
+    }
+
+    #[test]
+    fn estimate_display() {
+        assert_eq!(
+            LoadEstimate::new(&[64; 6]).to_string(),
+            "~68.7 billion cells, ~11 TB, ~31 h"
+        );
+        assert_eq!(
+            LoadEstimate::new(&[4, 4]).to_string(),
+            "~16 cells, ~1 KB, ~26 µs"
+        );
+    }
+
+    #[test]
+    fn limits_allow_small_shapes() {
+        let limits = LoadLimits::default();
+        assert!(limits.check(&[4, 15, 2]).is_ok());
+        assert_eq!(limits.suggest(&[4, 15, 2]), Some(vec![4, 15, 2]));
+    }
+
+    #[test]
+    fn suggest_shrinks_largest_first() {
+        let limits = LoadLimits {
+            max_cells: 100,
+            max_bytes: u64::MAX,
+        };
+        assert_eq!(limits.suggest(&[30, 5]), Some(vec![20, 5]));
+        assert_eq!(limits.suggest(&[3, 40, 3]), Some(vec![3, 11, 3]));
+        assert_eq!(limits.suggest(&[64; 6]).map(|s| s.len()), Some(6));
+        assert_eq!(limits.suggest(&[10, 10, 10]), Some(vec![4, 5, 5]));
+    }
+
+    #[test]
+    fn limits_refuse_absurd_shapes() {
+        let error = LoadLimits::default().check(&[64; 6]).unwrap_err();
+        assert!(matches!(error, LevelError::TooLarge { .. }));
+        assert!(error.to_string().contains("~68.7 billion cells"));
+        assert!(error.to_string().contains("[10, 10, 10, 10, 10, 10]"));
+    }
+}
@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+use super::bindings::Action;
+
+/// Hold-to-repeat for movement keys. The press itself moves straight away, then once the key
+/// has been held for `delay` it moves again every `interval` until it is released or a move
+/// is blocked.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyRepeat {
+    pub delay: Duration,
+    pub interval: Duration,
+    held: Option<Action>,
+    until_repeat: Duration,
+    /// The held move hit a wall, so it waits for the key to be released.
+    stalled: bool,
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(250), Duration::from_millis(100))
+    }
+}
+
+impl KeyRepeat {
+    pub fn new(delay: Duration, interval: Duration) -> Self {
+        Self {
+            delay,
+            interval,
+            held: None,
+            until_repeat: Duration::ZERO,
+            stalled: false,
+        }
+    }
+
+    /// Starts repeating `action`, which was just pressed and has already moved once.
+    pub fn press(&mut self, action: Action) {
+        self.held = Some(action);
+        self.until_repeat = self.delay;
+        self.stalled = false;
+    }
+
+    /// Advances the timer by `delta`, returning the held move when it is due again, or
+    /// forgetting it if `pressed` says its key is up.
+    pub fn tick(&mut self, delta: Duration, pressed: impl Fn(Action) -> bool) -> Option<Action> {
+        let held = self.held.filter(|action| pressed(*action));
+        self.held = held;
+        if self.stalled {
+            return None;
+        }
+        let held = held?;
+        if delta < self.until_repeat {
+            self.until_repeat -= delta;
+            return None;
+        }
+        self.until_repeat = self.interval;
+        Some(held)
+    }
+
+    /// Stops repeating until the key is pressed again, after the held move was blocked.
+    pub fn stall(&mut self) {
+        self.stalled = true;
+    }
+
+    /// Waits out the initial delay again, so a new set of displayed axes isn't raced through.
+    pub fn reset(&mut self) {
+        self.until_repeat = self.delay;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(50);
+    const NORTH: Option<Action> = Some(Action::MoveNorth);
+
+    fn run(repeat: &mut KeyRepeat, frames: usize, held: bool) -> Vec<Option<Action>> {
+        (0..frames).map(|_| repeat.tick(FRAME, |_| held)).collect()
+    }
+
+    #[test]
+    fn holding_repeats_after_the_delay() {
+        let mut repeat = KeyRepeat::default();
+        repeat.press(Action::MoveNorth);
+        assert_eq!(
+            run(&mut repeat, 9, true),
+            [None, None, None, None, NORTH, None, NORTH, None, NORTH]
+        );
+    }
+
+    #[test]
+    fn releasing_stops_the_repeat() {
+        let mut repeat = KeyRepeat::default();
+        repeat.press(Action::MoveNorth);
+        run(&mut repeat, 3, true);
+        assert_eq!(run(&mut repeat, 10, false), [None; 10]);
+        // Holding again without a new press doesn't resume it.
+        assert_eq!(run(&mut repeat, 10, true), [None; 10]);
+    }
+
+    #[test]
+    fn blocked_moves_stall_until_pressed_again() {
+        let mut repeat = KeyRepeat::default();
+        repeat.press(Action::MoveNorth);
+        assert_eq!(run(&mut repeat, 5, true).last(), Some(&NORTH));
+        repeat.stall();
+        assert_eq!(run(&mut repeat, 10, true), [None; 10]);
+
+        repeat.press(Action::MoveNorth);
+        assert_eq!(run(&mut repeat, 5, true).last(), Some(&NORTH));
+    }
+
+    #[test]
+    fn reset_waits_out_the_delay_again() {
+        let mut repeat = KeyRepeat::new(Duration::from_millis(100), Duration::from_millis(50));
+        repeat.press(Action::MoveEast);
+        let east = Some(Action::MoveEast);
+        assert_eq!(run(&mut repeat, 3, true), [None, east, east]);
+        repeat.reset();
+        assert_eq!(run(&mut repeat, 3, true), [None, east, east]);
+    }
+}
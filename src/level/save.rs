@@ -0,0 +1,566 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::{ecs::system::SystemParam, prelude::*, window::WindowCloseRequested};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    code::LevelCode,
+    demo::DemoMode,
+    difficulty::Difficulty,
+    error::LevelError,
+    loader::{LastLoad, LoadLevel},
+    maze_level::{AxisChanged, MazeLevel, PositionChanged},
+    stats::LevelStats,
+};
+use crate::{
+    toast::{ShowToast, ToastKind},
+    AppState,
+};
+
+/// Layout of `SaveFile`, bumped whenever a field changes so old saves are dropped cleanly.
+const SAVE_VERSION: u32 = 1;
+const SAVE_FILE: &str = "save.ron";
+
+/// A level in progress, enough to regenerate its maze and put the player back where they were.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SaveFile {
+    pub version: u32,
+    /// The `LevelCode` the maze is regenerated from.
+    pub code: String,
+    /// `Maze::fingerprint` of the saved maze, so a generator that has changed since is caught
+    /// instead of dropping the player into a different maze.
+    pub fingerprint: u64,
+    pub position: Vec<u16>,
+    pub axis: [u8; 2],
+    /// Every visited cell, in the order it was first entered.
+    pub trail: Vec<Vec<u16>>,
+    pub stats: SavedStats,
+    /// The campaign level being played, so continuing it still counts toward the campaign.
+    #[serde(default)]
+    pub campaign_level: Option<usize>,
+    /// The preset being played, which a level code doesn't carry, so continuing it keeps its
+    /// fog and still counts toward its best times.
+    #[serde(default)]
+    pub difficulty: Option<Difficulty>,
+    /// Whether the level is a time trial. The time left follows from the saved elapsed time,
+    /// so a continued trial picks up its countdown where it was paused.
+    #[serde(default)]
+    pub time_trial: bool,
+}
+
+/// `LevelStats` as it's written to disk.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SavedStats {
+    pub steps: u32,
+    pub blocked_moves: u32,
+    pub axis_rotations: u32,
+    pub hints_used: u32,
+    pub undos_used: u32,
+    pub elapsed: Duration,
+}
+
+impl From<&LevelStats> for SavedStats {
+    fn from(stats: &LevelStats) -> Self {
+        Self {
+            steps: stats.steps,
+            blocked_moves: stats.blocked_moves,
+            axis_rotations: stats.axis_rotations,
+            hints_used: stats.hints_used,
+            undos_used: stats.undos_used,
+            elapsed: stats.elapsed,
+        }
+    }
+}
+
+impl From<&SavedStats> for LevelStats {
+    fn from(stats: &SavedStats) -> Self {
+        Self {
+            steps: stats.steps,
+            blocked_moves: stats.blocked_moves,
+            axis_rotations: stats.axis_rotations,
+            hints_used: stats.hints_used,
+            undos_used: stats.undos_used,
+            elapsed: stats.elapsed,
+        }
+    }
+}
+
+impl SaveFile {
+    /// Captures the level being played, or `None` for a maze loaded from a file, which a
+    /// level code can't regenerate.
+    pub fn capture(last_load: &LastLoad, level: &MazeLevel, stats: &LevelStats) -> Option<Self> {
+        Some(Self {
+            version: SAVE_VERSION,
+            code: last_load.code()?,
+            fingerprint: level.fingerprint(),
+            position: level.dims().to_vec(),
+            axis: level.axis(),
+            trail: level.trail(),
+            stats: stats.into(),
+            campaign_level: last_load.0.campaign_level,
+            difficulty: last_load.0.difficulty.clone(),
+            time_trial: last_load.0.time_trial,
+        })
+    }
+
+    pub fn parse(text: &str) -> Result<Self, LevelError> {
+        let save: SaveFile = ron::from_str(text)?;
+        if save.version != SAVE_VERSION {
+            return Err(LevelError::UnsupportedVersion(save.version));
+        }
+        Ok(save)
+    }
+
+    /// The request that regenerates the saved maze.
+    pub fn load_level(&self) -> Result<LoadLevel, LevelError> {
+        let level = LevelCode::decode(&self.code).map_err(LevelError::InvalidCode)?;
+        Ok(LoadLevel {
+            campaign_level: self.campaign_level,
+            difficulty: self.difficulty.clone(),
+            time_trial: self.time_trial,
+            ..level
+        })
+    }
+
+    /// Puts the player back on a freshly generated `level`, refusing if it isn't the maze
+    /// that was saved.
+    pub fn restore(&self, level: &mut MazeLevel) -> Result<LevelStats, LevelError> {
+        if level.fingerprint() != self.fingerprint {
+            return Err(LevelError::SaveMismatch);
+        }
+        level.restore_trail(&self.trail)?;
+        level.restore(&self.position, self.axis)?;
+        Ok((&self.stats).into())
+    }
+}
+
+/// Where the game is saved, or `None` to never touch the disk.
+pub struct SaveLocation(pub Option<PathBuf>);
+
+impl Default for SaveLocation {
+    fn default() -> Self {
+        Self(data_dir().map(|dir| dir.join("nothing-moves").join(SAVE_FILE)))
+    }
+}
+
+impl SaveLocation {
+    /// Another file kept next to the save, such as campaign progress.
+    pub fn sibling(&self, name: &str) -> Option<PathBuf> {
+        self.0.as_ref().map(|path| path.with_file_name(name))
+    }
+}
+
+/// The platform's directory for per-user application data.
+fn data_dir() -> Option<PathBuf> {
+    let home = || env::var_os("HOME").map(PathBuf::from);
+    if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home().map(|home| home.join("Library").join("Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| home().map(|home| home.join(".local").join("share")))
+    }
+}
+
+/// The save on disk, kept in memory so the main menu can offer to continue it.
+#[derive(Default)]
+pub struct SavedGame(pub Option<SaveFile>);
+
+/// Sent by the main menu to continue the saved game.
+pub struct ContinueGame;
+
+/// A save to restore once its maze has been regenerated.
+pub struct PendingResume(SaveFile);
+
+pub fn drop_pending_resume(mut c: Commands) {
+    c.remove_resource::<PendingResume>();
+}
+
+/// Reads the save, treating a missing or unreadable one as no save at all.
+fn read_save(path: &Path) -> Option<SaveFile> {
+    let text = fs::read_to_string(path).ok()?;
+    match SaveFile::parse(&text) {
+        Ok(save) => Some(save),
+        Err(e) => {
+            warn!("Ignoring the saved game at {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Writes `value` as RON, creating the directory it goes in if needed.
+pub fn write_ron(path: &Path, value: &impl Serialize) -> Result<(), LevelError> {
+    let text =
+        ron::ser::to_string_pretty(value, Default::default()).map_err(LevelError::Serialize)?;
+    let io = |path: &Path, error| LevelError::Io {
+        path: path.to_path_buf(),
+        error,
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| io(dir, e))?;
+    }
+    fs::write(path, text).map_err(|e| io(path, e))
+}
+
+pub fn load_saved_game(mut c: Commands, location: Res<SaveLocation>) {
+    let save = location.0.as_deref().and_then(read_save);
+    c.insert_resource(SavedGame(save));
+}
+
+/// The level being played, as saving it needs.
+#[derive(SystemParam)]
+pub struct PlayedLevel<'w, 's> {
+    last_load: Option<Res<'w, LastLoad>>,
+    level: Option<Res<'w, MazeLevel>>,
+    stats: Res<'w, LevelStats>,
+    /// The demo plays mazes that aren't the player's, so nothing it does is saved.
+    demo: Res<'w, DemoMode>,
+    #[system_param(ignore)]
+    _marker: std::marker::PhantomData<&'s ()>,
+}
+
+fn save_level(location: &SaveLocation, saved: &mut SavedGame, played: &PlayedLevel) {
+    if played.demo.is_running() {
+        return;
+    }
+    let (path, last_load, level) = match (&location.0, &played.last_load, &played.level) {
+        (Some(path), Some(last_load), Some(level)) => (path, last_load, level),
+        _ => return,
+    };
+    let save = match SaveFile::capture(last_load, level, &played.stats) {
+        Some(save) => save,
+        None => return,
+    };
+    match write_ron(path, &save) {
+        Ok(()) => saved.0 = Some(save),
+        Err(e) => error!("Could not save the game: {}", e),
+    }
+}
+
+/// Saves whenever the game is paused, which is also the way back to the main menu.
+pub fn save_on_pause(
+    location: Res<SaveLocation>,
+    mut saved: ResMut<SavedGame>,
+    played: PlayedLevel,
+) {
+    save_level(&location, &mut saved, &played);
+}
+
+/// Saves a level that is still being played when the window is closed.
+pub fn save_on_close(
+    state: Res<State<AppState>>,
+    location: Res<SaveLocation>,
+    mut saved: ResMut<SavedGame>,
+    played: PlayedLevel,
+    mut closed: EventReader<WindowCloseRequested>,
+) {
+    let playing = matches!(state.current(), AppState::InMaze | AppState::Paused);
+    if closed.iter().count() > 0 && playing {
+        save_level(&location, &mut saved, &played);
+    }
+}
+
+/// Deletes the save once its maze is solved, as there is nothing left to continue.
+pub fn delete_completed_save(
+    location: Res<SaveLocation>,
+    mut saved: ResMut<SavedGame>,
+    last_load: Option<Res<LastLoad>>,
+    demo: Res<DemoMode>,
+) {
+    // A save the demo solved hasn't been solved by the player.
+    if demo.is_running() {
+        return;
+    }
+    let code = last_load.and_then(|last_load| last_load.code());
+    if saved.0.is_none() || saved.0.as_ref().map(|save| &save.code) != code.as_ref() {
+        return;
+    }
+    saved.0 = None;
+    if let Some(path) = &location.0 {
+        if let Err(e) = fs::remove_file(path) {
+            warn!(
+                "Could not delete the saved game at {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+}
+
+/// Regenerates the saved maze, which `resume_saved_game` restores the player onto.
+pub fn continue_game(
+    mut c: Commands,
+    saved: Res<SavedGame>,
+    mut requests: EventReader<ContinueGame>,
+    mut load_level: EventWriter<LoadLevel>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if requests.iter().count() == 0 {
+        return;
+    }
+    let save = match &saved.0 {
+        Some(save) => save,
+        None => return,
+    };
+    match save.load_level() {
+        Ok(level) => {
+            c.insert_resource(PendingResume(save.clone()));
+            load_level.send(level);
+        }
+        Err(e) => {
+            error!("Could not continue the saved game: {}", e);
+            toasts.send(ShowToast::new(ToastKind::Error, e.to_string()));
+        }
+    }
+}
+
+/// Everything resuming a save can announce.
+#[derive(SystemParam)]
+pub struct ResumeEvents<'w, 's> {
+    position: EventWriter<'w, 's, PositionChanged>,
+    axis: EventWriter<'w, 's, AxisChanged>,
+    toasts: EventWriter<'w, 's, ShowToast>,
+}
+
+/// Restores a continued save on top of its regenerated maze. Runs after the level's stats
+/// are reset, so the saved stats replace them.
+pub fn resume_saved_game(
+    mut c: Commands,
+    pending: Option<Res<PendingResume>>,
+    last_load: Option<Res<LastLoad>>,
+    mut level: ResMut<MazeLevel>,
+    mut app_state: ResMut<State<AppState>>,
+    mut events: ResumeEvents,
+) {
+    let save = match pending {
+        Some(pending) => pending.0.clone(),
+        None => return,
+    };
+    c.remove_resource::<PendingResume>();
+    // Another maze may have been loaded in the meantime, which the save doesn't belong to.
+    if last_load.and_then(|last_load| last_load.code()) != Some(save.code.clone()) {
+        return;
+    }
+    match save.restore(&mut level) {
+        Ok(stats) => {
+            c.insert_resource(stats);
+            events.position.send(PositionChanged::loaded(level.pos()));
+            events.axis.send(AxisChanged::loaded(level.axis()));
+        }
+        Err(e) => {
+            error!("Could not continue the saved game: {}", e);
+            events
+                .toasts
+                .send(ShowToast::new(ToastKind::Error, e.to_string()));
+            if let Err(e) = app_state.replace(AppState::MainMenu) {
+                error!("Could not return to the menu: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::*;
+
+    use super::*;
+    use crate::level::{
+        loader::RngSource,
+        maze_level::{Axis, Direction},
+    };
+
+    fn save() -> SaveFile {
+        SaveFile {
+            version: SAVE_VERSION,
+            code: "ABC".to_string(),
+            fingerprint: 42,
+            position: vec![1, 0, 1],
+            axis: [0, 2],
+            trail: vec![vec![0, 0, 0], vec![1, 0, 0], vec![1, 0, 1]],
+            stats: SavedStats {
+                steps: 7,
+                elapsed: Duration::from_millis(12_345),
+                ..Default::default()
+            },
+            campaign_level: Some(2),
+            difficulty: Some(Difficulty::Medium),
+            time_trial: true,
+        }
+    }
+
+    #[test]
+    fn saves_round_trip_through_ron() {
+        let save = save();
+        assert_eq!(
+            SaveFile::parse(&ron::to_string(&save).unwrap()).unwrap(),
+            save
+        );
+    }
+
+    #[test]
+    fn unreadable_saves_are_rejected() {
+        assert!(matches!(
+            SaveFile::parse("(version: 1"),
+            Err(LevelError::Parse(_))
+        ));
+        let future = SaveFile {
+            version: SAVE_VERSION + 1,
+            ..save()
+        };
+        assert!(matches!(
+            SaveFile::parse(&ron::to_string(&future).unwrap()),
+            Err(LevelError::UnsupportedVersion(v)) if v == SAVE_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn writing_under_a_file_names_the_path() {
+        let file = std::env::temp_dir().join(format!("nothing-moves-write-{}", std::process::id()));
+        fs::write(&file, "").unwrap();
+        let path = file.join(SAVE_FILE);
+        let result = write_ron(&path, &save());
+        fs::remove_file(&file).unwrap();
+        assert!(matches!(result, Err(LevelError::Io { path: p, .. }) if p == file));
+    }
+
+    #[test]
+    fn continuing_a_preset_keeps_its_difficulty() {
+        let last_load = LastLoad(LoadLevel::from_difficulty(
+            Difficulty::Nightmare,
+            RngSource::Seeded(684153987),
+        ));
+        let save = SaveFile::capture(&last_load, &level(), &LevelStats::default()).unwrap();
+        let continued = SaveFile::parse(&ron::to_string(&save).unwrap())
+            .unwrap()
+            .load_level()
+            .unwrap();
+        assert_eq!(continued.difficulty, Some(Difficulty::Nightmare));
+        assert_eq!(continued.dimensions, Difficulty::Nightmare.to_dimensions());
+
+        // Saves from before difficulties were kept continue as free play.
+        let text = ron::to_string(&save)
+            .unwrap()
+            .replace(",difficulty:Some(Nightmare)", "");
+        assert!(!text.contains("difficulty"));
+        assert_eq!(SaveFile::parse(&text).unwrap().difficulty, None);
+    }
+
+    #[test]
+    fn continuing_a_time_trial_keeps_its_clock() {
+        let last_load = LastLoad(LoadLevel {
+            rng_source: RngSource::Seeded(684153987),
+            time_trial: true,
+            ..Default::default()
+        });
+        let played = level();
+        let stats = LevelStats {
+            elapsed: Duration::from_secs(25),
+            ..Default::default()
+        };
+        let save = SaveFile::capture(&last_load, &played, &stats).unwrap();
+        let save = SaveFile::parse(&ron::to_string(&save).unwrap()).unwrap();
+        assert!(save.load_level().unwrap().time_trial);
+
+        // The countdown is worked out from the elapsed time, which comes back with the save.
+        let mut resumed = level();
+        assert_eq!(save.restore(&mut resumed).unwrap().elapsed, stats.elapsed);
+    }
+
+    fn level() -> MazeLevel {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        MazeLevel::from_maze(maze::Maze::new(&[4, 5, 2], &mut rng)).with_seed(684153987)
+    }
+
+    #[test]
+    fn restoring_puts_the_player_back() {
+        let mut played = level();
+        for dir in [Direction::Positive, Direction::Negative] {
+            for axis in [Axis::X, Axis::Y] {
+                played.move_pos(axis, dir);
+            }
+        }
+        played.shift_axis(Axis::Y, Direction::Positive);
+        let stats = LevelStats {
+            steps: 3,
+            elapsed: Duration::from_secs(20),
+            ..Default::default()
+        };
+        let save = SaveFile {
+            version: SAVE_VERSION,
+            code: "ABC".to_string(),
+            fingerprint: played.fingerprint(),
+            position: played.dims().to_vec(),
+            axis: played.axis(),
+            trail: played.trail(),
+            stats: (&stats).into(),
+            campaign_level: None,
+            difficulty: None,
+            time_trial: false,
+        };
+
+        let mut resumed = level();
+        assert_eq!(save.restore(&mut resumed).unwrap(), stats);
+        assert_eq!(resumed.dims(), played.dims());
+        assert_eq!(resumed.axis(), played.axis());
+        assert_eq!(resumed.trail(), played.trail());
+    }
+
+    #[test]
+    fn restoring_onto_a_different_maze_is_refused() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut other = MazeLevel::from_maze(maze::Maze::new(&[4, 5, 2], &mut rng));
+        let save = SaveFile {
+            fingerprint: level().fingerprint(),
+            ..save()
+        };
+        assert!(matches!(
+            save.restore(&mut other),
+            Err(LevelError::SaveMismatch)
+        ));
+        assert_eq!(other.dims(), &[0, 0, 0]);
+    }
+
+    #[test]
+    fn restoring_outside_the_maze_is_refused() {
+        let fingerprint = level().fingerprint();
+        let saves = [
+            SaveFile {
+                axis: [0, 3],
+                ..save()
+            },
+            SaveFile {
+                axis: [1, 1],
+                ..save()
+            },
+            SaveFile {
+                position: vec![1, 5, 1],
+                ..save()
+            },
+            SaveFile {
+                position: vec![1, 0],
+                ..save()
+            },
+            SaveFile {
+                trail: vec![vec![0, 0, 0], vec![0, 0, 2]],
+                ..save()
+            },
+        ];
+        for save in saves {
+            let save = SaveFile {
+                fingerprint,
+                ..save
+            };
+            assert!(
+                matches!(save.restore(&mut level()), Err(LevelError::InvalidSave(_))),
+                "{:?} was restored",
+                save
+            );
+        }
+    }
+}
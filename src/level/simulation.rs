@@ -0,0 +1,265 @@
+//! Plays levels without a window: the level's logic on top of `MinimalPlugins`, pressed by a
+//! script instead of a device, with everything it announces recorded for tests to check.
+
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::{ecs::event::Events, prelude::*, window::WindowCloseRequested};
+
+use super::{
+    bindings::{clear_triggered_actions, Action, TriggeredActions},
+    demo::{self, next_step, DemoStep},
+    goal::MazeCompleted,
+    input::MOVES,
+    loader::{DimensionLength, LoadLevel, RngSource},
+    maze_level::{AxisChanged, ChangeCause, MoveBlocked, PositionChanged},
+    plugin::LevelLogicPlugin,
+    save::SaveLocation,
+    stats::LevelStats,
+    MazeLevel,
+};
+use crate::{toast::ShowToast, AppState};
+
+const STATES: [AppState; 6] = [
+    AppState::MainMenu,
+    AppState::Loading,
+    AppState::InMaze,
+    AppState::Paused,
+    AppState::Completed,
+    AppState::Failed,
+];
+
+/// Actions waiting to be pressed, one a frame.
+#[derive(Default)]
+struct Script(VecDeque<Action>);
+
+/// Everything the level announced since the simulation started, which would otherwise be
+/// dropped from `Events` two frames after it was sent.
+#[derive(Default)]
+struct Recording {
+    positions: Vec<PositionChanged>,
+    axes: Vec<AxisChanged>,
+    blocked: Vec<MoveBlocked>,
+    completed: usize,
+    /// Every state entered, in order.
+    states: Vec<AppState>,
+}
+
+/// Presses the script's next action, as if on one more device.
+fn play_script(mut script: ResMut<Script>, mut actions: ResMut<TriggeredActions>) {
+    if let Some(action) = script.0.pop_front() {
+        actions.trigger(action);
+    }
+}
+
+fn record_events(
+    mut recording: ResMut<Recording>,
+    mut positions: EventReader<PositionChanged>,
+    mut axes: EventReader<AxisChanged>,
+    mut blocked: EventReader<MoveBlocked>,
+    mut completed: EventReader<MazeCompleted>,
+) {
+    recording.positions.extend(positions.iter().cloned());
+    recording.axes.extend(axes.iter().cloned());
+    recording.blocked.extend(blocked.iter().cloned());
+    recording.completed += completed.iter().count();
+}
+
+fn record_state(state: Res<State<AppState>>, mut recording: ResMut<Recording>) {
+    recording.states.push(state.current().clone());
+}
+
+struct Simulation {
+    app: App,
+}
+
+impl Simulation {
+    /// Starts on the main menu, with nothing loaded and nowhere to save.
+    fn new() -> Self {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<Input<KeyCode>>()
+            .init_resource::<Input<MouseButton>>()
+            .init_resource::<Gamepads>()
+            .init_resource::<Input<GamepadButton>>()
+            .init_resource::<Axis<GamepadAxis>>()
+            .add_event::<GamepadEvent>()
+            .add_event::<ShowToast>()
+            .add_event::<WindowCloseRequested>()
+            .add_state(AppState::MainMenu)
+            .add_plugin(LevelLogicPlugin)
+            .insert_resource(SaveLocation(None))
+            .init_resource::<Script>()
+            .init_resource::<Recording>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                play_script
+                    .after(clear_triggered_actions)
+                    .before(demo::demo_input),
+            )
+            .add_system_to_stage(CoreStage::Last, record_events);
+        for state in STATES {
+            app.add_system_set(SystemSet::on_enter(state).with_system(record_state));
+        }
+        app.update();
+        Self { app }
+    }
+
+    /// Loads `level` and runs until it can be played.
+    fn load(&mut self, level: LoadLevel) {
+        self.app
+            .world
+            .resource_mut::<Events<LoadLevel>>()
+            .send(level);
+        // Generation runs on another thread, so give it a bounded number of frames to finish.
+        for _ in 0..1000 {
+            self.app.update();
+            if self.state() == &AppState::InMaze {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        panic!("the level never loaded, ending on {:?}", self.state());
+    }
+
+    /// Presses each of `actions` on a frame of its own, then runs one more frame so systems
+    /// that react to the last press, in no particular order after it, have seen it.
+    fn press(&mut self, actions: impl IntoIterator<Item = Action>) {
+        self.app.world.resource_mut::<Script>().0.extend(actions);
+        while !self.app.world.resource::<Script>().0.is_empty() {
+            self.app.update();
+        }
+        self.app.update();
+    }
+
+    fn level(&self) -> &MazeLevel {
+        self.app.world.resource::<MazeLevel>()
+    }
+
+    fn recording(&self) -> &Recording {
+        self.app.world.resource::<Recording>()
+    }
+
+    fn state(&self) -> &AppState {
+        self.app.world.resource::<State<AppState>>().current()
+    }
+}
+
+fn seeded(dimensions: DimensionLength) -> LoadLevel {
+    LoadLevel {
+        rng_source: RngSource::Seeded(684153987),
+        dimensions,
+        ..Default::default()
+    }
+}
+
+/// A move from where the player stands that runs into a wall inside the maze, if any does.
+fn move_into_wall(level: &MazeLevel) -> Option<Action> {
+    let axis = level.axis();
+    MOVES
+        .iter()
+        .find(|(_, a, dir)| level.can_move(*a.get(&axis), *dir) == Some(false))
+        .map(|(action, _, _)| *action)
+}
+
+#[test]
+fn a_scripted_player_solves_a_seeded_maze() {
+    let mut sim = Simulation::new();
+    sim.load(seeded(DimensionLength::Three([3, 3, 2])));
+    let optimal = sim.level().optimal_moves();
+    for _ in 0..100 {
+        if sim.state() != &AppState::InMaze {
+            break;
+        }
+        let step = next_step(&**sim.level()).expect("the goal is reachable until it's reached");
+        sim.press([step.action()]);
+    }
+
+    assert!(sim.level().at_goal());
+    assert_eq!(
+        sim.app.world.resource::<LevelStats>().steps as usize,
+        optimal
+    );
+    let recording = sim.recording();
+    assert_eq!(recording.completed, 1);
+    assert!(recording.blocked.is_empty());
+    let moves = recording
+        .positions
+        .iter()
+        .filter(|changed| changed.cause == ChangeCause::Moved)
+        .count();
+    assert_eq!(moves, optimal);
+    assert_eq!(
+        recording.states,
+        [
+            AppState::MainMenu,
+            AppState::Loading,
+            AppState::InMaze,
+            AppState::Completed
+        ]
+    );
+}
+
+#[test]
+fn walking_into_a_wall_is_blocked() {
+    let mut sim = Simulation::new();
+    sim.load(seeded(DimensionLength::Two([6, 6])));
+    // Follow the way to the goal until a wall stands beside the player.
+    let bump = loop {
+        if let Some(action) = move_into_wall(sim.level()) {
+            break action;
+        }
+        match next_step(&**sim.level()) {
+            Some(step) => sim.press([step.action()]),
+            None => panic!("reached the goal without passing a wall"),
+        }
+    };
+    let position = sim.level().dims().to_vec();
+    let moves = sim.recording().positions.len();
+
+    sim.press([bump]);
+
+    assert_eq!(sim.level().dims(), position);
+    let recording = sim.recording();
+    assert_eq!(recording.positions.len(), moves);
+    let [blocked] = &recording.blocked[..] else {
+        panic!("expected one blocked move, got {:?}", recording.blocked);
+    };
+    assert_eq!(blocked.position, sim.level().pos());
+    assert!(!blocked.out_of_bounds);
+    assert_eq!(sim.app.world.resource::<LevelStats>().blocked_moves, 1);
+    assert_eq!(sim.state(), &AppState::InMaze);
+}
+
+#[test]
+fn rotating_moves_along_the_newly_displayed_dimension() {
+    let mut sim = Simulation::new();
+    sim.load(seeded(DimensionLength::Three([3, 3, 3])));
+    // The goal is across the hidden dimension, so the way there has to turn to it.
+    let rotation = loop {
+        match next_step(&**sim.level()).expect("the goal is across the hidden dimension") {
+            step @ DemoStep::Rotate(..) => break step,
+            step => sim.press([step.action()]),
+        }
+    };
+    let before = sim.level().axis();
+    sim.press([rotation.action()]);
+    let after = sim.level().axis();
+    assert_eq!(after[0], before[0]);
+    assert_ne!(after[1], before[1]);
+    assert_eq!(
+        sim.recording()
+            .axes
+            .last()
+            .map(|changed| (changed.from, changed.to)),
+        Some((before, after))
+    );
+
+    let step = next_step(&**sim.level()).unwrap();
+    assert!(matches!(step, DemoStep::Move(..)));
+    let position = sim.level().dims()[after[1] as usize];
+    sim.press([step.action()]);
+
+    assert_ne!(sim.level().dims()[after[1] as usize], position);
+    let moved = sim.recording().positions.last().unwrap();
+    assert_eq!(moved.dimension, Some(after[1] as usize));
+}
@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::maze_level::{AxisChanged, ChangeCause, MoveBlocked, PositionChanged};
+
+/// How the current level has been played so far, reset whenever a level starts.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LevelStats {
+    pub steps: u32,
+    pub blocked_moves: u32,
+    pub axis_rotations: u32,
+    pub hints_used: u32,
+    pub undos_used: u32,
+    /// Time spent playing, which stops while paused or minimized.
+    pub elapsed: Duration,
+}
+
+impl LevelStats {
+    pub fn record_position(&mut self, changed: &PositionChanged) {
+        match changed.cause {
+            ChangeCause::Moved => self.steps += 1,
+            ChangeCause::Undone => self.undos_used += 1,
+            ChangeCause::Loaded | ChangeCause::Rotated => {}
+        }
+    }
+
+    pub fn record_axis(&mut self, changed: &AxisChanged) {
+        if changed.cause == ChangeCause::Rotated {
+            self.axis_rotations += 1;
+        }
+    }
+
+    pub fn record_blocked(&mut self, _blocked: &MoveBlocked) {
+        self.blocked_moves += 1;
+    }
+}
+
+/// The fewest steps and shortest time a level has been completed in, which may come from
+/// different runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BestStats {
+    pub steps: u32,
+    pub elapsed: Duration,
+}
+
+impl BestStats {
+    /// `best` improved by a run that finished with `stats`.
+    pub fn improve(best: Option<BestStats>, stats: &LevelStats) -> Self {
+        match best {
+            Some(best) => BestStats {
+                steps: best.steps.min(stats.steps),
+                elapsed: best.elapsed.min(stats.elapsed),
+            },
+            None => BestStats {
+                steps: stats.steps,
+                elapsed: stats.elapsed,
+            },
+        }
+    }
+}
+
+/// Formats a duration as minutes and seconds, like `2:05`.
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+pub fn reset_stats(mut c: Commands) {
+    c.insert_resource(LevelStats::default());
+}
+
+pub fn track_stats(
+    mut stats: ResMut<LevelStats>,
+    mut position_changed: EventReader<PositionChanged>,
+    mut axis_changed: EventReader<AxisChanged>,
+    mut blocked: EventReader<MoveBlocked>,
+) {
+    for changed in position_changed.iter() {
+        stats.record_position(changed);
+    }
+    for changed in axis_changed.iter() {
+        stats.record_axis(changed);
+    }
+    for blocked in blocked.iter() {
+        stats.record_blocked(blocked);
+    }
+}
+
+/// Advances the level timer. Only runs while the maze is the active state, so pausing stops
+/// it, and a minimized window, which reports a size of zero, stops it too.
+pub fn tick_elapsed(time: Res<Time>, windows: Option<Res<Windows>>, mut stats: ResMut<LevelStats>) {
+    let minimized = windows
+        .as_ref()
+        .and_then(|windows| windows.get_primary())
+        .is_some_and(|window| window.width() == 0.0 || window.height() == 0.0);
+    if !minimized {
+        stats.elapsed += time.delta();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_count_player_actions_only() {
+        let position = |cause| PositionChanged::jump([0, 0], [0, 0], cause);
+        let axis = |cause| AxisChanged {
+            from: [0, 1],
+            to: [0, 1],
+            cause,
+        };
+        let blocked = MoveBlocked {
+            position: [0, 0],
+            dimension: 0,
+            positive: false,
+            out_of_bounds: true,
+        };
+
+        let mut stats = LevelStats::default();
+        // Loading a level, two steps, a wall, a rotation and another step.
+        stats.record_position(&position(ChangeCause::Loaded));
+        stats.record_axis(&axis(ChangeCause::Loaded));
+        stats.record_position(&position(ChangeCause::Moved));
+        stats.record_position(&position(ChangeCause::Moved));
+        stats.record_blocked(&blocked);
+        stats.record_axis(&axis(ChangeCause::Rotated));
+        stats.record_position(&position(ChangeCause::Rotated));
+        stats.record_position(&position(ChangeCause::Moved));
+        // Undoing a rotation restores the axes too, but neither counts as a step or rotation.
+        stats.record_axis(&axis(ChangeCause::Undone));
+        stats.record_position(&position(ChangeCause::Undone));
+
+        assert_eq!(
+            stats,
+            LevelStats {
+                steps: 3,
+                blocked_moves: 1,
+                axis_rotations: 1,
+                undos_used: 1,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn elapsed_formats_as_minutes() {
+        assert_eq!(format_elapsed(Duration::from_millis(5_900)), "0:05");
+        assert_eq!(format_elapsed(Duration::from_secs(125)), "2:05");
+    }
+}
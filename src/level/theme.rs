@@ -0,0 +1,242 @@
+use std::{fs, path::Path};
+
+use bevy::{asset::LoadState, ecs::system::SystemParam, prelude::*};
+use serde::Deserialize;
+
+use super::{
+    bindings::{Action, ActionInput},
+    loader::MazeAssets,
+    palette::AxisPalette,
+};
+
+/// Where themes live under `assets/`, one `<name>.ron` file each.
+const THEME_DIR: &str = "themes";
+/// The theme used when no other is selected, built in if there's no file by this name.
+pub const DEFAULT_THEME: &str = "default";
+
+/// How the maze looks, as read from `assets/themes/<name>.ron`. Anything left out keeps its
+/// built-in default, like `(wall: Rgba(red: 0.2, green: 0.3, blue: 0.5, alpha: 1.0))`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MazeTheme {
+    pub wall_thickness: f32,
+    pub wall_height: f32,
+    pub joint_width: f32,
+    pub joint_height: f32,
+    pub floor_thickness: f32,
+    pub player_radius: f32,
+    pub wall: Color,
+    /// An image under `assets/` to draw the walls with, tinted by `wall`.
+    pub wall_texture: Option<String>,
+    pub floor: Color,
+    /// An image under `assets/` to draw the floor with, tinted by `floor`.
+    pub floor_texture: Option<String>,
+    pub start: Color,
+    pub goal: Color,
+    pub hint: Color,
+    pub bump: Color,
+    /// The glow of the hinted tile and of a wall the player walked into.
+    pub highlight: Color,
+    pub trail: Color,
+    pub player: Color,
+}
+
+impl Default for MazeTheme {
+    fn default() -> Self {
+        Self {
+            wall_thickness: 0.1,
+            wall_height: 0.6,
+            joint_width: 0.2,
+            joint_height: 1.0,
+            floor_thickness: 0.02,
+            player_radius: 0.3,
+            wall: Color::rgb(0.8, 0.7, 0.6),
+            wall_texture: None,
+            floor: Color::rgb(0.3, 0.3, 0.35),
+            floor_texture: None,
+            start: Color::rgb(0.4, 0.5, 0.8),
+            goal: Color::rgb(0.4, 0.8, 0.4),
+            hint: Color::rgb(0.9, 0.8, 0.3),
+            bump: Color::rgb(0.9, 0.4, 0.3),
+            highlight: Color::rgb(0.9, 0.7, 0.2),
+            trail: Color::rgb(0.6, 0.65, 0.9),
+            player: Color::rgb(0.5, 0.5, 0.8),
+        }
+    }
+}
+
+/// Which theme the maze is drawn with.
+pub struct ThemeSettings {
+    pub selected: String,
+}
+
+impl Default for ThemeSettings {
+    fn default() -> Self {
+        Self {
+            selected: DEFAULT_THEME.to_string(),
+        }
+    }
+}
+
+/// Textures requested by the current theme that haven't finished loading, with the
+/// material each one is drawn on.
+#[derive(Default)]
+pub struct PendingTextures {
+    pub textures: Vec<(Handle<StandardMaterial>, Handle<Image>)>,
+}
+
+fn parse_theme(text: &str) -> Result<MazeTheme, ron::Error> {
+    ron::from_str(text)
+}
+
+/// The theme called `name`, or the built-in one if its file is missing or can't be parsed.
+pub fn load_theme(name: &str) -> MazeTheme {
+    let path = Path::new("assets")
+        .join(THEME_DIR)
+        .join(format!("{}.ron", name));
+    match fs::read_to_string(&path) {
+        Ok(text) => parse_theme(&text).unwrap_or_else(|e| {
+            warn!("Ignoring {}: {}", path.display(), e);
+            MazeTheme::default()
+        }),
+        Err(e) => {
+            if name != DEFAULT_THEME {
+                warn!("Could not read {}: {}", path.display(), e);
+            }
+            MazeTheme::default()
+        }
+    }
+}
+
+/// The names of the themes in `assets/themes`, and the built-in one, sorted.
+fn theme_names() -> Vec<String> {
+    let mut names = vec![DEFAULT_THEME.to_string()];
+    if let Ok(entries) = fs::read_dir(Path::new("assets").join(THEME_DIR)) {
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension().is_some_and(|extension| extension == "ron") {
+                if let Some(stem) = path.file_stem() {
+                    names.push(stem.to_string_lossy().into_owned());
+                }
+            }
+        }
+    }
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// The theme after `current` in `names`, wrapping around.
+fn next_theme<'a>(names: &'a [String], current: &str) -> Option<&'a String> {
+    let index = names.iter().position(|name| name == current);
+    let next = index.map_or(0, |index| (index + 1) % names.len());
+    names.get(next)
+}
+
+/// The asset stores a theme is built into.
+#[derive(SystemParam)]
+pub struct ThemeTargets<'w, 's> {
+    server: Res<'w, AssetServer>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    materials: ResMut<'w, Assets<StandardMaterial>>,
+    pending: ResMut<'w, PendingTextures>,
+    #[system_param(ignore)]
+    _marker: std::marker::PhantomData<&'s ()>,
+}
+
+/// Switches to the next theme on the debug key, restyling the maze assets in place so
+/// everything already spawned picks it up.
+pub fn cycle_theme(
+    input: ActionInput,
+    mut settings: ResMut<ThemeSettings>,
+    mut theme: ResMut<MazeTheme>,
+    palette: Res<AxisPalette>,
+    assets: Res<MazeAssets>,
+    mut targets: ThemeTargets,
+) {
+    if !input.just_pressed(Action::NextTheme) {
+        return;
+    }
+    let names = theme_names();
+    if let Some(next) = next_theme(&names, &settings.selected) {
+        settings.selected = next.clone();
+    }
+    info!("Theme: {}", settings.selected);
+    *theme = load_theme(&settings.selected);
+    let ThemeTargets {
+        server,
+        meshes,
+        materials,
+        pending,
+        ..
+    } = &mut targets;
+    pending.textures = assets.restyle(&theme, palette.as_ref(), server, meshes, materials);
+}
+
+/// Draws materials whose texture failed to load in their flat color instead, since a
+/// material never draws while its texture is missing.
+pub fn drop_failed_textures(
+    server: Res<AssetServer>,
+    mut pending: ResMut<PendingTextures>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    pending
+        .textures
+        .retain(|(material, texture)| match server.get_load_state(texture) {
+            LoadState::Failed => {
+                warn!("Could not load a theme texture, drawing it in its flat color");
+                if let Some(material) = materials.get_mut(material) {
+                    material.base_color_texture = None;
+                }
+                false
+            }
+            LoadState::Loaded => false,
+            _ => true,
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn themes_override_only_what_they_list() {
+        let theme = parse_theme(
+            r#"(
+                wall_height: 1.2,
+                wall: Rgba(red: 0.1, green: 0.2, blue: 0.3, alpha: 1.0),
+                floor_texture: Some("textures/floor.png"),
+            )"#,
+        )
+        .unwrap();
+        assert_eq!(
+            theme,
+            MazeTheme {
+                wall_height: 1.2,
+                wall: Color::rgb(0.1, 0.2, 0.3),
+                floor_texture: Some("textures/floor.png".to_string()),
+                ..Default::default()
+            }
+        );
+        assert!(
+            parse_theme("(wall_colour: Rgba(red: 1.0, green: 1.0, blue: 1.0, alpha: 1.0))")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn bundled_themes_parse() {
+        parse_theme(include_str!("../../assets/themes/night.ron")).unwrap();
+    }
+
+    #[test]
+    fn themes_cycle_in_order() {
+        let names: Vec<String> = ["default", "night", "paper"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        assert_eq!(next_theme(&names, "default").unwrap(), "night");
+        assert_eq!(next_theme(&names, "paper").unwrap(), "default");
+        assert_eq!(next_theme(&names, "removed").unwrap(), "default");
+        assert!(next_theme(&[], "default").is_none());
+    }
+}
@@ -0,0 +1,471 @@
+use std::{collections::BTreeMap, fs, time::Duration};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    bindings::{Action, ActionInput},
+    demo::DemoMode,
+    loader::{LastLoad, LevelEntity, LoadLevel},
+    maze_level::MazeLevel,
+    save::{write_ron, SaveLocation},
+    stats::{format_elapsed, LevelStats},
+};
+use crate::AppState;
+
+const LEADERBOARD_FILE: &str = "leaderboard.ron";
+/// How many times the leaderboard keeps for each level.
+const LEADERBOARD_SIZE: usize = 5;
+/// Time left under which the countdown turns red.
+const WARNING_TIME: Duration = Duration::from_secs(10);
+
+/// How much time a time trial allows, read from the `time_trial` section of
+/// `assets/keybindings.ron`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeTrialSettings {
+    /// Time given for each step of the shortest path to the goal.
+    pub seconds_per_step: f32,
+}
+
+impl Default for TimeTrialSettings {
+    fn default() -> Self {
+        Self {
+            seconds_per_step: 2.0,
+        }
+    }
+}
+
+impl TimeTrialSettings {
+    /// The time allowed for a maze whose shortest path is `optimal_moves` long. Even a maze
+    /// solved without moving is given a step's worth, so the clock doesn't run out at once.
+    pub fn budget(&self, optimal_moves: usize) -> Duration {
+        let seconds = self.seconds_per_step.max(0.0) * optimal_moves.max(1) as f32;
+        Duration::try_from_secs_f32(seconds).unwrap_or(Duration::MAX)
+    }
+
+    /// The time allowed for the current level, or `None` if it isn't a time trial.
+    fn level_budget(&self, last_load: Option<&LastLoad>, level: &MazeLevel) -> Option<Duration> {
+        last_load
+            .filter(|last_load| last_load.0.time_trial)
+            .map(|_| self.budget(level.optimal_moves()))
+    }
+}
+
+/// Formats the time left, rounded up so the countdown only reads `0:00` once time is up.
+fn format_countdown(remaining: Duration) -> String {
+    let seconds = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+    format_elapsed(Duration::from_secs(seconds))
+}
+
+/// One completed time trial.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrialTime {
+    pub elapsed: Duration,
+    pub steps: u32,
+}
+
+/// The fastest time trials on each level, keyed by level code and persisted between launches.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Leaderboard {
+    times: BTreeMap<String, Vec<TrialTime>>,
+    /// Where the last time trial placed, for the completion screen.
+    #[serde(skip)]
+    latest: Option<usize>,
+}
+
+impl Leaderboard {
+    /// The times kept for `code`, fastest first.
+    pub fn times(&self, code: &str) -> &[TrialTime] {
+        self.times.get(code).map_or(&[], |times| times.as_slice())
+    }
+
+    /// Places `time` on the board for `code`, fastest first with fewer steps breaking ties,
+    /// and an earlier run ahead of an equal later one. Returns its place, or `None` if it
+    /// was too slow to be kept.
+    pub fn insert(&mut self, code: &str, time: TrialTime) -> Option<usize> {
+        let times = self.times.entry(code.to_string()).or_default();
+        let place =
+            times.partition_point(|kept| (kept.elapsed, kept.steps) <= (time.elapsed, time.steps));
+        if place >= LEADERBOARD_SIZE {
+            return None;
+        }
+        times.insert(place, time);
+        times.truncate(LEADERBOARD_SIZE);
+        Some(place)
+    }
+
+    /// The completion screen's leaderboard lines for `code`.
+    pub fn lines(&self, code: &str) -> Vec<String> {
+        leaderboard_lines(self.times(code), self.latest)
+    }
+}
+
+/// The leaderboard lines of the completion screen, marking where the run just finished placed.
+fn leaderboard_lines(times: &[TrialTime], latest: Option<usize>) -> Vec<String> {
+    let mut lines = vec![match latest {
+        Some(0) => "New record!".to_string(),
+        Some(place) => format!("#{} on the leaderboard", place + 1),
+        None => "Too slow for the leaderboard".to_string(),
+    }];
+    for (place, time) in times.iter().enumerate() {
+        let marker = if latest == Some(place) { " <" } else { "" };
+        lines.push(format!(
+            "{}. {} in {} steps{}",
+            place + 1,
+            format_elapsed(time.elapsed),
+            time.steps,
+            marker
+        ));
+    }
+    lines
+}
+
+pub fn load_leaderboard(mut c: Commands, location: Res<SaveLocation>) {
+    let leaderboard = location
+        .sibling(LEADERBOARD_FILE)
+        .and_then(|path| {
+            let text = fs::read_to_string(&path).ok()?;
+            ron::from_str(&text)
+                .map_err(|e| warn!("Ignoring the leaderboard at {}: {}", path.display(), e))
+                .ok()
+        })
+        .unwrap_or_default();
+    c.insert_resource::<Leaderboard>(leaderboard);
+}
+
+/// Puts a completed time trial on the leaderboard before the completion screen is drawn from
+/// it. A level without a code, loaded from a file, has no board to go on.
+pub fn record_time_trial(
+    mut leaderboard: ResMut<Leaderboard>,
+    last_load: Option<Res<LastLoad>>,
+    stats: Res<LevelStats>,
+    location: Res<SaveLocation>,
+    demo: Res<DemoMode>,
+) {
+    leaderboard.latest = None;
+    let code = match last_load.filter(|last_load| last_load.0.time_trial) {
+        Some(last_load) if !demo.is_running() => last_load.code(),
+        _ => None,
+    };
+    let code = match code {
+        Some(code) => code,
+        None => return,
+    };
+    let time = TrialTime {
+        elapsed: stats.elapsed,
+        steps: stats.steps,
+    };
+    leaderboard.latest = leaderboard.insert(&code, time);
+    if let Some(path) = location.sibling(LEADERBOARD_FILE) {
+        if let Err(e) = write_ron(&path, &*leaderboard) {
+            error!("Could not save the leaderboard: {}", e);
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Countdown;
+
+pub fn spawn_countdown(
+    mut c: Commands,
+    settings: Res<TimeTrialSettings>,
+    level: Res<MazeLevel>,
+    last_load: Option<Res<LastLoad>>,
+    assets: Res<AssetServer>,
+) {
+    let budget = match settings.level_budget(last_load.as_deref(), &level) {
+        Some(budget) => budget,
+        None => return,
+    };
+    c.spawn_bundle(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                top: Val::Px(10.0),
+                ..default()
+            },
+            size: Size::new(Val::Percent(100.0), Val::Auto),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    })
+    .insert(LevelEntity)
+    .insert(Name::new("countdown"))
+    .with_children(|c| {
+        c.spawn_bundle(TextBundle {
+            text: Text::with_section(
+                format_countdown(budget),
+                TextStyle {
+                    font: assets.load("fonts/UnicaOne-Regular.ttf"),
+                    font_size: 72.0,
+                    color: Color::WHITE,
+                },
+                Default::default(),
+            ),
+            ..default()
+        })
+        .insert(Countdown);
+    });
+}
+
+/// Counts down from the level's budget. The time left is worked out from the elapsed time,
+/// a sum of whole frame durations, rather than ticked down on its own, so it can't drift
+/// from the timer the run is judged by.
+pub fn update_countdown(
+    settings: Res<TimeTrialSettings>,
+    level: Res<MazeLevel>,
+    last_load: Option<Res<LastLoad>>,
+    stats: Res<LevelStats>,
+    mut texts: Query<&mut Text, With<Countdown>>,
+) {
+    if !stats.is_changed() {
+        return;
+    }
+    let budget = match settings.level_budget(last_load.as_deref(), &level) {
+        Some(budget) => budget,
+        None => return,
+    };
+    let remaining = budget.saturating_sub(stats.elapsed);
+    let value = format_countdown(remaining);
+    let color = if remaining < WARNING_TIME {
+        Color::TOMATO
+    } else {
+        Color::WHITE
+    };
+    for mut text in texts.iter_mut() {
+        if let Some(section) = text.sections.first_mut() {
+            if section.value != value || section.style.color != color {
+                section.value = value.clone();
+                section.style.color = color;
+            }
+        }
+    }
+}
+
+/// Fails a time trial once its budget is spent, unless the goal was reached the same frame.
+pub fn run_out_of_time(
+    settings: Res<TimeTrialSettings>,
+    level: Res<MazeLevel>,
+    last_load: Option<Res<LastLoad>>,
+    stats: Res<LevelStats>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    let budget = match settings.level_budget(last_load.as_deref(), &level) {
+        Some(budget) => budget,
+        None => return,
+    };
+    if stats.elapsed < budget || level.at_goal() {
+        return;
+    }
+    // Pushed over InMaze like completing it, which stops the timer and movement.
+    if let Err(e) = app_state.push(AppState::Failed) {
+        error!("Could not end the time trial: {}", e);
+    }
+}
+
+#[derive(Component)]
+pub struct FailedScreen;
+
+#[derive(Component, Clone, Copy)]
+pub enum FailedButton {
+    Retry,
+    Menu,
+}
+
+pub fn spawn_failed_screen(
+    mut c: Commands,
+    assets: Res<AssetServer>,
+    level: Res<MazeLevel>,
+    stats: Res<LevelStats>,
+) {
+    let style = TextStyle {
+        font: assets.load("fonts/UnicaOne-Regular.ttf"),
+        font_size: 40.0,
+        color: Color::WHITE,
+    };
+    let text = |c: &mut ChildBuilder, value: String| {
+        c.spawn_bundle(TextBundle {
+            text: Text::with_section(value, style.clone(), Default::default()),
+            style: Style {
+                margin: Rect::all(Val::Px(5.0)),
+                ..default()
+            },
+            ..default()
+        });
+    };
+    c.spawn_bundle(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            flex_direction: FlexDirection::ColumnReverse,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+        ..default()
+    })
+    .insert(FailedScreen)
+    .insert(Name::new("failed screen"))
+    .with_children(|c| {
+        text(c, "Out of time".to_string());
+        text(
+            c,
+            format!("{} steps, optimal {}", stats.steps, level.optimal_moves()),
+        );
+        for (label, button) in [
+            ("Retry", FailedButton::Retry),
+            ("Return to menu", FailedButton::Menu),
+        ] {
+            c.spawn_bundle(ButtonBundle {
+                style: Style {
+                    padding: Rect::all(Val::Px(5.0)),
+                    margin: Rect::all(Val::Px(5.0)),
+                    ..default()
+                },
+                color: Color::DARK_GRAY.into(),
+                ..default()
+            })
+            .insert(button)
+            .with_children(|c| {
+                c.spawn_bundle(TextBundle {
+                    text: Text::with_section(label, style.clone(), Default::default()),
+                    ..default()
+                });
+            });
+        }
+    });
+}
+
+pub fn despawn_failed_screen(mut c: Commands, screens: Query<Entity, With<FailedScreen>>) {
+    for screen in screens.iter() {
+        c.entity(screen).despawn_recursive();
+    }
+}
+
+pub fn failed_input(
+    input: ActionInput,
+    last_load: Option<Res<LastLoad>>,
+    mut app_state: ResMut<State<AppState>>,
+    mut load_level: EventWriter<LoadLevel>,
+    buttons: Query<(&Interaction, &FailedButton), Changed<Interaction>>,
+) {
+    let clicked = buttons
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Clicked)
+        .map(|(_, button)| *button);
+    // Confirming retries, as another go is what a failed trial usually wants.
+    let pressed = if input.just_pressed(Action::Confirm) {
+        Some(FailedButton::Retry)
+    } else {
+        clicked
+    };
+
+    match pressed {
+        // The same seed again, unwinding the state stack into a fresh run.
+        Some(FailedButton::Retry) => {
+            if let Some(last_load) = last_load {
+                load_level.send(last_load.restart());
+            }
+        }
+        Some(FailedButton::Menu) => {
+            if let Err(e) = app_state.replace(AppState::MainMenu) {
+                error!("Could not return to the menu: {}", e);
+            }
+        }
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trial(seconds: u64, steps: u32) -> TrialTime {
+        TrialTime {
+            elapsed: Duration::from_secs(seconds),
+            steps,
+        }
+    }
+
+    #[test]
+    fn budget_scales_with_the_optimal_path() {
+        let settings = TimeTrialSettings::default();
+        assert_eq!(settings.budget(30), Duration::from_secs(60));
+        assert_eq!(settings.budget(0), Duration::from_secs(2));
+        let settings = TimeTrialSettings {
+            seconds_per_step: 1.5,
+        };
+        assert_eq!(settings.budget(7), Duration::from_millis(10_500));
+        let settings = TimeTrialSettings {
+            seconds_per_step: -1.0,
+        };
+        assert_eq!(settings.budget(7), Duration::ZERO);
+        let settings = TimeTrialSettings {
+            seconds_per_step: f32::INFINITY,
+        };
+        assert_eq!(settings.budget(7), Duration::MAX);
+    }
+
+    #[test]
+    fn countdown_rounds_up() {
+        assert_eq!(format_countdown(Duration::from_secs(60)), "1:00");
+        assert_eq!(format_countdown(Duration::from_millis(59_001)), "1:00");
+        assert_eq!(format_countdown(Duration::from_millis(1)), "0:01");
+        assert_eq!(format_countdown(Duration::ZERO), "0:00");
+    }
+
+    #[test]
+    fn leaderboard_keeps_the_fastest_times_in_order() {
+        let mut leaderboard = Leaderboard::default();
+        assert_eq!(leaderboard.insert("A", trial(40, 20)), Some(0));
+        assert_eq!(leaderboard.insert("A", trial(30, 25)), Some(0));
+        assert_eq!(leaderboard.insert("A", trial(35, 20)), Some(1));
+        // A tie on time goes to fewer steps, and an exact tie to the earlier run.
+        assert_eq!(leaderboard.insert("A", trial(35, 18)), Some(1));
+        assert_eq!(leaderboard.insert("A", trial(35, 20)), Some(3));
+        assert_eq!(leaderboard.insert("B", trial(90, 50)), Some(0));
+        assert_eq!(
+            leaderboard.times("A"),
+            [
+                trial(30, 25),
+                trial(35, 18),
+                trial(35, 20),
+                trial(35, 20),
+                trial(40, 20)
+            ]
+        );
+
+        // A full board drops its slowest time for a faster one, and turns away slower ones.
+        assert_eq!(leaderboard.insert("A", trial(41, 1)), None);
+        assert_eq!(leaderboard.insert("A", trial(10, 30)), Some(0));
+        assert_eq!(leaderboard.times("A").len(), LEADERBOARD_SIZE);
+        assert_eq!(leaderboard.times("A").last(), Some(&trial(35, 20)));
+        assert_eq!(leaderboard.times("B"), [trial(90, 50)]);
+        assert!(leaderboard.times("C").is_empty());
+    }
+
+    #[test]
+    fn leaderboard_lines_mark_the_latest_run() {
+        let times = [trial(30, 25), trial(35, 18)];
+        assert_eq!(
+            leaderboard_lines(&times, Some(0)),
+            [
+                "New record!",
+                "1. 0:30 in 25 steps <",
+                "2. 0:35 in 18 steps"
+            ]
+        );
+        assert_eq!(
+            leaderboard_lines(&times, Some(1))[0],
+            "#2 on the leaderboard"
+        );
+        assert_eq!(
+            leaderboard_lines(&times, None)[0],
+            "Too slow for the leaderboard"
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use bevy::{pbr::NotShadowCaster, prelude::*};
+
+use super::{
+    bindings::{Action, ActionInput},
+    loader::MazeAssets,
+    maze_level::{AxisChanged, MazeLevel, PositionChanged},
+    maze_renderer::{cell_center, MazeRenderer, FLOOR_HEIGHT},
+};
+
+/// Most trail markers spawned at once, past which the oldest cells are recycled.
+const MAX_TRAIL_MARKERS: usize = 500;
+
+/// Whether the breadcrumb trail is drawn.
+pub struct TrailSettings {
+    pub shown: bool,
+}
+
+impl Default for TrailSettings {
+    fn default() -> Self {
+        Self { shown: true }
+    }
+}
+
+/// The trail markers spawned under a maze root, keyed by the slice cell they sit on.
+#[derive(Component, Default)]
+pub struct TrailMarkers {
+    markers: HashMap<[u16; 2], Entity>,
+}
+
+/// The most recently entered cells of the trail in the displayed slice, oldest first.
+fn trail_cells(level: &MazeLevel, settings: &TrailSettings) -> Vec<[u16; 2]> {
+    if !settings.shown {
+        return Vec::new();
+    }
+    let mut cells = level.trail_in_current();
+    let skip = cells.len().saturating_sub(MAX_TRAIL_MARKERS);
+    cells.drain(..skip);
+    cells
+}
+
+fn marker_transform(cell: [u16; 2]) -> Transform {
+    // Above the start and goal tiles, so the trail still shows over them.
+    Transform::from_translation(cell_center(cell) + Vec3::Y * (FLOOR_HEIGHT + 0.015))
+}
+
+pub fn toggle_trail(input: ActionInput, mut settings: ResMut<TrailSettings>) {
+    if input.just_pressed(Action::ToggleTrail) {
+        settings.shown = !settings.shown;
+    }
+}
+
+/// Keeps one marker on each trail cell of the displayed slice, moving markers whose cell
+/// dropped out of the trail before spawning any new ones.
+pub fn update_trail(
+    mut c: Commands,
+    level: Res<MazeLevel>,
+    assets: Res<MazeAssets>,
+    settings: Res<TrailSettings>,
+    mut roots: Query<(Entity, &mut TrailMarkers), With<MazeRenderer>>,
+    mut position_changed: EventReader<PositionChanged>,
+    mut axis_changed: EventReader<AxisChanged>,
+) {
+    if axis_changed.iter().count() + position_changed.iter().count() == 0 && !settings.is_changed()
+    {
+        return;
+    }
+    let cells = trail_cells(&level, &settings);
+
+    for (root, mut trail) in roots.iter_mut() {
+        let mut spare: Vec<Entity> = Vec::new();
+        trail.markers.retain(|cell, marker| {
+            let keep = cells.contains(cell);
+            if !keep {
+                spare.push(*marker);
+            }
+            keep
+        });
+        for cell in &cells {
+            if trail.markers.contains_key(cell) {
+                continue;
+            }
+            let marker = match spare.pop() {
+                Some(marker) => {
+                    c.entity(marker).insert(marker_transform(*cell));
+                    marker
+                }
+                None => {
+                    let marker = c
+                        .spawn_bundle(assets.trail(marker_transform(*cell)))
+                        .insert(Name::new("trail"))
+                        .insert(NotShadowCaster)
+                        .id();
+                    c.entity(root).add_child(marker);
+                    marker
+                }
+            };
+            trail.markers.insert(*cell, marker);
+        }
+        for marker in spare {
+            c.entity(marker).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn trail_cells_are_capped_and_toggleable() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let mut level = MazeLevel::from_maze(maze::Maze::new(&[30, 30, 2], &mut rng));
+        for x in 0..30 {
+            for y in 0..30 {
+                level.restore(&[x, y, 0], [0, 1]).unwrap();
+            }
+        }
+        // Going back over the trail, or into another slice, adds nothing to this one.
+        level.restore(&[0, 0, 0], [0, 1]).unwrap();
+        level.restore(&[0, 0, 1], [0, 1]).unwrap();
+        level.restore(&[0, 0, 0], [0, 1]).unwrap();
+
+        let settings = TrailSettings::default();
+        let cells = trail_cells(&level, &settings);
+        assert_eq!(level.trail_in_current().len(), 900);
+        assert_eq!(cells.len(), MAX_TRAIL_MARKERS);
+        assert_eq!(cells.first(), Some(&[13, 10]));
+        assert_eq!(cells.last(), Some(&[29, 29]));
+        assert!(trail_cells(&level, &TrailSettings { shown: false }).is_empty());
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use super::maze_level::{AxisChanged, ChangeCause, MazeView, PositionChanged};
+
+/// Where the player was and which dimensions were displayed, from before a move.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    pub position: Vec<u16>,
+    pub axis: [u8; 2],
+}
+
+impl Snapshot {
+    pub fn of(level: &dyn MazeView) -> Self {
+        Self {
+            position: level.dims().to_vec(),
+            axis: level.axis(),
+        }
+    }
+}
+
+/// The moves that can be undone in the current level, most recent last.
+pub struct MoveHistory {
+    entries: VecDeque<Snapshot>,
+    /// Most moves kept, past which the oldest is forgotten.
+    pub depth: usize,
+    /// Whether changing the displayed dimensions can be undone too, and not only steps.
+    pub undo_rotations: bool,
+}
+
+impl Default for MoveHistory {
+    fn default() -> Self {
+        Self {
+            entries: VecDeque::new(),
+            depth: 64,
+            undo_rotations: false,
+        }
+    }
+}
+
+impl MoveHistory {
+    pub fn push(&mut self, snapshot: Snapshot) {
+        if self.depth == 0 {
+            return;
+        }
+        while self.entries.len() >= self.depth {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(snapshot);
+    }
+
+    pub fn pop(&mut self) -> Option<Snapshot> {
+        self.entries.pop_back()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Restores the most recent snapshot, returning the events describing the change, or `None`
+/// if there is nothing to undo.
+pub fn undo(
+    level: &mut dyn MazeView,
+    history: &mut MoveHistory,
+) -> Option<(PositionChanged, Option<AxisChanged>)> {
+    let snapshot = history.pop()?;
+    let (from, from_axis) = (level.pos(), level.axis());
+    level
+        .restore(&snapshot.position, snapshot.axis)
+        .expect("snapshots are taken from the level they're restored onto");
+    let axis_changed = (from_axis != level.axis()).then(|| AxisChanged {
+        from: from_axis,
+        to: level.axis(),
+        cause: ChangeCause::Undone,
+    });
+    let position_changed = PositionChanged::jump(from, level.pos(), ChangeCause::Undone);
+    Some((position_changed, axis_changed))
+}
+
+pub fn clear_history(mut history: ResMut<MoveHistory>) {
+    history.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::maze_level::{Axis, Direction, MazeLevel};
+    use rand::prelude::*;
+
+    fn snapshot(x: u16) -> Snapshot {
+        Snapshot {
+            position: vec![x, 0],
+            axis: [0, 1],
+        }
+    }
+
+    #[test]
+    fn history_forgets_the_oldest_past_its_depth() {
+        let mut history = MoveHistory {
+            depth: 2,
+            ..Default::default()
+        };
+        history.push(snapshot(0));
+        history.push(snapshot(1));
+        history.push(snapshot(2));
+        assert_eq!(history.pop(), Some(snapshot(2)));
+        assert_eq!(history.pop(), Some(snapshot(1)));
+        assert_eq!(history.pop(), None);
+
+        let mut disabled = MoveHistory {
+            depth: 0,
+            ..Default::default()
+        };
+        disabled.push(snapshot(0));
+        assert_eq!(disabled.pop(), None);
+    }
+
+    #[test]
+    fn undo_restores_position_and_axes() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let mut level = MazeLevel::from_maze(maze::Maze::new(&[5, 1, 1], &mut rng));
+        let mut history = MoveHistory::default();
+        assert!(undo(&mut *level, &mut history).is_none());
+
+        history.push(Snapshot::of(&*level));
+        assert!(level.move_pos(Axis::X, Direction::Positive));
+        history.push(Snapshot::of(&*level));
+        level.shift_axis(Axis::Y, Direction::Positive);
+        assert_eq!(level.axis(), [0, 2]);
+
+        let (position, axis) = undo(&mut *level, &mut history).unwrap();
+        assert_eq!(level.dims(), &[1, 0, 0]);
+        // The player was on the first cell of the rotated slice, shown along dimension 2.
+        assert_eq!(
+            position,
+            PositionChanged::jump([1, 0], [1, 0], ChangeCause::Undone)
+        );
+        assert_eq!(
+            axis,
+            Some(AxisChanged {
+                from: [0, 2],
+                to: [0, 1],
+                cause: ChangeCause::Undone
+            })
+        );
+
+        let (position, axis) = undo(&mut *level, &mut history).unwrap();
+        assert_eq!(level.dims(), &[0, 0, 0]);
+        assert_eq!((position.from, position.to), ([1, 0], [0, 0]));
+        assert_eq!((position.dimension, position.positive), (None, None));
+        assert!(axis.is_none());
+        assert!(undo(&mut *level, &mut history).is_none());
+    }
+}
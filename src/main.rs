@@ -1,46 +1,104 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+// Bevy 0.7's `#[derive(Bundle)]` forgets each field after moving it out, which trips this lint.
+#![allow(clippy::forget_non_drop)]
 
+mod clipboard;
 mod level;
-mod maze;
+mod menu;
+mod toast;
 
 use bevy::prelude::*;
+use level::{Difficulty, LoadLevel, RngSource};
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum AppState {
     MainMenu,
+    Loading,
     InMaze,
     Paused,
+    Completed,
+    Failed,
+}
+
+/// A level to start in straight away, skipping the main menu.
+struct StartingLevel(Option<LoadLevel>);
+
+/// Reads `--difficulty <preset or lengths>` off the command line.
+fn difficulty_flag(mut args: impl Iterator<Item = String>) -> Result<Option<Difficulty>, String> {
+    let mut difficulty = None;
+    while let Some(arg) = args.next() {
+        let value = match arg.strip_prefix("--difficulty") {
+            Some("") => args.next().ok_or("--difficulty needs a value")?,
+            Some(value) if value.starts_with('=') => value[1..].to_string(),
+            _ => return Err(format!("unknown argument '{}'", arg)),
+        };
+        difficulty = Some(value.parse::<Difficulty>().map_err(|e| e.to_string())?);
+    }
+    Ok(difficulty)
 }
 
 fn main() {
+    let difficulty = match difficulty_flag(std::env::args().skip(1)) {
+        Ok(difficulty) => difficulty,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
     App::new()
+        .insert_resource(StartingLevel(difficulty.map(|difficulty| {
+            LoadLevel::from_difficulty(difficulty, RngSource::Entropy)
+        })))
         .add_state(AppState::MainMenu)
         .add_plugins(DefaultPlugins)
+        .add_plugin(toast::ToastPlugin)
         .add_plugin(level::LevelPlugin)
+        .add_plugin(menu::MenuPlugin)
         .add_startup_system(setup)
+        .add_startup_system(load_starting_level)
         .run();
 }
 
-fn setup(mut c: Commands, mut maze_spawner: EventWriter<level::LoadLevel>) {
-    c.spawn_bundle(OrthographicCameraBundle::new_2d());
-    c.spawn_bundle(PointLightBundle {
-        point_light: PointLight {
-            intensity: 1500.0,
-            shadows_enabled: true,
-            ..Default::default()
-        },
-        transform: Transform::from_xyz(4.0, 8.0, 4.0),
-        ..Default::default()
-    });
+fn load_starting_level(
+    mut starting: ResMut<StartingLevel>,
+    mut load_level: EventWriter<LoadLevel>,
+) {
+    if let Some(level) = starting.0.take() {
+        load_level.send(level);
+    }
+}
 
+fn setup(mut c: Commands) {
     c.spawn_bundle(PerspectiveCameraBundle {
         transform: Transform::from_xyz(-6.0, 10.0, -4.0)
             .looking_at(Vec3::new(2.0, 0.0, 2.0), Vec3::Y),
         ..Default::default()
-    });
+    })
+    .insert(level::OrbitCamera::default());
     c.spawn_bundle(UiCameraBundle::default());
-    maze_spawner.send(level::LoadLevel {
-        dimensions: level::DimensionLength::Three([4, 15, 2]),
-        ..Default::default()
-    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flag(args: &[&str]) -> Result<Option<Difficulty>, String> {
+        difficulty_flag(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn difficulty_flag_takes_a_preset_or_lengths() {
+        assert!(matches!(flag(&[]), Ok(None)));
+        assert!(matches!(
+            flag(&["--difficulty", "nightmare"]),
+            Ok(Some(Difficulty::Nightmare))
+        ));
+        assert!(matches!(
+            flag(&["--difficulty=3x3"]),
+            Ok(Some(Difficulty::Custom(_)))
+        ));
+        assert!(flag(&["--difficulty"]).is_err());
+        assert!(flag(&["--difficulty", "impossible"]).is_err());
+        assert!(flag(&["--fullscreen"]).is_err());
+    }
 }
@@ -0,0 +1,662 @@
+use std::{fmt, marker::PhantomData};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use rand::random;
+
+use crate::{
+    clipboard,
+    level::{
+        Campaign, ContinueGame, Difficulty, DimensionLength, LevelCode, LevelCodeError,
+        LoadEstimate, LoadLevel, LoadLimits, RngSource, SavedGame, StartCampaign,
+    },
+    toast::{ShowToast, ToastKind},
+    AppState,
+};
+
+/// Fewest and most dimensions the menu will start a maze with.
+const MIN_DIMENSIONS: usize = 2;
+const MAX_DIMENSIONS: usize = 6;
+/// Length given to an axis when the dimension count is raised.
+const NEW_AXIS_LENGTH: u16 = 4;
+/// Longest seed that can be typed, which is the length of `u64::MAX`.
+const MAX_SEED_DIGITS: usize = 20;
+/// Longest level code that can be typed, with room for dashes between groups.
+const MAX_CODE_CHARS: usize = 64;
+
+/// Settings chosen in the main menu, kept around so they're still there after a maze.
+pub struct MenuSettings {
+    pub lengths: Vec<u16>,
+    /// The preset `lengths` came from, until they're changed by hand.
+    pub preset: Option<Difficulty>,
+    /// The typed seed, or empty to draw one from entropy.
+    pub seed: String,
+    /// A typed or pasted level code, loaded instead of the settings above.
+    pub code: String,
+    /// The text field typing goes into.
+    pub editing: MenuField,
+    /// Whether mazes started here are played against the clock.
+    pub time_trial: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuField {
+    Seed,
+    Code,
+}
+
+impl Default for MenuSettings {
+    fn default() -> Self {
+        Self {
+            lengths: vec![4, 15, 2],
+            preset: None,
+            seed: String::new(),
+            code: String::new(),
+            editing: MenuField::Seed,
+            time_trial: false,
+        }
+    }
+}
+
+/// Why the menu settings can't start a maze yet.
+#[derive(Debug, PartialEq, Eq)]
+pub enum MenuError {
+    DimensionCount(usize),
+    ZeroLength { axis: usize },
+    InvalidSeed,
+}
+
+impl fmt::Display for MenuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MenuError::DimensionCount(count) => write!(
+                f,
+                "a maze needs {} to {} dimensions, not {}",
+                MIN_DIMENSIONS, MAX_DIMENSIONS, count
+            ),
+            MenuError::ZeroLength { axis } => write!(f, "axis {} needs a length", axis + 1),
+            MenuError::InvalidSeed => write!(f, "the seed must be a number below 2^64"),
+        }
+    }
+}
+
+impl MenuSettings {
+    /// The load request these settings describe.
+    pub fn load_level(&self) -> Result<LoadLevel, MenuError> {
+        if let Some(axis) = self.lengths.iter().position(|length| *length == 0) {
+            return Err(MenuError::ZeroLength { axis });
+        }
+        let dimensions = DimensionLength::from_lengths(&self.lengths)
+            .ok_or(MenuError::DimensionCount(self.lengths.len()))?;
+        let rng_source = if self.seed.is_empty() {
+            RngSource::Entropy
+        } else {
+            RngSource::Seeded(self.seed.parse().map_err(|_| MenuError::InvalidSeed)?)
+        };
+        let difficulty = match &self.preset {
+            Some(preset) => preset.clone(),
+            None => Difficulty::Custom(dimensions),
+        };
+        Ok(LoadLevel {
+            time_trial: self.time_trial,
+            ..LoadLevel::from_difficulty(difficulty, rng_source)
+        })
+    }
+
+    fn select_preset(&mut self, preset: Difficulty) {
+        self.lengths = preset.to_dimensions().lengths().to_vec();
+        self.preset = Some(preset);
+    }
+
+    /// The level the typed code describes, or `None` while no code has been typed.
+    pub fn code_level(&self) -> Option<Result<LoadLevel, LevelCodeError>> {
+        if self.code.is_empty() {
+            return None;
+        }
+        Some(LevelCode::decode(&self.code).map(|level| LoadLevel {
+            time_trial: self.time_trial,
+            ..level
+        }))
+    }
+
+    /// Types a character into the field being edited, dropping any that don't belong there.
+    fn type_char(&mut self, typed: char) {
+        match self.editing {
+            MenuField::Seed => {
+                if typed.is_ascii_digit() && self.seed.len() < MAX_SEED_DIGITS {
+                    self.seed.push(typed);
+                }
+            }
+            MenuField::Code => {
+                if (typed.is_ascii_alphanumeric() || typed == '-')
+                    && self.code.len() < MAX_CODE_CHARS
+                {
+                    self.code.push(typed.to_ascii_uppercase());
+                }
+            }
+        }
+    }
+
+    fn backspace(&mut self) {
+        match self.editing {
+            MenuField::Seed => self.seed.pop(),
+            MenuField::Code => self.code.pop(),
+        };
+    }
+
+    fn set_dimension_count(&mut self, count: usize) {
+        self.preset = None;
+        self.lengths
+            .resize(count.clamp(MIN_DIMENSIONS, MAX_DIMENSIONS), NEW_AXIS_LENGTH);
+    }
+}
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MenuSettings>()
+            .add_system(sync_menu)
+            .add_system_set(
+                SystemSet::on_update(AppState::MainMenu)
+                    .with_system(menu_buttons)
+                    .with_system(type_text),
+            );
+    }
+}
+
+#[derive(Component)]
+struct MainMenu;
+
+#[derive(Component, Clone, Copy)]
+enum MenuButton {
+    /// An index into `Difficulty::PRESETS`.
+    Preset(usize),
+    FewerDimensions,
+    MoreDimensions,
+    Shorter(usize),
+    Longer(usize),
+    RandomizeSeed,
+    ClearSeed,
+    Edit(MenuField),
+    ToggleTimeTrial,
+    PasteCode,
+    ClearCode,
+    LoadCode,
+    Start,
+    Continue,
+    Campaign,
+}
+
+/// What the menu shows besides its own settings.
+#[derive(SystemParam)]
+struct MenuSources<'w, 's> {
+    saved: Option<Res<'w, SavedGame>>,
+    campaign: Option<Res<'w, Campaign>>,
+    limits: Option<Res<'w, LoadLimits>>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+impl<'w, 's> MenuSources<'w, 's> {
+    fn is_changed(&self) -> bool {
+        self.saved.as_ref().is_some_and(|saved| saved.is_changed())
+            || self
+                .campaign
+                .as_ref()
+                .is_some_and(|campaign| campaign.is_changed())
+            || self
+                .limits
+                .as_ref()
+                .is_some_and(|limits| limits.is_changed())
+    }
+}
+
+/// Keeps the menu spawned exactly while the main menu is the active state, rebuilding it
+/// whenever the settings, the saved game, the campaign or the load limits change.
+fn sync_menu(
+    mut c: Commands,
+    state: Res<State<AppState>>,
+    settings: Res<MenuSettings>,
+    sources: MenuSources,
+    assets: Res<AssetServer>,
+    menus: Query<Entity, With<MainMenu>>,
+) {
+    let active = *state.current() == AppState::MainMenu;
+    let spawned = !menus.is_empty();
+    let changed = settings.is_changed() || sources.is_changed();
+    if spawned && (!active || changed) {
+        for menu in menus.iter() {
+            c.entity(menu).despawn_recursive();
+        }
+    }
+    if active && (!spawned || changed) {
+        let can_continue = sources.saved.is_some_and(|saved| saved.0.is_some());
+        let campaign = sources
+            .campaign
+            .and_then(|campaign| campaign_label(&campaign));
+        let limits = sources.limits.as_deref().cloned().unwrap_or_default();
+        let estimate = load_estimate(&settings.lengths, &limits);
+        spawn_menu(&mut c, &settings, can_continue, campaign, estimate, &assets);
+    }
+}
+
+fn spawn_menu(
+    c: &mut Commands,
+    settings: &MenuSettings,
+    can_continue: bool,
+    campaign: Option<String>,
+    estimate: Result<String, String>,
+    assets: &AssetServer,
+) {
+    let style = TextStyle {
+        font: assets.load("fonts/UnicaOne-Regular.ttf"),
+        font_size: 40.0,
+        color: Color::WHITE,
+    };
+    let text = |c: &mut ChildBuilder, value: String, color: Color| {
+        c.spawn_bundle(TextBundle {
+            text: Text::with_section(
+                value,
+                TextStyle {
+                    color,
+                    ..style.clone()
+                },
+                Default::default(),
+            ),
+            style: Style {
+                margin: Rect::all(Val::Px(5.0)),
+                ..default()
+            },
+            ..default()
+        });
+    };
+    let button = |c: &mut ChildBuilder, label: &str, button: MenuButton| {
+        c.spawn_bundle(ButtonBundle {
+            style: Style {
+                padding: Rect::all(Val::Px(5.0)),
+                margin: Rect::all(Val::Px(5.0)),
+                ..default()
+            },
+            color: Color::DARK_GRAY.into(),
+            ..default()
+        })
+        .insert(button)
+        .with_children(|c| text(c, label.to_string(), Color::WHITE));
+    };
+    let row = |c: &mut ChildBuilder, spawn: &dyn Fn(&mut ChildBuilder)| {
+        c.spawn_bundle(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|c| spawn(c));
+    };
+
+    c.spawn_bundle(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            // Columns fill from the bottom up.
+            flex_direction: FlexDirection::ColumnReverse,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    })
+    .insert(MainMenu)
+    .insert(Name::new("main menu"))
+    .with_children(|c| {
+        text(c, "nothing moves".to_string(), Color::WHITE);
+        if can_continue {
+            button(c, "Continue", MenuButton::Continue);
+        }
+        if let Some(label) = &campaign {
+            button(c, label, MenuButton::Campaign);
+        }
+        row(c, &|c| {
+            let difficulty = match &settings.preset {
+                Some(preset) => preset.to_string(),
+                None => "custom".to_string(),
+            };
+            text(c, format!("difficulty: {}", difficulty), Color::WHITE);
+            for (index, preset) in Difficulty::PRESETS.iter().enumerate() {
+                button(c, &preset.to_string(), MenuButton::Preset(index));
+            }
+        });
+        row(c, &|c| {
+            text(
+                c,
+                format!("{} dimensions", settings.lengths.len()),
+                Color::WHITE,
+            );
+            button(c, "-", MenuButton::FewerDimensions);
+            button(c, "+", MenuButton::MoreDimensions);
+        });
+        for (axis, length) in settings.lengths.iter().enumerate() {
+            row(c, &|c| {
+                text(c, format!("axis {}: {}", axis + 1, length), Color::WHITE);
+                button(c, "-", MenuButton::Shorter(axis));
+                button(c, "+", MenuButton::Longer(axis));
+            });
+        }
+        match &estimate {
+            Ok(estimate) => text(c, estimate.clone(), Color::GRAY),
+            Err(error) => text(c, error.clone(), Color::TOMATO),
+        }
+        row(c, &|c| {
+            let seed = if settings.seed.is_empty() {
+                "random".to_string()
+            } else {
+                settings.seed.clone()
+            };
+            text(
+                c,
+                format!("seed: {}{}", seed, caret(settings, MenuField::Seed)),
+                Color::WHITE,
+            );
+            if settings.editing != MenuField::Seed {
+                button(c, "edit", MenuButton::Edit(MenuField::Seed));
+            }
+            button(c, "randomize", MenuButton::RandomizeSeed);
+            button(c, "clear", MenuButton::ClearSeed);
+        });
+        row(c, &|c| {
+            let mode = if settings.time_trial { "on" } else { "off" };
+            text(c, format!("time trial: {}", mode), Color::WHITE);
+            button(c, "toggle", MenuButton::ToggleTimeTrial);
+        });
+        row(c, &|c| {
+            let code = if settings.code.is_empty() {
+                "none"
+            } else {
+                &settings.code
+            };
+            text(
+                c,
+                format!("code: {}{}", code, caret(settings, MenuField::Code)),
+                Color::WHITE,
+            );
+            if settings.editing != MenuField::Code {
+                button(c, "edit", MenuButton::Edit(MenuField::Code));
+            }
+            button(c, "paste", MenuButton::PasteCode);
+            button(c, "clear", MenuButton::ClearCode);
+            match settings.code_level() {
+                Some(Ok(_)) => button(c, "Load code", MenuButton::LoadCode),
+                Some(Err(error)) => text(c, error.to_string(), Color::TOMATO),
+                None => {}
+            }
+        });
+        match settings.load_level() {
+            // A shape over the limits is already explained under its lengths.
+            Ok(_) if estimate.is_err() => {}
+            Ok(_) => button(c, "Start", MenuButton::Start),
+            Err(error) => text(c, error.to_string(), Color::TOMATO),
+        }
+    });
+}
+
+/// What generating a maze of `lengths` will take, shown as the lengths are adjusted, or the
+/// error the load would be refused with, which suggests a shape within `limits`.
+fn load_estimate(lengths: &[u16], limits: &LoadLimits) -> Result<String, String> {
+    limits.check(lengths).map_err(|error| error.to_string())?;
+    Ok(format!("estimate: {}", LoadEstimate::new(lengths)))
+}
+
+/// The campaign button's label, or `None` in free play without a campaign.
+fn campaign_label(campaign: &Campaign) -> Option<String> {
+    if campaign.is_empty() {
+        None
+    } else if campaign.is_finished() {
+        Some("Play the campaign again".to_string())
+    } else {
+        Some(format!(
+            "Campaign level {} of {}",
+            campaign.progress.current + 1,
+            campaign.len()
+        ))
+    }
+}
+
+/// Marks the field being typed into.
+fn caret(settings: &MenuSettings, field: MenuField) -> &'static str {
+    if settings.editing == field {
+        "_"
+    } else {
+        ""
+    }
+}
+
+fn menu_buttons(
+    mut settings: ResMut<MenuSettings>,
+    mut load_level: EventWriter<LoadLevel>,
+    mut continue_game: EventWriter<ContinueGame>,
+    mut start_campaign: EventWriter<StartCampaign>,
+    mut toasts: EventWriter<ShowToast>,
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match *button {
+            MenuButton::Preset(index) => {
+                if let Some(preset) = Difficulty::PRESETS.get(index) {
+                    settings.select_preset(preset.clone());
+                }
+            }
+            MenuButton::FewerDimensions => {
+                let count = settings.lengths.len().saturating_sub(1);
+                settings.set_dimension_count(count);
+            }
+            MenuButton::MoreDimensions => {
+                let count = settings.lengths.len() + 1;
+                settings.set_dimension_count(count);
+            }
+            MenuButton::Shorter(axis) => {
+                settings.preset = None;
+                if let Some(length) = settings.lengths.get_mut(axis) {
+                    *length = length.saturating_sub(1);
+                }
+            }
+            MenuButton::Longer(axis) => {
+                settings.preset = None;
+                if let Some(length) = settings.lengths.get_mut(axis) {
+                    *length = length.saturating_add(1);
+                }
+            }
+            MenuButton::RandomizeSeed => settings.seed = random::<u64>().to_string(),
+            MenuButton::ClearSeed => settings.seed.clear(),
+            MenuButton::Edit(field) => settings.editing = field,
+            MenuButton::ToggleTimeTrial => settings.time_trial = !settings.time_trial,
+            MenuButton::PasteCode => match clipboard::paste() {
+                Ok(pasted) => {
+                    settings.code.clear();
+                    settings.editing = MenuField::Code;
+                    for typed in pasted.trim().chars() {
+                        settings.type_char(typed);
+                    }
+                }
+                Err(e) => {
+                    warn!("Could not paste a level code: {}", e);
+                    toasts.send(ShowToast::new(
+                        ToastKind::Warn,
+                        "No clipboard to paste from, type the code instead",
+                    ));
+                }
+            },
+            MenuButton::ClearCode => settings.code.clear(),
+            MenuButton::LoadCode => match settings.code_level() {
+                Some(Ok(level)) => load_level.send(level),
+                Some(Err(error)) => warn!("Not loading the level code: {}", error),
+                None => {}
+            },
+            MenuButton::Start => match settings.load_level() {
+                Ok(level) => load_level.send(level),
+                Err(error) => warn!("Not starting the maze: {}", error),
+            },
+            MenuButton::Continue => continue_game.send(ContinueGame),
+            MenuButton::Campaign => start_campaign.send(StartCampaign),
+        }
+    }
+}
+
+/// Typing edits the seed or level code, and backspace removes the last character.
+fn type_text(
+    mut settings: ResMut<MenuSettings>,
+    mut characters: EventReader<ReceivedCharacter>,
+    keys: Res<Input<KeyCode>>,
+) {
+    for typed in characters.iter() {
+        settings.type_char(typed.char);
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        settings.backspace();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_build_load_requests() {
+        let settings = MenuSettings {
+            lengths: vec![3, 4, 5, 6],
+            seed: "42".to_string(),
+            ..Default::default()
+        };
+        let level = settings.load_level().unwrap();
+        assert!(matches!(
+            level.dimensions,
+            DimensionLength::Four([3, 4, 5, 6])
+        ));
+        assert!(matches!(level.rng_source, RngSource::Seeded(42)));
+        assert!(!level.time_trial);
+
+        let level = MenuSettings::default().load_level().unwrap();
+        assert!(matches!(level.rng_source, RngSource::Entropy));
+    }
+
+    #[test]
+    fn time_trial_carries_into_every_load() {
+        let mut settings = MenuSettings {
+            time_trial: true,
+            ..Default::default()
+        };
+        assert!(settings.load_level().unwrap().time_trial);
+        settings.code = LevelCode::encode(&LoadLevel::default());
+        assert!(settings.code_level().unwrap().unwrap().time_trial);
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        let settings = |lengths: &[u16], seed: &str| MenuSettings {
+            lengths: lengths.to_vec(),
+            seed: seed.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            settings(&[4, 0, 2], "").load_level().err(),
+            Some(MenuError::ZeroLength { axis: 1 })
+        );
+        assert_eq!(
+            settings(&[4], "").load_level().err(),
+            Some(MenuError::DimensionCount(1))
+        );
+        assert_eq!(
+            settings(&[2; 7], "").load_level().err(),
+            Some(MenuError::DimensionCount(7))
+        );
+        assert_eq!(
+            settings(&[4, 4], "99999999999999999999").load_level().err(),
+            Some(MenuError::InvalidSeed)
+        );
+    }
+
+    #[test]
+    fn dimension_count_is_clamped() {
+        let mut settings = MenuSettings::default();
+        settings.set_dimension_count(1);
+        assert_eq!(settings.lengths, [4, 15]);
+        settings.set_dimension_count(9);
+        assert_eq!(settings.lengths, [4, 15, 4, 4, 4, 4]);
+    }
+
+    #[test]
+    fn typing_goes_to_the_field_being_edited() {
+        let mut settings = MenuSettings::default();
+        for typed in "4a2".chars() {
+            settings.type_char(typed);
+        }
+        assert_eq!(settings.seed, "42");
+
+        settings.editing = MenuField::Code;
+        for typed in "ab-1!".chars() {
+            settings.type_char(typed);
+        }
+        settings.backspace();
+        assert_eq!(settings.code, "AB-");
+        assert_eq!(settings.seed, "42");
+        assert!(matches!(settings.code_level(), Some(Err(_))));
+    }
+
+    #[test]
+    fn a_typed_code_loads_its_level() {
+        let shared = LoadLevel {
+            rng_source: RngSource::Seeded(99),
+            dimensions: DimensionLength::Five([2, 3, 4, 5, 6]),
+            ..Default::default()
+        };
+        let mut settings = MenuSettings {
+            editing: MenuField::Code,
+            ..Default::default()
+        };
+        assert!(settings.code_level().is_none());
+        for typed in LevelCode::encode(&shared).to_lowercase().chars() {
+            settings.type_char(typed);
+        }
+        let level = settings.code_level().unwrap().unwrap();
+        assert!(matches!(
+            level.dimensions,
+            DimensionLength::Five([2, 3, 4, 5, 6])
+        ));
+        assert!(matches!(level.rng_source, RngSource::Seeded(99)));
+    }
+
+    #[test]
+    fn the_estimate_suggests_a_shape_that_fits() {
+        let limits = LoadLimits {
+            max_cells: 100,
+            max_bytes: u64::MAX,
+        };
+        assert_eq!(
+            load_estimate(&[4, 4], &limits),
+            Ok("estimate: ~16 cells, ~1 KB, ~26 µs".to_string())
+        );
+        let error = load_estimate(&[30, 5], &limits).unwrap_err();
+        assert!(error.contains("too large"));
+        assert!(error.ends_with("try [20, 5]"));
+    }
+
+    #[test]
+    fn presets_fill_in_lengths_until_edited() {
+        let mut settings = MenuSettings::default();
+        settings.select_preset(Difficulty::Hard);
+        assert_eq!(settings.lengths, [5, 5, 4, 3]);
+        let level = settings.load_level().unwrap();
+        assert!(matches!(level.difficulty, Some(Difficulty::Hard)));
+
+        settings.set_dimension_count(3);
+        let level = settings.load_level().unwrap();
+        assert!(matches!(
+            level.difficulty,
+            Some(Difficulty::Custom(DimensionLength::Three([5, 5, 4])))
+        ));
+    }
+}
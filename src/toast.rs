@@ -0,0 +1,305 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::prelude::*;
+
+/// Fade in/out time at either end of a toast's lifetime.
+const FADE: Duration = Duration::from_millis(250);
+
+/// Request to briefly show a message in the corner of the screen.
+#[derive(Clone, Debug)]
+pub struct ShowToast {
+    pub text: String,
+    pub kind: ToastKind,
+    pub duration: Duration,
+}
+
+impl ShowToast {
+    pub fn new(kind: ToastKind, text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            kind,
+            duration: Duration::from_secs(5),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToastKind {
+    Info,
+    Warn,
+    Error,
+}
+
+impl ToastKind {
+    fn color(&self) -> Color {
+        match self {
+            ToastKind::Info => Color::WHITE,
+            ToastKind::Warn => Color::GOLD,
+            ToastKind::Error => Color::TOMATO,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Toast {
+    pub id: u64,
+    pub text: String,
+    pub kind: ToastKind,
+    /// How many identical requests were collapsed into this toast.
+    pub count: u32,
+    shown_at: Duration,
+    expires_at: Duration,
+}
+
+impl Toast {
+    /// Opacity at `now`, ramping up after it is shown and down before it expires.
+    pub fn alpha(&self, now: Duration) -> f32 {
+        let fade = FADE.as_secs_f32();
+        let since_shown = now.saturating_sub(self.shown_at).as_secs_f32();
+        let until_expiry = self.expires_at.saturating_sub(now).as_secs_f32();
+        (since_shown / fade)
+            .min(until_expiry / fade)
+            .clamp(0.0, 1.0)
+    }
+}
+
+/// The visible toasts, oldest first.
+pub struct ToastQueue {
+    toasts: VecDeque<Toast>,
+    next_id: u64,
+    capacity: usize,
+    dedup_window: Duration,
+}
+
+impl Default for ToastQueue {
+    fn default() -> Self {
+        Self::new(4, Duration::from_secs(2))
+    }
+}
+
+impl ToastQueue {
+    pub fn new(capacity: usize, dedup_window: Duration) -> Self {
+        Self {
+            toasts: VecDeque::with_capacity(capacity),
+            next_id: 0,
+            capacity,
+            dedup_window,
+        }
+    }
+
+    /// Shows a toast, collapsing it into an identical one shown within the dedup window,
+    /// and dropping the oldest toast if the queue is full.
+    pub fn push(&mut self, now: Duration, request: &ShowToast) {
+        let dedup_window = self.dedup_window;
+        if let Some(existing) = self.toasts.iter_mut().find(|t| {
+            t.kind == request.kind
+                && t.text == request.text
+                && now.saturating_sub(t.shown_at) <= dedup_window
+        }) {
+            existing.count += 1;
+            existing.expires_at = existing.expires_at.max(now + request.duration);
+            return;
+        }
+
+        if self.toasts.len() >= self.capacity {
+            self.toasts.pop_front();
+        }
+        self.toasts.push_back(Toast {
+            id: self.next_id,
+            text: request.text.clone(),
+            kind: request.kind,
+            count: 1,
+            shown_at: now,
+            expires_at: now + request.duration,
+        });
+        self.next_id += 1;
+    }
+
+    /// Removes every toast that has expired by `now`.
+    pub fn expire(&mut self, now: Duration) {
+        self.toasts.retain(|t| t.expires_at > now);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Toast> {
+        self.toasts.iter()
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Toast> {
+        self.toasts.iter().find(|t| t.id == id)
+    }
+}
+
+pub struct ToastPlugin;
+
+impl Plugin for ToastPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ToastQueue>()
+            .add_event::<ShowToast>()
+            .add_startup_system(spawn_toast_stack)
+            .add_system(queue_toasts)
+            .add_system(update_toast_stack.after(queue_toasts));
+    }
+}
+
+#[derive(Component)]
+struct ToastStack;
+
+#[derive(Component)]
+struct ToastEntry {
+    id: u64,
+}
+
+fn spawn_toast_stack(mut c: Commands) {
+    c.spawn_bundle(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                right: Val::Px(10.0),
+                bottom: Val::Px(10.0),
+                ..default()
+            },
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::FlexEnd,
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    })
+    .insert(ToastStack);
+}
+
+fn queue_toasts(
+    time: Res<Time>,
+    mut queue: ResMut<ToastQueue>,
+    mut requests: EventReader<ShowToast>,
+) {
+    let now = time.time_since_startup();
+    for request in requests.iter() {
+        queue.push(now, request);
+    }
+    queue.expire(now);
+}
+
+fn update_toast_stack(
+    mut c: Commands,
+    time: Res<Time>,
+    queue: Res<ToastQueue>,
+    assets: Res<AssetServer>,
+    stack: Query<Entity, With<ToastStack>>,
+    mut entries: Query<(Entity, &ToastEntry, &mut Text)>,
+) {
+    let now = time.time_since_startup();
+
+    for (entity, entry, mut text) in entries.iter_mut() {
+        if let Some(toast) = queue.get(entry.id) {
+            if let Some(section) = text.sections.first_mut() {
+                section.value = toast_label(toast);
+                section.style.color = toast.kind.color();
+                section.style.color.set_a(toast.alpha(now));
+            }
+        } else {
+            c.entity(entity).despawn_recursive();
+        }
+    }
+
+    let stack = match stack.get_single() {
+        Ok(stack) => stack,
+        Err(_) => return,
+    };
+    for toast in queue.iter() {
+        if entries.iter().any(|(_, entry, _)| entry.id == toast.id) {
+            continue;
+        }
+        let mut color = toast.kind.color();
+        color.set_a(toast.alpha(now));
+        let entry = c
+            .spawn_bundle(TextBundle {
+                text: Text::with_section(
+                    toast_label(toast),
+                    TextStyle {
+                        font: assets.load("fonts/UnicaOne-Regular.ttf"),
+                        font_size: 24.0,
+                        color,
+                    },
+                    Default::default(),
+                ),
+                ..default()
+            })
+            .insert(ToastEntry { id: toast.id })
+            .id();
+        c.entity(stack).add_child(entry);
+    }
+}
+
+fn toast_label(toast: &Toast) -> String {
+    if toast.count > 1 {
+        format!("{} (x{})", toast.text, toast.count)
+    } else {
+        toast.text.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(text: &str) -> ShowToast {
+        ShowToast {
+            text: text.into(),
+            kind: ToastKind::Info,
+            duration: Duration::from_secs(3),
+        }
+    }
+
+    #[test]
+    fn queue_drops_oldest_when_full() {
+        let mut queue = ToastQueue::new(2, Duration::ZERO);
+        queue.push(Duration::ZERO, &info("a"));
+        queue.push(Duration::ZERO, &info("b"));
+        queue.push(Duration::ZERO, &info("c"));
+        let texts: Vec<_> = queue.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, ["b", "c"]);
+    }
+
+    #[test]
+    fn queue_collapses_duplicates_in_window() {
+        let mut queue = ToastQueue::new(4, Duration::from_secs(2));
+        queue.push(Duration::ZERO, &info("saved"));
+        queue.push(Duration::from_secs(1), &info("saved"));
+        assert_eq!(queue.iter().count(), 1);
+        assert_eq!(queue.iter().next().unwrap().count, 2);
+
+        queue.push(Duration::from_secs(5), &info("saved"));
+        assert_eq!(queue.iter().count(), 2);
+
+        queue.push(
+            Duration::from_secs(5),
+            &ShowToast {
+                kind: ToastKind::Error,
+                ..info("saved")
+            },
+        );
+        assert_eq!(queue.iter().count(), 3);
+    }
+
+    #[test]
+    fn queue_expires_toasts() {
+        let mut queue = ToastQueue::default();
+        queue.push(Duration::ZERO, &info("a"));
+        queue.push(Duration::from_secs(2), &info("b"));
+        queue.expire(Duration::from_secs(3));
+        let texts: Vec<_> = queue.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, ["b"]);
+    }
+
+    #[test]
+    fn toast_fades_in_and_out() {
+        let mut queue = ToastQueue::default();
+        queue.push(Duration::ZERO, &info("a"));
+        let toast = queue.iter().next().unwrap();
+        assert_eq!(toast.alpha(Duration::ZERO), 0.0);
+        assert_eq!(toast.alpha(Duration::from_secs(1)), 1.0);
+        assert!((toast.alpha(Duration::from_millis(2875)) - 0.5).abs() < 1e-4);
+        assert_eq!(toast.alpha(Duration::from_secs(4)), 0.0);
+    }
+}